@@ -0,0 +1,284 @@
+use crate::{
+    config,
+    identity::is_path_key,
+    types::{BookId, Location, RgbColor},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightColor {
+    #[default]
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+}
+
+impl HighlightColor {
+    pub const ALL: [HighlightColor; 4] = [
+        HighlightColor::Yellow,
+        HighlightColor::Green,
+        HighlightColor::Blue,
+        HighlightColor::Pink,
+    ];
+
+    pub fn rgb(self) -> RgbColor {
+        match self {
+            HighlightColor::Yellow => RgbColor::new(0x80, 0x6b, 0x1c),
+            HighlightColor::Green => RgbColor::new(0x2e, 0x6b, 0x3a),
+            HighlightColor::Blue => RgbColor::new(0x2c, 0x4f, 0x7c),
+            HighlightColor::Pink => RgbColor::new(0x7c, 0x2c, 0x5a),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HighlightColor::Yellow => "yellow",
+            HighlightColor::Green => "green",
+            HighlightColor::Blue => "blue",
+            HighlightColor::Pink => "pink",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|color| color.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The following color in the palette, or None after the last one.
+    pub fn next(self) -> Option<Self> {
+        let idx = Self::ALL.iter().position(|c| *c == self)?;
+        Self::ALL.get(idx + 1).copied()
+    }
+}
+
+/// A highlighted range of text with an optional note. Both ends are
+/// content-anchored locations, so the range survives reflow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    start: Location,
+    end: Location,
+    #[serde(default)]
+    color: HighlightColor,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(default)]
+    text: String,
+    created_at: String,
+}
+
+impl Annotation {
+    pub fn new(
+        start: Location,
+        end: Location,
+        color: HighlightColor,
+        text: impl Into<String>,
+        created_at: impl Into<String>,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            color,
+            note: None,
+            text: text.into(),
+            created_at: created_at.into(),
+        }
+    }
+
+    pub fn start(&self) -> &Location {
+        &self.start
+    }
+
+    pub fn end(&self) -> &Location {
+        &self.end
+    }
+
+    pub fn color(&self) -> HighlightColor {
+        self.color
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn created_at(&self) -> &str {
+        &self.created_at
+    }
+
+    pub fn set_color(&mut self, color: HighlightColor) {
+        self.color = color;
+    }
+
+    pub fn set_note(&mut self, note: Option<String>) {
+        self.note = note.filter(|n| !n.trim().is_empty());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BookAnnotations {
+    book: BookId,
+    annotations: Vec<Annotation>,
+}
+
+fn annotation_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(primary) = config::config_root() {
+        paths.push(primary.join("annotations.json"));
+    }
+    for legacy in config::legacy_config_roots() {
+        paths.push(legacy.join("annotations.json"));
+    }
+    paths
+}
+
+pub fn load_annotations(book: &BookId) -> Vec<Annotation> {
+    for path in annotation_paths() {
+        if let Some(annotations) = load_annotations_from(&path, book) {
+            return annotations;
+        }
+    }
+    Vec::new()
+}
+
+pub fn save_annotations(book: &BookId, annotations: &[Annotation]) -> std::io::Result<()> {
+    let dir = config::config_root()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config dir"))?;
+    fs::create_dir_all(&dir)?;
+    save_annotations_to(&dir.join("annotations.json"), book, annotations)
+}
+
+fn read_records(path: &Path) -> Vec<BookAnnotations> {
+    fs::read(path)
+        .ok()
+        .and_then(|d| serde_json::from_slice(&d).ok())
+        .unwrap_or_default()
+}
+
+// Match by id, falling back to the path for records without a stable id
+fn find_record(records: &[BookAnnotations], book: &BookId) -> Option<usize> {
+    records
+        .iter()
+        .position(|rec| rec.book.id() == book.id())
+        .or_else(|| {
+            records
+                .iter()
+                .position(|rec| is_path_key(rec.book.id()) && rec.book.path() == book.path())
+        })
+}
+
+pub(crate) fn load_annotations_from(path: &Path, book: &BookId) -> Option<Vec<Annotation>> {
+    let mut records = read_records(path);
+    let idx = find_record(&records, book)?;
+    Some(records.swap_remove(idx).annotations)
+}
+
+/// Give records a new identity where `resolve` returns one.
+pub(crate) fn migrate_book_ids(resolve: &dyn Fn(&BookId) -> Option<BookId>) -> std::io::Result<()> {
+    for path in annotation_paths() {
+        let mut records = read_records(&path);
+        let mut changed = false;
+        for rec in &mut records {
+            if let Some(book) = resolve(&rec.book) {
+                rec.book = book;
+                changed = true;
+            }
+        }
+        if changed {
+            write_records(&path, &records)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_records(path: &Path, records: &[BookAnnotations]) -> std::io::Result<()> {
+    let mut f = fs::File::create(path)?;
+    let s = serde_json::to_string_pretty(records).unwrap_or_else(|_| "[]".into());
+    f.write_all(s.as_bytes())
+}
+
+pub(crate) fn save_annotations_to(
+    path: &Path,
+    book: &BookId,
+    annotations: &[Annotation],
+) -> std::io::Result<()> {
+    let mut records = read_records(path);
+    if let Some(idx) = find_record(&records, book) {
+        records.remove(idx);
+    }
+    if !annotations.is_empty() {
+        records.push(BookAnnotations {
+            book: book.clone(),
+            annotations: annotations.to_vec(),
+        });
+    }
+    write_records(path, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DocumentFormat;
+
+    fn book(id: &str, path: &str) -> BookId {
+        BookId::new(id, path, None, DocumentFormat::Epub3)
+    }
+
+    fn highlight(block: usize, note: Option<&str>) -> Annotation {
+        let mut ann = Annotation::new(
+            Location::anchored(0, Some("ch1.xhtml".into()), block, 0, 0),
+            Location::anchored(0, Some("ch1.xhtml".into()), block, 12, 0),
+            HighlightColor::Green,
+            "Hello there",
+            "2024-01-01T00:00:00+00:00",
+        );
+        ann.set_note(note.map(str::to_string));
+        ann
+    }
+
+    #[test]
+    fn annotations_round_trip_per_book() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("annotations.json");
+        let first = book("a", "/books/a.epub");
+        let second = book("b", "/books/b.epub");
+        save_annotations_to(&path, &first, &[highlight(3, Some("remember"))]).unwrap();
+        save_annotations_to(&path, &second, &[highlight(7, None)]).unwrap();
+
+        let loaded = load_annotations_from(&path, &first).unwrap();
+        assert_eq!(loaded, vec![highlight(3, Some("remember"))]);
+        let loaded = load_annotations_from(&path, &second).unwrap();
+        assert_eq!(loaded[0].start().block_index(), Some(7));
+        assert_eq!(loaded[0].note(), None);
+    }
+
+    #[test]
+    fn saving_empty_list_drops_the_book() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("annotations.json");
+        let first = book("a", "/books/a.epub");
+        save_annotations_to(&path, &first, &[highlight(1, None)]).unwrap();
+        save_annotations_to(&path, &first, &[]).unwrap();
+        assert!(load_annotations_from(&path, &first).is_none());
+    }
+
+    #[test]
+    fn colors_cycle_and_end() {
+        assert_eq!(HighlightColor::Yellow.next(), Some(HighlightColor::Green));
+        assert_eq!(HighlightColor::Pink.next(), None);
+        assert_eq!(
+            HighlightColor::from_name("Blue"),
+            Some(HighlightColor::Blue)
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Comic book archives (`.cbz`): a zip of page images, read in file name
+//! order, with optional `ComicInfo.xml` metadata. Each image becomes a
+//! page-sized `ImageBlock`.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use quick_xml::escape::unescape;
+use quick_xml::events::Event;
+use quick_xml::Reader as XmlReader;
+use thiserror::Error;
+use zip::ZipArchive;
+
+use crate::identity::book_key_for_bytes;
+use crate::text::title_from_path;
+use crate::types::{
+    Block, BookMetadata, CreatorEntry, Document, DocumentFormat, DocumentInfo, ImageBlock,
+    SeriesInfo, TitleEntry, TitleKind,
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+#[derive(Debug, Error)]
+pub enum CbzError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("no page images in archive")]
+    Empty,
+}
+
+/// Whether `path` names a comic book zip.
+pub fn is_cbz_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cbz"))
+}
+
+struct PageImage {
+    name: String,
+    data: Vec<u8>,
+    size: Option<(u32, u32)>,
+}
+
+pub struct CbzBook {
+    pub path: PathBuf,
+    pub metadata: BookMetadata,
+    id: String,
+    pages: Vec<PageImage>,
+}
+
+impl CbzBook {
+    pub fn open(path: &Path) -> Result<Self, CbzError> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(path, &data)
+    }
+
+    /// The `ComicInfo.xml` metadata and the number of pages, read without
+    /// decompressing the page images.
+    pub fn read_metadata(path: &Path) -> Result<(BookMetadata, usize), CbzError> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let page_count = archive
+            .file_names()
+            .filter(|name| is_page_name(name))
+            .count();
+        if page_count == 0 {
+            return Err(CbzError::Empty);
+        }
+        Ok((read_comic_info(&mut archive)?, page_count))
+    }
+
+    fn from_bytes(path: &Path, data: &[u8]) -> Result<Self, CbzError> {
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+        let mut names: Vec<String> = archive
+            .file_names()
+            .filter(|name| is_page_name(name))
+            .map(str::to_string)
+            .collect();
+        if names.is_empty() {
+            return Err(CbzError::Empty);
+        }
+        names.sort_by(|a, b| natural_cmp(a, b));
+        let mut pages = Vec::with_capacity(names.len());
+        for name in names {
+            let mut file = archive.by_name(&name)?;
+            let mut bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut bytes)?;
+            pages.push(PageImage {
+                size: image_size(&bytes),
+                name,
+                data: bytes,
+            });
+        }
+        let metadata = read_comic_info(&mut archive)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            id: book_key_for_bytes(data, metadata.identifier()),
+            metadata,
+            pages,
+        })
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The pages as a document; the image data moves into it.
+    pub fn into_document(self) -> Document {
+        let blocks = self
+            .pages
+            .into_iter()
+            .map(|page| {
+                let (width, height) = page.size.unzip();
+                Block::Image(ImageBlock::full_page(page.name, page.data, width, height))
+            })
+            .collect();
+        let title = self
+            .metadata
+            .main_title()
+            .map(str::to_string)
+            .or_else(|| title_from_path(&self.path))
+            .unwrap_or_else(|| "Untitled".to_string());
+        let path = self.path.to_string_lossy().into_owned();
+        let info = DocumentInfo::new(
+            self.id,
+            path.clone(),
+            Some(title.clone()),
+            None,
+            self.metadata.author_string(),
+            Some(self.metadata),
+            DocumentFormat::Cbz,
+        );
+        Document::new(info, blocks, vec![title], vec![path], Vec::new())
+    }
+}
+
+// Page images by archive path, skipping directories and the resource
+// forks and hidden files that zip tools leave behind
+fn is_page_name(name: &str) -> bool {
+    let base = name.rsplit('/').next().unwrap_or(name);
+    !base.starts_with('.') && !name.starts_with("__MACOSX/") && is_image_name(base)
+}
+
+fn read_comic_info<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<BookMetadata, CbzError> {
+    let name = archive
+        .file_names()
+        .find(|name| {
+            name.rsplit('/')
+                .next()
+                .is_some_and(|base| base.eq_ignore_ascii_case("ComicInfo.xml"))
+        })
+        .map(str::to_string);
+    let Some(name) = name else {
+        return Ok(BookMetadata::default());
+    };
+    let mut xml = String::new();
+    archive.by_name(&name)?.read_to_string(&mut xml)?;
+    Ok(parse_comic_info(&xml))
+}
+
+fn is_image_name(name: &str) -> bool {
+    name.rsplit_once('.').is_some_and(|(_, ext)| {
+        IMAGE_EXTENSIONS
+            .iter()
+            .any(|known| ext.eq_ignore_ascii_case(known))
+    })
+}
+
+/// Compare file names so that `page2` sorts before `page10`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let is_digit = |c: char| c.is_ascii_digit();
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_end = a.find(|c: char| !is_digit(c)).unwrap_or(a.len());
+            let b_end = b.find(|c: char| !is_digit(c)).unwrap_or(b.len());
+            let a_num = a[..a_end].trim_start_matches('0');
+            let b_num = b[..b_end].trim_start_matches('0');
+            let order = a_num.len().cmp(&b_num.len()).then(a_num.cmp(b_num));
+            if order != Ordering::Equal {
+                return order;
+            }
+            a = &a[a_end..];
+            b = &b[b_end..];
+        } else {
+            let order = x.to_ascii_lowercase().cmp(&y.to_ascii_lowercase());
+            if order != Ordering::Equal {
+                return order;
+            }
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+        }
+    }
+}
+
+/// Pixel width and height from a PNG, JPEG, GIF, WebP or BMP header.
+fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| {
+        Some(u32::from(u16::from_be_bytes(
+            data.get(at..at + 2)?.try_into().ok()?,
+        )))
+    };
+    let le16 = |at: usize| {
+        Some(u32::from(u16::from_le_bytes(
+            data.get(at..at + 2)?.try_into().ok()?,
+        )))
+    };
+    let le24 = |at: usize| {
+        let b = data.get(at..at + 3)?;
+        Some(u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16)
+    };
+    let le32 = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let be32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+
+    let size = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        (be32(16)?, be32(20)?)
+    } else if data.starts_with(b"GIF8") {
+        (le16(6)?, le16(8)?)
+    } else if data.starts_with(b"BM") {
+        (le32(18)?, (le32(22)? as i32).unsigned_abs())
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        match data.get(12..16)? {
+            b"VP8 " => (le16(26)? & 0x3fff, le16(28)? & 0x3fff),
+            b"VP8L" => {
+                let bits = le32(21)?;
+                ((bits & 0x3fff) + 1, (bits >> 14 & 0x3fff) + 1)
+            }
+            b"VP8X" => (le24(24)? + 1, le24(27)? + 1),
+            _ => return None,
+        }
+    } else if data.starts_with(&[0xff, 0xd8]) {
+        // Walk the segments up to the frame header
+        let mut at = 2;
+        loop {
+            if *data.get(at)? != 0xff {
+                return None;
+            }
+            let marker = *data.get(at + 1)?;
+            match marker {
+                0xff => at += 1,
+                0x01 | 0xd0..=0xd9 => at += 2,
+                0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                    break (be16(at + 7)?, be16(at + 5)?);
+                }
+                _ => at += 2 + be16(at + 2)? as usize,
+            }
+        }
+    } else {
+        return None;
+    };
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// Title, creators, series, language and page order from `ComicInfo.xml`.
+fn parse_comic_info(xml: &str) -> BookMetadata {
+    let mut fields: HashMap<String, String> = HashMap::new();
+    let mut reader = XmlReader::from_str(xml);
+    let mut depth = 0usize;
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                depth += 1;
+                current = (depth == 2)
+                    .then(|| String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+            }
+            Ok(Event::End(_)) => {
+                depth = depth.saturating_sub(1);
+                current = None;
+            }
+            Ok(Event::Text(t)) => {
+                if let (Some(name), Ok(text)) = (&current, t.decode()) {
+                    fields.entry(name.clone()).or_default().push_str(&text);
+                }
+            }
+            Ok(Event::GeneralRef(r)) => {
+                if let (Some(name), Ok(entity)) = (&current, r.decode()) {
+                    let raw = format!("&{};", entity);
+                    if let Ok(text) = unescape(&raw) {
+                        fields.entry(name.clone()).or_default().push_str(&text);
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+
+    let number = field("Number");
+    let series =
+        field("Series").map(|name| SeriesInfo::new(name, number.and_then(|n| n.parse().ok())));
+    let title = field("Title").map(str::to_string).or_else(|| {
+        let series = field("Series")?;
+        Some(match number {
+            Some(number) => format!("{} #{}", series, number),
+            None => series.to_string(),
+        })
+    });
+    let titles = title
+        .map(|title| vec![TitleEntry::new(title, TitleKind::Main)])
+        .unwrap_or_default();
+    let mut creators = Vec::new();
+    for (name, role) in [("Writer", "aut"), ("Penciller", "ill"), ("Artist", "ill")] {
+        for person in field(name).into_iter().flat_map(|names| names.split(',')) {
+            let person = person.trim();
+            if !person.is_empty() {
+                creators.push(CreatorEntry::new(person, vec![role.to_string()]));
+            }
+        }
+    }
+    let mut metadata = BookMetadata::new(titles, creators, series);
+    metadata.set_language(field("LanguageISO").map(str::to_string));
+    metadata.set_identifier(field("GTIN").map(str::to_string));
+    metadata.set_right_to_left(field("Manga") == Some("YesAndRightToLeft"));
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    fn archive(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    const COMIC_INFO: &str = r#"<?xml version="1.0"?>
+<ComicInfo>
+  <Series>Night &amp; Day</Series>
+  <Number>3</Number>
+  <Writer>Ann Writer, Bo Writer</Writer>
+  <Penciller>Cy Artist</Penciller>
+  <LanguageISO>ja</LanguageISO>
+  <Manga>YesAndRightToLeft</Manga>
+  <Pages><Page Image="0"/></Pages>
+</ComicInfo>"#;
+
+    #[test]
+    fn pages_are_full_page_images_in_natural_order() {
+        let data = archive(&[
+            ("vol/page10.png", png(800, 1200)),
+            ("vol/page2.png", png(800, 1200)),
+            ("vol/page1.png", png(1600, 1200)),
+            ("__MACOSX/vol/._page1.png", vec![0; 4]),
+            ("vol/notes.txt", b"not a page".to_vec()),
+            ("ComicInfo.xml", COMIC_INFO.as_bytes().to_vec()),
+        ]);
+        let book = CbzBook::from_bytes(Path::new("night.cbz"), &data).unwrap();
+        assert_eq!(book.page_count(), 3);
+        let document = book.into_document();
+        let pages: Vec<(&str, Option<u32>, bool)> = document
+            .blocks()
+            .iter()
+            .filter_map(|block| match block {
+                Block::Image(image) => Some((image.id(), image.width(), image.is_page())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            pages,
+            vec![
+                ("vol/page1.png", Some(1600), true),
+                ("vol/page2.png", Some(800), true),
+                ("vol/page10.png", Some(800), true),
+            ]
+        );
+        assert_eq!(document.info().title(), Some("Night & Day #3"));
+        assert_eq!(document.info().format(), DocumentFormat::Cbz);
+    }
+
+    #[test]
+    fn catalog_metadata_and_id_match_the_opened_book() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = COMIC_INFO.replace("<Number>", "<GTIN>9781234567897</GTIN><Number>");
+        for (name, info) in [("gtin.cbz", info.as_str()), ("plain.cbz", "<ComicInfo/>")] {
+            let path = dir.path().join(name);
+            std::fs::write(
+                &path,
+                archive(&[
+                    ("1.png", png(10, 10)),
+                    ("2.png", png(10, 10)),
+                    ("ComicInfo.xml", info.as_bytes().to_vec()),
+                ]),
+            )
+            .unwrap();
+            let (metadata, page_count) = CbzBook::read_metadata(&path).unwrap();
+            assert_eq!(page_count, 2);
+            let key = crate::identity::book_key(&path, metadata.identifier()).unwrap();
+            let document = CbzBook::open(&path).unwrap().into_document();
+            assert_eq!(document.info().id(), key);
+        }
+        let (metadata, _) = CbzBook::read_metadata(&dir.path().join("gtin.cbz")).unwrap();
+        assert_eq!(metadata.identifier(), Some("9781234567897"));
+    }
+
+    #[test]
+    fn comic_info_maps_series_creators_and_page_order() {
+        let meta = parse_comic_info(COMIC_INFO);
+        let series = meta.series().unwrap();
+        assert_eq!(series.name(), "Night & Day");
+        assert_eq!(series.index(), Some(3.0));
+        assert_eq!(
+            meta.author_string().as_deref(),
+            Some("Ann Writer, Bo Writer")
+        );
+        assert_eq!(meta.creators().len(), 3);
+        assert_eq!(meta.language(), Some("ja"));
+        assert!(meta.right_to_left());
+        assert!(!parse_comic_info("<ComicInfo><Manga>Yes</Manga></ComicInfo>").right_to_left());
+    }
+
+    #[test]
+    fn reads_image_sizes_and_rejects_empty_archives() {
+        assert_eq!(image_size(&png(10, 20)), Some((10, 20)));
+        let gif = b"GIF89a\x0a\x00\x14\x00".to_vec();
+        assert_eq!(image_size(&gif), Some((10, 20)));
+        // SOI, an APP0 segment, then a baseline frame header
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x11, 0x08, 0x00,
+            0x14, 0x00, 0x0a,
+        ];
+        assert_eq!(image_size(&jpeg), Some((10, 20)));
+        assert_eq!(image_size(b"nope"), None);
+        let data = archive(&[("readme.txt", b"hi".to_vec())]);
+        assert!(matches!(
+            CbzBook::from_bytes(Path::new("x.cbz"), &data),
+            Err(CbzError::Empty)
+        ));
+        assert!(is_cbz_path(Path::new("/comics/Issue 1.CBZ")));
+    }
+}
@@ -5,3 +5,5 @@ mod opf;
 
 pub use book::{EpubBook, SpineItem};
 pub use error::ReaderError;
+
+pub(crate) use opf::parse_opf;
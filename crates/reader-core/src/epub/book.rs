@@ -15,6 +15,21 @@ use super::container::read_container;
 use super::error::ReaderError;
 use super::opf::read_opf;
 
+// Spine files with these in their name are front or back matter, skipped
+// unless the table of contents names them
+const SKIP_HINTS: [&str; 10] = [
+    "cover",
+    "nav",
+    "toc",
+    "title",
+    "front",
+    "copyright",
+    "acknowledg",
+    "glossary",
+    "colophon",
+    "dedication",
+];
+
 #[derive(Debug, Clone)]
 pub struct SpineItem {
     pub id: String,
@@ -103,6 +118,35 @@ impl EpubBook {
         &self.spine
     }
 
+    /// Whether the reader loads this spine item as a chapter. `labels` are
+    /// the table of contents titles from [`EpubBook::toc_labels`].
+    pub fn is_chapter_item(&self, item: &SpineItem, labels: &HashMap<String, String>) -> bool {
+        let href = item.href.to_ascii_lowercase();
+        let is_html = item
+            .media_type
+            .as_deref()
+            .map(|mt| mt.contains("xhtml") || mt.contains("html"))
+            .unwrap_or(true);
+        if !is_html || href.contains("nav") || href.contains("toc") {
+            return false;
+        }
+        let key = self
+            .opf_base()
+            .join(item.href.split('#').next().unwrap_or(&item.href))
+            .to_string_lossy()
+            .to_string();
+        labels.contains_key(&key) || !SKIP_HINTS.iter().any(|hint| href.contains(hint))
+    }
+
+    /// Number of spine items read as chapters, the unit saved locations count in.
+    pub fn chapter_count(&self) -> usize {
+        let labels = self.toc_labels().unwrap_or_default();
+        self.spine
+            .iter()
+            .filter(|item| self.is_chapter_item(item, &labels))
+            .count()
+    }
+
     pub fn opf_base(&self) -> PathBuf {
         self.rootfile
             .parent()
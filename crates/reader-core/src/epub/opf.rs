@@ -49,6 +49,9 @@ struct OpfParseState {
     meta_subtitle: Option<String>,
     calibre_series: Option<String>,
     calibre_series_index: Option<f32>,
+    language: Option<String>,
+    unique_identifier: Option<String>,
+    identifiers: Vec<(Option<String>, String)>,
 }
 
 impl OpfParseState {
@@ -67,6 +70,9 @@ impl OpfParseState {
             meta_subtitle: None,
             calibre_series: None,
             calibre_series_index: None,
+            language: None,
+            unique_identifier: None,
+            identifiers: Vec::new(),
         }
     }
 
@@ -158,7 +164,12 @@ pub(crate) fn read_opf(
     let mut opf = zip.by_name(opf_path.to_string_lossy().as_ref())?;
     let mut opf_xml = String::new();
     opf.read_to_string(&mut opf_xml)?;
-    let mut reader = XmlReader::from_str(&opf_xml);
+    parse_opf(&opf_xml)
+}
+
+/// Metadata, manifest, spine and NCX id from a package document.
+pub(crate) fn parse_opf(opf_xml: &str) -> Result<OpfResult, ReaderError> {
+    let mut reader = XmlReader::from_str(opf_xml);
     let mut state = OpfParseState::new();
     loop {
         match reader.read_event() {
@@ -218,7 +229,15 @@ pub(crate) fn read_opf(
         state.calibre_series_index,
         &state.collections,
     );
-    let metadata = BookMetadata::new(title_entries, creator_entries, series);
+    let mut metadata = BookMetadata::new(title_entries, creator_entries, series);
+    metadata.set_language(state.language);
+    let identifier = state
+        .identifiers
+        .iter()
+        .find(|(id, _)| id.is_some() && *id == state.unique_identifier)
+        .or_else(|| state.identifiers.first())
+        .map(|(_, value)| value.clone());
+    metadata.set_identifier(identifier);
     Ok((metadata, state.manifest, state.spine_ids, state.spine_toc))
 }
 
@@ -236,6 +255,20 @@ fn handle_opf_event(
         return Ok(());
     }
 
+    if local == "package" {
+        for a in e.attributes().flatten() {
+            let key = String::from_utf8_lossy(a.key.as_ref());
+            if local_name(&key) != "unique-identifier" {
+                continue;
+            }
+            let val = a
+                .unescape_value()
+                .map_err(|e| ReaderError::Parse(e.to_string()))?;
+            state.unique_identifier = Some(val.into_owned());
+        }
+        return Ok(());
+    }
+
     if local == "item" {
         let mut id: Option<String> = None;
         let mut href: Option<String> = None;
@@ -328,6 +361,41 @@ fn handle_opf_event(
         return Ok(());
     }
 
+    if local == "identifier" {
+        let mut id: Option<String> = None;
+        for a in e.attributes().flatten() {
+            let key = String::from_utf8_lossy(a.key.as_ref());
+            if local_name(&key) != "id" {
+                continue;
+            }
+            let val = a
+                .unescape_value()
+                .map_err(|e| ReaderError::Parse(e.to_string()))?;
+            id = Some(val.into_owned());
+        }
+        if !is_empty {
+            if let Some(text) = read_text_value(reader) {
+                let text = normalize_meta_text(&text);
+                if !text.is_empty() {
+                    state.identifiers.push((id, text));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if local == "language" {
+        if !is_empty && state.language.is_none() {
+            if let Some(text) = read_text_value(reader) {
+                let text = normalize_meta_text(&text);
+                if !text.is_empty() {
+                    state.language = Some(text);
+                }
+            }
+        }
+        return Ok(());
+    }
+
     if local == "creator" || local == "author" || local == "contributor" {
         let mut id: Option<String> = None;
         let mut roles: Vec<String> = Vec::new();
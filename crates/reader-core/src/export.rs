@@ -0,0 +1,353 @@
+use crate::{
+    annotations::Annotation,
+    types::{BookId, Location},
+};
+use chrono::DateTime;
+use serde::Serialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Annotations and bookmarks of one book, ready to be written out.
+/// `chapter_title` names the chapter a location falls in; exports group
+/// entries under those titles in reading order.
+pub struct BookExport<'a, F>
+where
+    F: Fn(&Location) -> String,
+{
+    book: &'a BookId,
+    annotations: &'a [Annotation],
+    bookmarks: &'a [Location],
+    chapter_title: F,
+}
+
+enum Entry<'a> {
+    Highlight(&'a Annotation),
+    Bookmark(&'a Location),
+}
+
+impl<'a> Entry<'a> {
+    fn location(&self) -> &'a Location {
+        match *self {
+            Entry::Highlight(ann) => ann.start(),
+            Entry::Bookmark(loc) => loc,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    book: JsonBook<'a>,
+    annotations: Vec<JsonAnnotation<'a>>,
+    bookmarks: Vec<JsonBookmark<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonBook<'a> {
+    id: &'a str,
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonAnnotation<'a> {
+    chapter: String,
+    start: JsonAnchor<'a>,
+    end: JsonAnchor<'a>,
+    color: &'static str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+    created_at: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonBookmark<'a> {
+    chapter: String,
+    anchor: JsonAnchor<'a>,
+}
+
+// Content anchor: chapter href plus chapter-relative block and character
+// offset. `page` is only meaningful at the width the location was saved at.
+#[derive(Serialize)]
+struct JsonAnchor<'a> {
+    chapter_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chapter_href: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_index: Option<usize>,
+    char_offset: usize,
+    page: usize,
+}
+
+impl<'a> JsonAnchor<'a> {
+    fn new(loc: &'a Location) -> Self {
+        Self {
+            chapter_index: loc.spine_index(),
+            chapter_href: loc.chapter_href(),
+            block_index: loc.block_index(),
+            char_offset: loc.char_offset(),
+            page: loc.offset(),
+        }
+    }
+}
+
+impl<'a, F> BookExport<'a, F>
+where
+    F: Fn(&Location) -> String,
+{
+    pub fn new(
+        book: &'a BookId,
+        annotations: &'a [Annotation],
+        bookmarks: &'a [Location],
+        chapter_title: F,
+    ) -> Self {
+        Self {
+            book,
+            annotations,
+            bookmarks,
+            chapter_title,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty() && self.bookmarks.is_empty()
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}", self.title());
+        let mut chapter: Option<(usize, Option<&str>)> = None;
+        for entry in self.entries() {
+            let loc = entry.location();
+            let key = (loc.spine_index(), loc.chapter_href());
+            if chapter != Some(key) {
+                chapter = Some(key);
+                let _ = write!(out, "\n## {}\n", (self.chapter_title)(loc));
+            }
+            out.push('\n');
+            match entry {
+                Entry::Highlight(ann) => {
+                    for line in ann.text().lines() {
+                        let _ = writeln!(out, "> {}", line);
+                    }
+                    if let Some(note) = ann.note() {
+                        out.push('\n');
+                        for line in note.lines() {
+                            let _ = writeln!(out, "{}", line);
+                        }
+                    }
+                    let _ = writeln!(
+                        out,
+                        "\n*Highlighted {} ({})*",
+                        format_timestamp(ann.created_at()),
+                        ann.color().name()
+                    );
+                }
+                Entry::Bookmark(loc) => {
+                    let _ = writeln!(out, "- Bookmark (page {})", loc.offset() + 1);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let export = JsonExport {
+            book: JsonBook {
+                id: self.book.id(),
+                path: self.book.path(),
+                title: self.book.title(),
+            },
+            annotations: self
+                .sorted_annotations()
+                .into_iter()
+                .map(|ann| JsonAnnotation {
+                    chapter: (self.chapter_title)(ann.start()),
+                    start: JsonAnchor::new(ann.start()),
+                    end: JsonAnchor::new(ann.end()),
+                    color: ann.color().name(),
+                    text: ann.text(),
+                    note: ann.note(),
+                    created_at: ann.created_at(),
+                })
+                .collect(),
+            bookmarks: self
+                .sorted_bookmarks()
+                .into_iter()
+                .map(|loc| JsonBookmark {
+                    chapter: (self.chapter_title)(loc),
+                    anchor: JsonAnchor::new(loc),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&export).unwrap_or_else(|_| "{}".into())
+    }
+
+    fn title(&self) -> &str {
+        self.book.title().unwrap_or_else(|| {
+            std::path::Path::new(self.book.path())
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Untitled")
+        })
+    }
+
+    fn sorted_annotations(&self) -> Vec<&'a Annotation> {
+        let mut anns: Vec<&Annotation> = self.annotations.iter().collect();
+        anns.sort_by_key(|ann| reading_order(ann.start()));
+        anns
+    }
+
+    fn sorted_bookmarks(&self) -> Vec<&'a Location> {
+        let mut locs: Vec<&Location> = self.bookmarks.iter().collect();
+        locs.sort_by_key(|loc| reading_order(loc));
+        locs
+    }
+
+    fn entries(&self) -> Vec<Entry<'a>> {
+        let mut entries: Vec<Entry> = self
+            .annotations
+            .iter()
+            .map(Entry::Highlight)
+            .chain(self.bookmarks.iter().map(Entry::Bookmark))
+            .collect();
+        entries.sort_by_key(|entry| reading_order(entry.location()));
+        entries
+    }
+}
+
+fn reading_order(loc: &Location) -> (usize, usize, usize, usize) {
+    (
+        loc.spine_index(),
+        loc.block_index().unwrap_or(0),
+        loc.char_offset(),
+        loc.offset(),
+    )
+}
+
+fn format_timestamp(stamp: &str) -> String {
+    match DateTime::parse_from_rfc3339(stamp) {
+        Ok(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        Err(_) => stamp.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::HighlightColor;
+    use crate::types::DocumentFormat;
+
+    fn book() -> BookId {
+        BookId::new(
+            "path:/books/alice.epub",
+            "/books/alice.epub",
+            Some("Alice".to_string()),
+            DocumentFormat::Epub3,
+        )
+    }
+
+    fn at(chapter: usize, block: usize, offset: usize) -> Location {
+        Location::anchored(
+            chapter,
+            Some(format!("OEBPS/ch{}.xhtml", chapter + 1)),
+            block,
+            offset,
+            chapter * 10 + block,
+        )
+    }
+
+    fn highlight(chapter: usize, block: usize, text: &str, note: Option<&str>) -> Annotation {
+        let mut ann = Annotation::new(
+            at(chapter, block, 0),
+            at(chapter, block, text.len()),
+            HighlightColor::Yellow,
+            text,
+            "2024-03-05T14:07:00+00:00",
+        );
+        ann.set_note(note.map(str::to_string));
+        ann
+    }
+
+    fn title(loc: &Location) -> String {
+        format!("Chapter {}", loc.spine_index() + 1)
+    }
+
+    #[test]
+    fn markdown_groups_entries_by_chapter() {
+        let book = book();
+        let annotations = vec![
+            highlight(1, 4, "Off with her head!", None),
+            highlight(0, 2, "Curiouser and curiouser!", Some("Alice grows")),
+        ];
+        let bookmarks = vec![at(0, 0, 0)];
+        let md = BookExport::new(&book, &annotations, &bookmarks, title).to_markdown();
+        let expected = "# Alice\n\
+            \n## Chapter 1\n\
+            \n- Bookmark (page 1)\n\
+            \n> Curiouser and curiouser!\n\
+            \nAlice grows\n\
+            \n*Highlighted 2024-03-05 14:07 (yellow)*\n\
+            \n## Chapter 2\n\
+            \n> Off with her head!\n\
+            \n*Highlighted 2024-03-05 14:07 (yellow)*\n";
+        assert_eq!(md, expected);
+    }
+
+    #[test]
+    fn json_carries_anchors() {
+        let book = book();
+        let annotations = vec![highlight(1, 4, "Off with her head!", Some("Queen"))];
+        let bookmarks = vec![at(0, 3, 12)];
+        let json = BookExport::new(&book, &annotations, &bookmarks, title).to_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let ann = &value["annotations"][0];
+        assert_eq!(ann["chapter"], "Chapter 2");
+        assert_eq!(ann["start"]["chapter_href"], "OEBPS/ch2.xhtml");
+        assert_eq!(ann["start"]["block_index"], 4);
+        assert_eq!(ann["end"]["char_offset"], 18);
+        assert_eq!(ann["note"], "Queen");
+        let mark = &value["bookmarks"][0]["anchor"];
+        assert_eq!(mark["chapter_index"], 0);
+        assert_eq!(mark["block_index"], 3);
+        assert_eq!(mark["char_offset"], 12);
+    }
+
+    #[test]
+    fn format_names() {
+        assert_eq!(ExportFormat::from_name("MD"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_name("json"), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_name("pdf"), None);
+    }
+}
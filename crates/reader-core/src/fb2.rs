@@ -0,0 +1,831 @@
+//! FictionBook 2 (`.fb2`, `.fb2.zip`) loading. Bodies are rewritten as
+//! XHTML, a chapter per top-level section, and go through the same
+//! normalizer as EPUB chapters.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use quick_xml::escape::{escape, unescape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader as XmlReader;
+use thiserror::Error;
+use zip::ZipArchive;
+
+use crate::identity::book_key_for_bytes;
+use crate::normalize::{html_to_blocks_with_assets, postprocess_blocks};
+use crate::text::title_from_path;
+use crate::types::{
+    Block, BookMetadata, CreatorEntry, Document, DocumentFormat, DocumentInfo, SeriesInfo,
+    TitleEntry, TitleKind, TocEntry,
+};
+
+#[derive(Debug, Error)]
+pub enum Fb2Error {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Parse error: {0}")]
+    Parse(String),
+}
+
+/// Whether `path` names an `.fb2` or `.fb2.zip` file.
+pub fn is_fb2_path(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".fb2") || name.ends_with(".fb2.zip")
+}
+
+// Note sections are anchored under this prefix so links to them open the
+// footnote popup
+const NOTE_ANCHOR_PREFIX: &str = "footnote-";
+
+struct Chapter {
+    title: String,
+    href: String,
+    html: String,
+}
+
+pub struct Fb2Book {
+    pub path: PathBuf,
+    pub metadata: BookMetadata,
+    id: String,
+    chapters: Vec<Chapter>,
+    toc: Vec<TocEntry>,
+    binaries: HashMap<String, Vec<u8>>,
+    notes: HashSet<String>,
+}
+
+impl Fb2Book {
+    pub fn open(path: &Path) -> Result<Self, Fb2Error> {
+        let data = std::fs::read(path)?;
+        let zipped = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+        let xml = if zipped {
+            decode_xml(&unzip_fb2(&data)?)
+        } else {
+            decode_xml(&data)
+        };
+        Self::from_xml(path, &data, &xml)
+    }
+
+    // `data` is the file as read, for the key of books without an id
+    fn from_xml(path: &Path, data: &[u8], xml: &str) -> Result<Self, Fb2Error> {
+        let mut parser = Parser::default();
+        let mut reader = XmlReader::from_str(xml);
+        let error = loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => parser.start(&e, false),
+                Ok(Event::Empty(e)) => parser.start(&e, true),
+                Ok(Event::End(e)) => {
+                    let name = local_name(e.name().as_ref());
+                    parser.end(&name);
+                }
+                Ok(Event::Text(t)) => {
+                    if let Ok(text) = t.decode() {
+                        parser.text(&text, &text);
+                    }
+                }
+                Ok(Event::CData(t)) => {
+                    if let Ok(text) = t.decode() {
+                        parser.text(&escape(text.as_ref()), &text);
+                    }
+                }
+                Ok(Event::GeneralRef(r)) => {
+                    if let Ok(name) = r.decode() {
+                        let raw = format!("&{};", name);
+                        let text = unescape(&raw).map(|s| s.into_owned()).unwrap_or_default();
+                        parser.text(&raw, &text);
+                    }
+                }
+                Ok(Event::Eof) => break None,
+                Err(e) => break Some(e.to_string()),
+                _ => {}
+            }
+        };
+        // Rather than a book cut short at the error
+        if let Some(error) = error {
+            return Err(Fb2Error::Parse(error));
+        }
+        if parser.chapters.is_empty() {
+            return Err(Fb2Error::Parse("no <body> found".into()));
+        }
+        Ok(parser.finish(path, data))
+    }
+
+    pub fn chapter_count(&self) -> usize {
+        self.chapters.len()
+    }
+
+    pub fn to_document(&self) -> Document {
+        let mut blocks = Vec::new();
+        let mut chapter_titles = Vec::with_capacity(self.chapters.len());
+        let mut chapter_hrefs = Vec::with_capacity(self.chapters.len());
+        for (idx, chapter) in self.chapters.iter().enumerate() {
+            let mut chapter_blocks = postprocess_blocks(html_to_blocks_with_assets(
+                &chapter.html,
+                None,
+                |src| self.image(src),
+                |href| self.link_target(href),
+            ));
+            let title = if chapter.title.is_empty() {
+                first_heading(&chapter_blocks).unwrap_or_else(|| format!("Section {}", idx + 1))
+            } else {
+                chapter.title.clone()
+            };
+            if chapter_blocks.is_empty() {
+                chapter_blocks.push(Block::Heading(title.clone(), 1));
+            }
+            if idx > 0 {
+                blocks.push(Block::Paragraph(String::new()));
+                blocks.push(Block::Paragraph("───".into()));
+                blocks.push(Block::Paragraph(String::new()));
+            }
+            blocks.append(&mut chapter_blocks);
+            chapter_titles.push(title);
+            chapter_hrefs.push(chapter.href.clone());
+        }
+        let title = self
+            .metadata
+            .main_title()
+            .map(str::to_string)
+            .or_else(|| title_from_path(&self.path));
+        let info = DocumentInfo::new(
+            self.id.clone(),
+            self.path.to_string_lossy().into_owned(),
+            title,
+            None,
+            self.metadata.author_string(),
+            Some(self.metadata.clone()),
+            DocumentFormat::Fb2,
+        );
+        Document::new(
+            info,
+            blocks,
+            chapter_titles,
+            chapter_hrefs,
+            self.toc.clone(),
+        )
+    }
+
+    fn image(&self, src: &str) -> Option<(String, Vec<u8>)> {
+        let id = src.trim_start_matches('#');
+        let data = self.binaries.get(id)?;
+        Some((id.to_string(), data.clone()))
+    }
+
+    // Ids are global to the file, so internal links keep their fragment
+    fn link_target(&self, href: &str) -> Option<String> {
+        let id = href.strip_prefix('#')?;
+        if self.notes.contains(id) {
+            Some(format!("#{}{}", NOTE_ANCHOR_PREFIX, id))
+        } else {
+            Some(href.to_string())
+        }
+    }
+}
+
+fn unzip_fb2(data: &[u8]) -> Result<Vec<u8>, Fb2Error> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let name = archive
+        .file_names()
+        .find(|name| name.to_ascii_lowercase().ends_with(".fb2"))
+        .map(str::to_string)
+        .ok_or_else(|| Fb2Error::Parse("no .fb2 file in archive".into()))?;
+    let mut out = Vec::new();
+    archive.by_name(&name)?.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+// Many FB2 files are windows-1251 or another legacy encoding named in the
+// XML declaration
+fn decode_xml(data: &[u8]) -> String {
+    let head = String::from_utf8_lossy(&data[..data.len().min(200)]);
+    let label = head
+        .split("?>")
+        .next()
+        .and_then(|decl| decl.split("encoding=").nth(1))
+        .and_then(|rest| {
+            let quote = rest.chars().next()?;
+            rest[quote.len_utf8()..].split(quote).next()
+        });
+    let encoding = label
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(data).0.into_owned()
+}
+
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    match name.rsplit_once(':') {
+        Some((_, local)) => local.to_string(),
+        None => name.into_owned(),
+    }
+}
+
+fn attr(e: &BytesStart, key: &str) -> Option<String> {
+    e.attributes().flatten().find_map(|a| {
+        if local_name(a.key.as_ref()) != key {
+            return None;
+        }
+        let value = a.unescape_value().ok()?.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    })
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first_heading(blocks: &[Block]) -> Option<String> {
+    blocks.iter().find_map(|block| match block {
+        Block::Heading(text, _) => Some(crate::layout::strip_style_markers(text)),
+        _ => None,
+    })
+}
+
+struct Section {
+    id: String,
+    note: bool,
+}
+
+/// A title being read; notes render theirs inline before the note text.
+struct Title {
+    level: Option<u8>,
+    text: String,
+    lines: usize,
+}
+
+/// What the end tag of an open body element has to do.
+enum Close {
+    Html(String),
+    Lines(String),
+    Title,
+    Section,
+}
+
+#[derive(Default)]
+struct Parser {
+    path: Vec<String>,
+    text: String,
+    name_parts: Vec<(String, String)>,
+    titles: Vec<TitleEntry>,
+    creators: Vec<CreatorEntry>,
+    series: Option<SeriesInfo>,
+    language: Option<String>,
+    identifier: Option<String>,
+    binary: Option<String>,
+    binaries: HashMap<String, Vec<u8>>,
+    in_body: bool,
+    notes_body: bool,
+    body_title: Option<String>,
+    body_count: usize,
+    closes: Vec<Close>,
+    sections: Vec<Section>,
+    section_count: usize,
+    title: Option<Title>,
+    // Lines written in each open epigraph, cite or stanza
+    lines: Vec<usize>,
+    pending_id: Option<String>,
+    note_open: bool,
+    html: String,
+    chapter: Option<Chapter>,
+    chapters: Vec<Chapter>,
+    toc: Vec<TocEntry>,
+    notes: HashSet<String>,
+}
+
+impl Parser {
+    fn within(&self, name: &str) -> bool {
+        self.path.iter().any(|open| open == name)
+    }
+
+    fn start(&mut self, e: &BytesStart, empty: bool) {
+        let name = local_name(e.name().as_ref());
+        if self.in_body {
+            self.body_start(&name, e, empty);
+        } else {
+            self.text.clear();
+            match name.as_str() {
+                "body" if !empty => self.start_body(e),
+                "binary" => self.binary = attr(e, "id"),
+                "author" | "translator" => self.name_parts.clear(),
+                "sequence" if self.within("title-info") && self.series.is_none() => {
+                    if let Some(series) = attr(e, "name") {
+                        let number = attr(e, "number").and_then(|n| n.parse().ok());
+                        self.series = Some(SeriesInfo::new(series, number));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !empty {
+            self.path.push(name);
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        self.path.pop();
+        if self.in_body {
+            if name == "body" {
+                self.end_body();
+            } else {
+                self.body_end();
+            }
+            return;
+        }
+        let text = collapse_whitespace(&mem::take(&mut self.text));
+        let in_title_info = self.within("title-info");
+        match name {
+            "book-title" if in_title_info && !text.is_empty() => {
+                self.titles.push(TitleEntry::new(text, TitleKind::Main));
+            }
+            "first-name" | "middle-name" | "last-name" | "nickname" if in_title_info => {
+                self.name_parts.push((name.to_string(), text));
+            }
+            "author" | "translator" if in_title_info => {
+                if let Some(person) = self.person_name() {
+                    let role = if name == "author" { "aut" } else { "trl" };
+                    self.creators
+                        .push(CreatorEntry::new(person, vec![role.to_string()]));
+                }
+            }
+            "lang" if in_title_info && !text.is_empty() => self.language = Some(text),
+            "id" if self.within("document-info") && !text.is_empty() => {
+                self.identifier = Some(text);
+            }
+            "binary" => {
+                if let Some(id) = self.binary.take() {
+                    let data: String = text.split_whitespace().collect();
+                    if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) {
+                        self.binaries.insert(id, bytes);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // `raw` goes into the XHTML as is, `text` is the plain reading
+    fn text(&mut self, raw: &str, text: &str) {
+        if self.in_body {
+            self.html.push_str(raw);
+            if let Some(title) = &mut self.title {
+                title.text.push_str(text);
+            }
+        } else if self.binary.is_some() || self.within("description") {
+            self.text.push_str(text);
+        }
+    }
+
+    fn person_name(&self) -> Option<String> {
+        let part = |key: &str| {
+            self.name_parts
+                .iter()
+                .find(|(name, value)| name == key && !value.is_empty())
+                .map(|(_, value)| value.as_str())
+        };
+        let full: Vec<&str> = ["first-name", "middle-name", "last-name"]
+            .into_iter()
+            .filter_map(part)
+            .collect();
+        if full.is_empty() {
+            part("nickname").map(str::to_string)
+        } else {
+            Some(full.join(" "))
+        }
+    }
+
+    fn start_body(&mut self, e: &BytesStart) {
+        self.in_body = true;
+        self.body_count += 1;
+        self.body_title = None;
+        let name = attr(e, "name");
+        self.notes_body = name
+            .as_deref()
+            .is_some_and(|name| matches!(name, "notes" | "comments" | "footnotes"));
+        if self.notes_body {
+            self.chapter = Some(Chapter {
+                title: String::new(),
+                href: name.unwrap_or_default(),
+                html: String::new(),
+            });
+        }
+    }
+
+    fn end_body(&mut self) {
+        self.in_body = false;
+        self.closes.clear();
+        self.sections.clear();
+        self.title = None;
+        self.lines.clear();
+        let mut chapter = match self.chapter.take() {
+            Some(chapter) => chapter,
+            None if self.html.trim().is_empty() => return,
+            None => Chapter {
+                title: String::new(),
+                href: format!("body-{}", self.body_count),
+                html: String::new(),
+            },
+        };
+        if chapter.title.is_empty() {
+            chapter.title = self.body_title.take().unwrap_or_default();
+        }
+        if self.notes_body {
+            if chapter.title.is_empty() {
+                chapter.title = "Notes".into();
+            }
+            self.toc.push(TocEntry::new(
+                chapter.href.clone(),
+                chapter.title.clone(),
+                0,
+            ));
+        }
+        chapter.html = mem::take(&mut self.html);
+        self.chapters.push(chapter);
+    }
+
+    // Anything before the first section stays in front of its chapter
+    fn start_chapter(&mut self, href: String) {
+        if let Some(mut chapter) = self.chapter.take() {
+            chapter.html = mem::take(&mut self.html);
+            self.chapters.push(chapter);
+        }
+        self.chapter = Some(Chapter {
+            title: String::new(),
+            href,
+            html: String::new(),
+        });
+    }
+
+    // Writes `<tag>` with the element's own id, then the id a section is
+    // waiting to place
+    fn open(&mut self, tag: &str, e: &BytesStart) {
+        self.html.push('<');
+        self.html.push_str(tag);
+        if let Some(id) = attr(e, "id") {
+            self.html
+                .push_str(&format!(" id=\"{}\"", escape(id.as_str())));
+        }
+        self.html.push('>');
+        if let Some(id) = self.pending_id.take() {
+            self.html
+                .push_str(&format!("<span id=\"{}\"></span>", escape(id.as_str())));
+        }
+    }
+
+    fn body_start(&mut self, name: &str, e: &BytesStart, empty: bool) {
+        let line = matches!(name, "p" | "v" | "subtitle" | "text-author")
+            && (self.title.is_some() || !self.lines.is_empty());
+        let close = if line {
+            let written = match &mut self.title {
+                Some(title) => {
+                    if title.lines > 0 {
+                        title.text.push(' ');
+                    }
+                    title.lines += 1;
+                    title.lines - 1
+                }
+                None => {
+                    let lines = self.lines.last_mut().expect("line context");
+                    *lines += 1;
+                    *lines - 1
+                }
+            };
+            if written > 0 {
+                self.html.push_str("<br/>");
+            }
+            if name == "text-author" {
+                self.html.push_str("<em>");
+                Close::Html("</em>".into())
+            } else {
+                Close::Html(String::new())
+            }
+        } else {
+            match name {
+                "section" => {
+                    self.section_count += 1;
+                    let own_id = attr(e, "id");
+                    let note = self.notes_body && own_id.is_some();
+                    let id = own_id.unwrap_or_else(|| format!("section-{}", self.section_count));
+                    if note {
+                        self.notes.insert(id.clone());
+                        self.pending_id = Some(format!("{}{}", NOTE_ANCHOR_PREFIX, id));
+                    } else {
+                        if !self.notes_body && self.sections.is_empty() {
+                            self.start_chapter(id.clone());
+                        }
+                        self.pending_id = Some(id.clone());
+                    }
+                    self.sections.push(Section { id, note });
+                    Close::Section
+                }
+                "title" => {
+                    let note = self.sections.last().is_some_and(|s| s.note);
+                    let level = if note {
+                        self.html.push_str("<p><strong>");
+                        None
+                    } else {
+                        let level = self.sections.len().clamp(1, 6) as u8;
+                        self.open(&format!("h{}", level), e);
+                        Some(level)
+                    };
+                    self.title = Some(Title {
+                        level,
+                        text: String::new(),
+                        lines: 0,
+                    });
+                    Close::Title
+                }
+                "p" if self.note_open => {
+                    self.note_open = false;
+                    Close::Html("</p>\n".into())
+                }
+                "p" => {
+                    self.open("p", e);
+                    Close::Html("</p>\n".into())
+                }
+                "subtitle" => {
+                    let level = (self.sections.len() + 1).clamp(2, 6);
+                    self.open(&format!("h{}", level), e);
+                    Close::Html(format!("</h{}>\n", level))
+                }
+                "epigraph" | "cite" | "annotation" => {
+                    self.open("blockquote", e);
+                    self.lines.push(0);
+                    Close::Lines("</blockquote>\n".into())
+                }
+                "stanza" => {
+                    self.open("p", e);
+                    self.lines.push(0);
+                    Close::Lines("</p>\n".into())
+                }
+                "image" => {
+                    if let Some(href) = attr(e, "href") {
+                        let alt = attr(e, "alt").or_else(|| attr(e, "title"));
+                        self.html.push_str(&format!(
+                            "<img src=\"{}\" alt=\"{}\"/>",
+                            escape(href.trim_start_matches('#')),
+                            escape(alt.unwrap_or_default().as_str()),
+                        ));
+                    }
+                    Close::Html(String::new())
+                }
+                "a" => {
+                    let href = attr(e, "href").unwrap_or_default();
+                    self.html
+                        .push_str(&format!("<a href=\"{}\">", escape(href.as_str())));
+                    Close::Html("</a>".into())
+                }
+                "emphasis" => self.inline("em"),
+                "strong" => self.inline("strong"),
+                "strikethrough" => self.inline("s"),
+                "sub" | "sup" | "code" | "table" | "tr" | "th" | "td" => self.inline(name),
+                "style" => self.inline("span"),
+                _ => Close::Html(String::new()),
+            }
+        };
+        if empty {
+            self.close(close);
+        } else {
+            self.closes.push(close);
+        }
+    }
+
+    fn inline(&mut self, tag: &str) -> Close {
+        self.html.push_str(&format!("<{}>", tag));
+        Close::Html(format!("</{}>", tag))
+    }
+
+    fn body_end(&mut self) {
+        if let Some(close) = self.closes.pop() {
+            self.close(close);
+        }
+    }
+
+    fn close(&mut self, close: Close) {
+        match close {
+            Close::Html(html) => self.html.push_str(&html),
+            Close::Lines(html) => {
+                self.lines.pop();
+                self.html.push_str(&html);
+            }
+            Close::Title => self.end_title(),
+            Close::Section => {
+                if mem::take(&mut self.note_open) {
+                    self.html.push_str("</p>\n");
+                }
+                self.pending_id = None;
+                self.sections.pop();
+            }
+        }
+    }
+
+    fn end_title(&mut self) {
+        let Some(title) = self.title.take() else {
+            return;
+        };
+        let Some(level) = title.level else {
+            self.html.push_str("</strong> ");
+            if let Some(id) = self.pending_id.take() {
+                self.html
+                    .push_str(&format!("<span id=\"{}\"></span>", escape(id.as_str())));
+            }
+            self.note_open = true;
+            return;
+        };
+        self.html.push_str(&format!("</h{}>\n", level));
+        let label = collapse_whitespace(&title.text);
+        if label.is_empty() {
+            return;
+        }
+        match self.path.last().map(String::as_str) {
+            Some("body") => self.body_title = Some(label),
+            Some("section") if !self.notes_body => {
+                let depth = self.sections.len();
+                let Some(section) = self.sections.last() else {
+                    return;
+                };
+                let href = if depth == 1 {
+                    if let Some(chapter) = &mut self.chapter {
+                        if chapter.title.is_empty() {
+                            chapter.title = label.clone();
+                        }
+                    }
+                    section.id.clone()
+                } else {
+                    format!("#{}", section.id)
+                };
+                self.toc.push(TocEntry::new(href, label, depth - 1));
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(self, path: &Path, data: &[u8]) -> Fb2Book {
+        let mut metadata = BookMetadata::new(self.titles, self.creators, self.series);
+        metadata.set_language(self.language);
+        metadata.set_identifier(self.identifier);
+        Fb2Book {
+            path: path.to_path_buf(),
+            id: book_key_for_bytes(data, metadata.identifier()),
+            metadata,
+            chapters: self.chapters,
+            toc: self.toc,
+            binaries: self.binaries,
+            notes: self.notes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: &str = r##"<?xml version="1.0" encoding="utf-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+  <description>
+    <title-info>
+      <author><first-name>Mary</first-name><last-name>Shelley</last-name></author>
+      <book-title>Frankenstein</book-title>
+      <lang>en</lang>
+      <sequence name="Gothic Classics" number="2"/>
+    </title-info>
+    <document-info><id>fb2-0001</id></document-info>
+  </description>
+  <body>
+    <title><p>Frankenstein</p></title>
+    <section id="letters">
+      <title><p>Letters</p></title>
+      <section>
+        <title><p>Letter 1</p></title>
+        <p>You will <emphasis>rejoice</emphasis> to hear<a l:href="#n1" type="note">[1]</a>.</p>
+        <image l:href="#pic.png"/>
+      </section>
+    </section>
+    <section>
+      <title><p>Chapter 1</p><p>Origins</p></title>
+      <epigraph><p>Did I request thee, Maker?</p><text-author>Milton</text-author></epigraph>
+      <p>I am by birth a Genevese &amp; proud of it.</p>
+    </section>
+  </body>
+  <body name="notes">
+    <section id="n1"><title><p>1</p></title><p>Written to his sister.</p></section>
+  </body>
+  <binary id="pic.png" content-type="image/png">iVBORw0K
+    GgoAAAANSUhEUg==</binary>
+</FictionBook>"##;
+
+    fn book() -> Fb2Book {
+        Fb2Book::from_xml(Path::new("frankenstein.fb2"), BOOK.as_bytes(), BOOK).unwrap()
+    }
+
+    #[test]
+    fn reads_title_info() {
+        let meta = book().metadata;
+        assert_eq!(meta.main_title(), Some("Frankenstein"));
+        assert_eq!(meta.author_string().as_deref(), Some("Mary Shelley"));
+        assert_eq!(meta.language(), Some("en"));
+        assert_eq!(meta.identifier(), Some("fb2-0001"));
+        let series = meta.series().unwrap();
+        assert_eq!(series.name(), "Gothic Classics");
+        assert_eq!(series.index(), Some(2.0));
+    }
+
+    #[test]
+    fn sections_become_chapters_and_toc_levels() {
+        let document = book().to_document();
+        assert_eq!(
+            document.chapter_titles(),
+            &["Letters", "Chapter 1 Origins", "Notes"]
+        );
+        assert_eq!(document.chapter_hrefs(), &["letters", "section-3", "notes"]);
+        let toc: Vec<(&str, &str, usize)> = document
+            .toc_entries()
+            .iter()
+            .map(|e| (e.href(), e.label(), e.level()))
+            .collect();
+        assert_eq!(
+            toc,
+            vec![
+                ("letters", "Letters", 0),
+                ("#section-2", "Letter 1", 1),
+                ("section-3", "Chapter 1 Origins", 0),
+                ("notes", "Notes", 0),
+            ]
+        );
+        let blocks = document.blocks();
+        assert!(matches!(&blocks[0], Block::Heading(t, 1) if t == "Frankenstein"));
+        assert!(blocks
+            .iter()
+            .any(|b| matches!(b, Block::Quote(t) if t.contains("Maker?") && t.contains("Milton"))));
+        assert!(blocks
+            .iter()
+            .any(|b| matches!(b, Block::Paragraph(t) if t.contains("Genevese & proud"))));
+        let separators = blocks
+            .iter()
+            .filter(|b| matches!(b, Block::Paragraph(t) if t == "───"))
+            .count();
+        assert_eq!(separators, 2);
+    }
+
+    #[test]
+    fn decodes_binary_images() {
+        let document = book().to_document();
+        let image = document
+            .blocks()
+            .iter()
+            .find_map(|b| match b {
+                Block::Image(image) => Some(image),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(image.id(), "pic.png");
+        assert_eq!(&image.data().unwrap()[1..4], b"PNG");
+    }
+
+    #[test]
+    fn note_links_point_at_note_anchors() {
+        let document = book().to_document();
+        let blocks = document.blocks();
+        let text = blocks
+            .iter()
+            .find_map(|b| match b {
+                Block::Paragraph(t) if t.contains("rejoice") => Some(t.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert!(text.contains("\x1C#footnote-n1\x1D[1]"));
+        let note = blocks
+            .iter()
+            .find_map(|b| match b {
+                Block::Paragraph(t) if t.contains("Written to") => Some(t.clone()),
+                _ => None,
+            })
+            .unwrap();
+        let (_, after) = note.split_once("\x18#footnote-n1\x17").unwrap();
+        assert_eq!(after.trim(), "Written to his sister.");
+    }
+
+    #[test]
+    fn malformed_xml_is_an_error_rather_than_a_truncated_book() {
+        let cut = BOOK.replace("</emphasis>", "</strong>");
+        let result = Fb2Book::from_xml(Path::new("cut.fb2"), cut.as_bytes(), &cut);
+        assert!(matches!(result, Err(Fb2Error::Parse(_))));
+    }
+
+    #[test]
+    fn decodes_legacy_encodings_and_zip_names() {
+        let xml = b"<?xml version=\"1.0\" encoding=\"windows-1251\"?><a>\xcf\xf0\xe8</a>";
+        assert!(decode_xml(xml).contains("\u{41f}\u{440}\u{438}"));
+        assert!(is_fb2_path(Path::new("/books/War.FB2")));
+        assert!(is_fb2_path(Path::new("war.fb2.zip")));
+        assert!(!is_fb2_path(Path::new("war.zip")));
+    }
+}
@@ -0,0 +1,595 @@
+//! Standalone HTML/XHTML pages and HTMLZ archives (a zipped page with its
+//! images and, when calibre wrote it, `metadata.opf`). The page goes
+//! through the EPUB normalizer and is split into chapters at `h1`/`h2`.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+
+use base64::Engine;
+use kuchiki::traits::*;
+use thiserror::Error;
+use zip::ZipArchive;
+
+use crate::epub::parse_opf;
+use crate::identity::{book_key_for_bytes, bytes_key};
+use crate::layout::strip_style_markers;
+use crate::normalize::{extract_article, html_to_blocks_with_assets, postprocess_blocks};
+use crate::text::title_from_path;
+use crate::types::{
+    Block, BookMetadata, CreatorEntry, Document, DocumentFormat, DocumentInfo, TitleEntry,
+    TitleKind, TocEntry,
+};
+
+#[derive(Debug, Error)]
+pub enum HtmlError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Parse error: {0}")]
+    Parse(String),
+}
+
+/// Whether `path` names an HTML or XHTML page or an HTMLZ archive.
+pub fn is_html_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "html" | "htm" | "xhtml" | "xht" | "htmlz"
+            )
+        })
+}
+
+fn is_htmlz_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("htmlz"))
+}
+
+fn is_page_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    [".html", ".htm", ".xhtml", ".xht"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Where relative image paths are looked up.
+enum Assets {
+    Dir(PathBuf),
+    Archive {
+        dir: String,
+        files: HashMap<String, Vec<u8>>,
+    },
+}
+
+pub struct HtmlBook {
+    pub path: PathBuf,
+    pub metadata: BookMetadata,
+    id: String,
+    html: String,
+    name: String,
+    assets: Assets,
+    packaged: bool, // metadata came from metadata.opf rather than the page
+}
+
+impl HtmlBook {
+    pub fn open(path: &Path) -> Result<Self, HtmlError> {
+        let data = std::fs::read(path)?;
+        if is_htmlz_path(path) {
+            return Self::from_htmlz(path, &data);
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        Ok(Self::from_html(
+            path,
+            &data,
+            decode_html(&data),
+            name,
+            Assets::Dir(dir),
+            None,
+        ))
+    }
+
+    fn from_htmlz(path: &Path, data: &[u8]) -> Result<Self, HtmlError> {
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+        let mut files = HashMap::new();
+        for idx in 0..archive.len() {
+            let mut file = archive.by_index(idx)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            files.insert(file.name().to_string(), bytes);
+        }
+        // calibre names the page index.html; otherwise take the shallowest one
+        let page = files
+            .keys()
+            .filter(|name| is_page_name(name))
+            .min_by_key(|name| (*name != "index.html", name.matches('/').count(), *name))
+            .cloned()
+            .ok_or_else(|| HtmlError::Parse("no HTML page in archive".into()))?;
+        let html = decode_html(&files.remove(&page).unwrap_or_default());
+        let opf = files
+            .iter()
+            .find(|(name, _)| name.to_ascii_lowercase().ends_with(".opf"))
+            .and_then(|(_, xml)| parse_opf(&String::from_utf8_lossy(xml)).ok())
+            .map(|(metadata, ..)| metadata);
+        let (dir, name) = match page.rsplit_once('/') {
+            Some((dir, name)) => (dir.to_string(), name.to_string()),
+            None => (String::new(), page),
+        };
+        Ok(Self::from_html(
+            path,
+            data,
+            html,
+            name,
+            Assets::Archive { dir, files },
+            opf,
+        ))
+    }
+
+    // `data` is the file as read, for the key of books without an id
+    fn from_html(
+        path: &Path,
+        data: &[u8],
+        html: String,
+        name: String,
+        assets: Assets,
+        opf: Option<BookMetadata>,
+    ) -> Self {
+        let opf = opf.filter(|metadata| metadata.main_title().is_some());
+        let packaged = opf.is_some();
+        let metadata = opf.unwrap_or_else(|| head_metadata(&html));
+        Self {
+            path: path.to_path_buf(),
+            id: book_key_for_bytes(data, metadata.identifier()),
+            metadata,
+            html,
+            name,
+            assets,
+            packaged,
+        }
+    }
+
+    /// Cut the page down to its main article, taking the title and byline
+    /// from around it unless an archive brought its own metadata. False
+    /// when no article stands out and the page is kept whole.
+    pub fn extract_article(&mut self) -> bool {
+        let Some(article) = extract_article(&self.html) else {
+            return false;
+        };
+        self.html = article.html;
+        if self.packaged {
+            return true;
+        }
+        let title = article
+            .title
+            .or_else(|| self.metadata.main_title().map(str::to_string));
+        let titles = title
+            .map(|title| vec![TitleEntry::new(title, TitleKind::Main)])
+            .unwrap_or_default();
+        let mut creators = self.metadata.creators().to_vec();
+        if creators.is_empty() {
+            if let Some(byline) = article.byline {
+                let name = byline
+                    .strip_prefix("By ")
+                    .or_else(|| byline.strip_prefix("by "))
+                    .unwrap_or(&byline)
+                    .trim();
+                creators.push(CreatorEntry::new(name, vec!["aut".to_string()]));
+            }
+        }
+        let mut metadata = BookMetadata::new(titles, creators, self.metadata.series().cloned());
+        metadata.set_language(self.metadata.language().map(str::to_string));
+        metadata.set_identifier(self.metadata.identifier().map(str::to_string));
+        self.metadata = metadata;
+        true
+    }
+
+    pub fn to_document(&self) -> Document {
+        let blocks = postprocess_blocks(html_to_blocks_with_assets(
+            &self.html,
+            None,
+            |src| self.image(src),
+            |href| self.link_target(href),
+        ));
+        let title = self
+            .metadata
+            .main_title()
+            .map(str::to_string)
+            .or_else(|| title_from_path(&self.path));
+        let sections = split_sections(blocks);
+        let top = sections
+            .iter()
+            .filter_map(|section| section.level)
+            .min()
+            .unwrap_or(1);
+        let mut blocks = Vec::new();
+        let mut chapter_titles = Vec::with_capacity(sections.len());
+        let mut chapter_hrefs = Vec::with_capacity(sections.len());
+        let mut toc = Vec::new();
+        for (idx, mut section) in sections.into_iter().enumerate() {
+            if idx > 0 {
+                blocks.push(Block::Paragraph(String::new()));
+                blocks.push(Block::Paragraph("───".into()));
+                blocks.push(Block::Paragraph(String::new()));
+            }
+            let href = format!("section-{}", idx + 1);
+            let label = section
+                .title
+                .or_else(|| title.clone())
+                .unwrap_or_else(|| format!("Section {}", idx + 1));
+            if let Some(level) = section.level {
+                toc.push(TocEntry::new(
+                    href.clone(),
+                    label.clone(),
+                    usize::from(level - top),
+                ));
+            }
+            blocks.append(&mut section.blocks);
+            chapter_titles.push(label);
+            chapter_hrefs.push(href);
+        }
+        let info = DocumentInfo::new(
+            self.id.clone(),
+            self.path.to_string_lossy().into_owned(),
+            title,
+            self.metadata.subtitle().map(str::to_string),
+            self.metadata.author_string(),
+            Some(self.metadata.clone()),
+            DocumentFormat::Html,
+        );
+        Document::new(info, blocks, chapter_titles, chapter_hrefs, toc)
+    }
+
+    // Relative and root-relative paths resolve against the page's folder,
+    // on disk or in the archive; remote images and files outside that
+    // folder are left out
+    fn image(&self, src: &str) -> Option<(String, Vec<u8>)> {
+        if let Some(uri) = src.strip_prefix("data:") {
+            let (_, encoded) = uri.split_once(";base64,")?;
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .ok()?;
+            return Some((bytes_key(&data), data));
+        }
+        if src.contains("://") {
+            return None;
+        }
+        let src = percent_decode(src.split(['?', '#']).next()?);
+        match &self.assets {
+            Assets::Dir(dir) => {
+                let path = normalize_path(&dir.join(src.trim_start_matches('/')));
+                let real = path.canonicalize().ok()?;
+                if !real.starts_with(dir.canonicalize().ok()?) || !real.is_file() {
+                    return None;
+                }
+                let data = std::fs::read(&real).ok()?;
+                Some((path.to_string_lossy().into_owned(), data))
+            }
+            Assets::Archive { dir, files } => {
+                let joined = match src.strip_prefix('/') {
+                    Some(absolute) => PathBuf::from(absolute),
+                    None => Path::new(dir).join(&src),
+                };
+                let name = normalize_path(&joined).to_string_lossy().replace('\\', "/");
+                let data = files.get(&name)?;
+                Some((name, data.clone()))
+            }
+        }
+    }
+
+    // Ids are global to the page, so links within it keep only their fragment
+    fn link_target(&self, href: &str) -> Option<String> {
+        let (path, fragment) = href.split_once('#')?;
+        (path.is_empty() || percent_decode(path) == self.name).then(|| format!("#{}", fragment))
+    }
+}
+
+/// Blocks from one `h1`/`h2` heading to the next.
+struct Section {
+    title: Option<String>,
+    level: Option<u8>,
+    blocks: Vec<Block>,
+}
+
+// A heading only opens a new section once the current one has more than
+// headings, so a page title over its first chapter heading stays together
+fn split_sections(blocks: Vec<Block>) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    for block in blocks {
+        let level = match &block {
+            Block::Heading(_, level) if *level <= 2 => Some(*level),
+            _ => None,
+        };
+        let has_body = sections.last().is_some_and(|section| {
+            section
+                .blocks
+                .iter()
+                .any(|block| !matches!(block, Block::Heading(..)))
+        });
+        if sections.is_empty() || (level.is_some() && has_body) {
+            sections.push(Section {
+                title: None,
+                level: None,
+                blocks: Vec::new(),
+            });
+        }
+        let Some(section) = sections.last_mut() else {
+            continue;
+        };
+        if let (Block::Heading(text, _), Some(level)) = (&block, level) {
+            if section.title.is_none() {
+                section.title = Some(strip_style_markers(text));
+                section.level = Some(level);
+            }
+        }
+        section.blocks.push(block);
+    }
+    sections
+}
+
+/// Title, author and language from `<title>`, `<html lang>` and the usual
+/// `<meta>` names.
+fn head_metadata(html: &str) -> BookMetadata {
+    let document = kuchiki::parse_html().one(html);
+    let meta = |names: &[&str]| {
+        document.select("meta").ok()?.find_map(|node| {
+            let attrs = node.attributes.borrow();
+            let key = attrs.get("name").or_else(|| attrs.get("property"))?;
+            if !names.iter().any(|name| key.eq_ignore_ascii_case(name)) {
+                return None;
+            }
+            let content = attrs.get("content")?.trim();
+            (!content.is_empty()).then(|| content.to_string())
+        })
+    };
+    let title = document
+        .select_first("title")
+        .ok()
+        .map(|node| collapse_whitespace(&node.text_contents()))
+        .filter(|title| !title.is_empty())
+        .or_else(|| meta(&["og:title", "dc.title", "dcterms.title"]));
+    let titles = title
+        .map(|title| vec![TitleEntry::new(title, TitleKind::Main)])
+        .unwrap_or_default();
+    let creators = meta(&["author", "dc.creator", "dcterms.creator", "article:author"])
+        .map(|name| vec![CreatorEntry::new(name, vec!["aut".to_string()])])
+        .unwrap_or_default();
+    let language = document
+        .select_first("html")
+        .ok()
+        .and_then(|node| {
+            let attrs = node.attributes.borrow();
+            let lang = attrs.get("lang").or_else(|| attrs.get("xml:lang"))?.trim();
+            (!lang.is_empty()).then(|| lang.to_string())
+        })
+        .or_else(|| meta(&["dc.language", "dcterms.language"]));
+    let mut metadata = BookMetadata::new(titles, creators, None);
+    metadata.set_language(language);
+    metadata.set_identifier(meta(&["dc.identifier", "dcterms.identifier"]));
+    metadata
+}
+
+// A byte order mark wins, then a charset named in the first kilobyte
+fn decode_html(data: &[u8]) -> String {
+    if let Some((encoding, bom)) = encoding_rs::Encoding::for_bom(data) {
+        return encoding
+            .decode_without_bom_handling(&data[bom..])
+            .0
+            .into_owned();
+    }
+    let head = String::from_utf8_lossy(&data[..data.len().min(1024)]).to_ascii_lowercase();
+    let label = head.split("charset=").nth(1).and_then(|rest| {
+        rest.trim_start_matches(['"', '\''])
+            .split(|c: char| matches!(c, '"' | '\'' | ';' | '>' | '/') || c.is_whitespace())
+            .next()
+    });
+    let encoding = label
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(data).0.into_owned()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            _ => out.push(component.as_os_str()),
+        }
+    }
+    out
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nimage";
+
+    const PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en"><head>
+  <meta charset="utf-8">
+  <title>Field  Notes</title>
+  <meta name="author" content="Ann Author">
+</head><body>
+  <h1>Field Notes</h1>
+  <h2 id="spring">Spring</h2>
+  <p>Buds, see <a href="#autumn">autumn</a>.</p>
+  <p><img src="img/bud%201.png" alt="A bud"></p>
+  <h2 id="autumn">Autumn</h2>
+  <h3>Leaves</h3>
+  <p>Falling.</p>
+</body></html>"##;
+
+    fn image_ids(document: &Document) -> Vec<&str> {
+        document
+            .blocks()
+            .iter()
+            .filter_map(|block| match block {
+                Block::Image(image) if image.data() == Some(PNG) => Some(image.id()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_a_page_into_chapters_at_h1_and_h2() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("img")).unwrap();
+        std::fs::write(dir.path().join("img/bud 1.png"), PNG).unwrap();
+        let path = dir.path().join("notes.html");
+        std::fs::write(&path, PAGE).unwrap();
+
+        let book = HtmlBook::open(&path).unwrap();
+        assert_eq!(book.metadata.main_title(), Some("Field Notes"));
+        assert_eq!(book.metadata.author_string().as_deref(), Some("Ann Author"));
+        assert_eq!(book.metadata.language(), Some("en"));
+        let document = book.to_document();
+        assert_eq!(document.info().format(), DocumentFormat::Html);
+        assert_eq!(document.chapter_titles(), &["Field Notes", "Autumn"]);
+        assert_eq!(document.chapter_hrefs(), &["section-1", "section-2"]);
+        let toc: Vec<(&str, usize)> = document
+            .toc_entries()
+            .iter()
+            .map(|entry| (entry.label(), entry.level()))
+            .collect();
+        assert_eq!(toc, vec![("Field Notes", 0), ("Autumn", 1)]);
+        assert_eq!(image_ids(&document).len(), 1);
+        let link = document.blocks().iter().any(
+            |block| matches!(block, Block::Paragraph(text) if text.contains("\x1C#autumn\x1D")),
+        );
+        assert!(link);
+    }
+
+    #[test]
+    fn images_stay_inside_the_page_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = dir.path().join("site");
+        std::fs::create_dir_all(site.join("img")).unwrap();
+        std::fs::write(site.join("img/bud.png"), PNG).unwrap();
+        std::fs::write(dir.path().join("secret.png"), PNG).unwrap();
+        let path = site.join("page.html");
+        std::fs::write(
+            &path,
+            "<html><head><meta name=\"dc.identifier\" content=\"urn:page:1\"></head><body>\
+             <p><img src=\"/img/bud.png\"></p><p><img src=\"../secret.png\"></p>\
+             <p><img src=\"img\"></p></body></html>",
+        )
+        .unwrap();
+
+        let book = HtmlBook::open(&path).unwrap();
+        let document = book.to_document();
+        let ids = image_ids(&document);
+        assert_eq!(ids.len(), 1);
+        assert!(ids[0].ends_with("bud.png"));
+        let key = crate::identity::book_key(&path, book.metadata.identifier()).unwrap();
+        assert_eq!(document.info().id(), key);
+        assert_eq!(key, "dc:urn:page:1");
+    }
+
+    #[test]
+    fn htmlz_reads_images_and_opf_metadata_from_the_archive() {
+        let opf = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Zipped Notes</dc:title>
+    <dc:creator>Bo Author</dc:creator>
+  </metadata>
+</package>"#;
+        let page = PAGE.replace("img/bud%201.png", "../images/bud.png");
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in [
+            ("text/index.html", page.as_bytes()),
+            ("images/bud.png", PNG),
+            ("metadata.opf", opf.as_bytes()),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let data = zip.finish().unwrap().into_inner();
+
+        let book = HtmlBook::from_htmlz(Path::new("notes.htmlz"), &data).unwrap();
+        assert_eq!(book.metadata.main_title(), Some("Zipped Notes"));
+        assert_eq!(book.metadata.author_string().as_deref(), Some("Bo Author"));
+        assert_eq!(image_ids(&book.to_document()), vec!["images/bud.png"]);
+    }
+
+    #[test]
+    fn article_extraction_takes_title_and_byline_from_the_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("canyons.html");
+        std::fs::write(&path, crate::normalize::tests::SAVED_PAGE).unwrap();
+
+        let mut book = HtmlBook::open(&path).unwrap();
+        assert!(book.extract_article());
+        assert_eq!(
+            book.metadata.main_title(),
+            Some("How Rivers Carve Canyons Over Time")
+        );
+        assert_eq!(book.metadata.author_string().as_deref(), Some("Rita River"));
+        let document = book.to_document();
+        assert_eq!(
+            document.chapter_titles(),
+            &["How Rivers Carve Canyons Over Time"]
+        );
+        let home = document
+            .blocks()
+            .iter()
+            .any(|block| matches!(block, Block::Paragraph(text) if text.contains("Home")));
+        assert!(!home);
+    }
+
+    #[test]
+    fn decodes_declared_charsets_and_recognizes_paths() {
+        let latin1 = b"<meta charset=\"iso-8859-1\"><p>caf\xe9</p>";
+        assert!(decode_html(latin1).contains("café"));
+        assert_eq!(percent_decode("a%20b%2"), "a b%2");
+        assert!(is_html_path(Path::new("/saved/Page.XHTML")));
+        assert!(is_html_path(Path::new("book.htmlz")));
+        assert!(!is_html_path(Path::new("book.epub")));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    annotations, cbz::CbzBook, config, epub::EpubBook, fb2::Fb2Book, html::HtmlBook,
+    mobi::MobiBook, state, types::BookId, types::DocumentFormat,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+const PATH_PREFIX: &str = "path:";
+// Written to the config directory once path-based ids have been migrated
+const MIGRATED_MARKER: &str = "ids-migrated";
+
+/// Stable identity for a book file, independent of where it lives: the EPUB
+/// `dc:identifier` when the package has one, otherwise a SHA-256 of the bytes.
+/// Files without an identifier, such as PDFs, text and Markdown, are hashed
+/// whole on every open, and editing one gives it a new key, so its saved
+/// position and notes no longer apply.
+pub fn book_key(path: &Path, identifier: Option<&str>) -> io::Result<String> {
+    match identifier_key(identifier) {
+        Some(key) => Ok(key),
+        None => Ok(format!("sha256:{}", file_sha256(path)?)),
+    }
+}
+
+/// `book_key` for a file already read into memory as `bytes`.
+pub fn book_key_for_bytes(bytes: &[u8], identifier: Option<&str>) -> String {
+    identifier_key(identifier).unwrap_or_else(|| bytes_key(bytes))
+}
+
+fn identifier_key(identifier: Option<&str>) -> Option<String> {
+    identifier
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| format!("dc:{}", id))
+}
+
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Key for content already in memory; equal to `book_key` of the same bytes.
+pub fn bytes_key(bytes: &[u8]) -> String {
+    format!("sha256:{}", hex(&Sha256::digest(bytes)))
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Key for a book, opening the formats that record an identifier (all
+/// but PDF, text and Markdown) to read it.
+pub fn key_for_file(path: &Path, format: DocumentFormat) -> io::Result<String> {
+    let identifier = match format {
+        DocumentFormat::Epub3 | DocumentFormat::Epub2 => EpubBook::open(path)
+            .ok()
+            .and_then(|book| book.metadata.identifier().map(str::to_string)),
+        DocumentFormat::Fb2 => Fb2Book::open(path)
+            .ok()
+            .and_then(|book| book.metadata.identifier().map(str::to_string)),
+        DocumentFormat::Mobi => MobiBook::open(path)
+            .ok()
+            .and_then(|book| book.metadata.identifier().map(str::to_string)),
+        DocumentFormat::Cbz => CbzBook::read_metadata(path)
+            .ok()
+            .and_then(|(metadata, _)| metadata.identifier().map(str::to_string)),
+        DocumentFormat::Html => HtmlBook::open(path)
+            .ok()
+            .and_then(|book| book.metadata.identifier().map(str::to_string)),
+        _ => None,
+    };
+    book_key(path, identifier.as_deref())
+}
+
+/// Ids written before content hashing were derived from the file path.
+pub fn is_path_key(id: &str) -> bool {
+    id.starts_with(PATH_PREFIX)
+}
+
+/// Rewrite path-based ids in saved state, annotations and spritz sessions
+/// for books whose file still exists. Runs once per config directory, as
+/// everything saved afterwards already has stable ids. Returns the number
+/// of books migrated.
+pub fn migrate_path_keys() -> io::Result<usize> {
+    let marker = config::config_root().map(|dir| dir.join(MIGRATED_MARKER));
+    if marker.as_ref().is_some_and(|marker| marker.exists()) {
+        return Ok(0);
+    }
+    let resolve = |book: &BookId| -> Option<BookId> {
+        if !is_path_key(book.id()) || !Path::new(book.path()).is_file() {
+            return None;
+        }
+        let key = key_for_file(Path::new(book.path()), book.format()).ok()?;
+        Some(BookId::new(
+            key,
+            book.path(),
+            book.title().map(str::to_string),
+            book.format(),
+        ))
+    };
+    let migrated = state::migrate_book_ids(&resolve)?;
+    annotations::migrate_book_ids(&|book| {
+        migrated
+            .iter()
+            .find(|(old, _)| old == book.id())
+            .map(|(_, new)| new.clone())
+            .or_else(|| resolve(book))
+    })?;
+    if let Some(marker) = marker {
+        if let Some(dir) = marker.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(marker, "")?;
+    }
+    Ok(migrated.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn identifier_wins_over_file_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.txt");
+        File::create(&path).unwrap().write_all(b"abc").unwrap();
+        assert_eq!(
+            book_key(&path, Some(" urn:isbn:9780000000000 ")).unwrap(),
+            "dc:urn:isbn:9780000000000"
+        );
+        assert_eq!(
+            book_key(&path, None).unwrap(),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Moving the file keeps its key
+        let moved = dir.path().join("renamed.txt");
+        std::fs::rename(&path, &moved).unwrap();
+        assert!(book_key(&moved, None).unwrap().ends_with("15ad"));
+        assert_eq!(bytes_key(b"abc"), book_key(&moved, None).unwrap());
+        assert!(is_path_key("path:/books/a.epub"));
+    }
+}
@@ -0,0 +1,547 @@
+use crate::{
+    config,
+    identity::bytes_key,
+    layout::strip_style_markers,
+    library::LibraryBook,
+    types::{Block, Location},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+const SNIPPET_BEFORE: usize = 40;
+const SNIPPET_AFTER: usize = 80;
+
+/// Searchable text of one chapter: the plain text of each block, in the
+/// same order as the blocks the reader lays out.
+pub struct IndexedChapter {
+    href: String,
+    title: String,
+    blocks: Vec<String>,
+}
+
+impl IndexedChapter {
+    pub fn from_blocks(
+        href: impl Into<String>,
+        title: impl Into<String>,
+        blocks: &[Block],
+    ) -> Self {
+        Self {
+            href: href.into(),
+            title: title.into(),
+            blocks: blocks.iter().map(block_text).collect(),
+        }
+    }
+}
+
+// Text of a block as far as search is concerned. Every block keeps its slot,
+// so block numbers match the reader's chapter-relative block index.
+fn block_text(block: &Block) -> String {
+    match block {
+        Block::Paragraph(text) | Block::Heading(text, _) | Block::Quote(text) => {
+            let text = strip_style_markers(text);
+            if text.trim() == "───" {
+                String::new()
+            } else {
+                text
+            }
+        }
+        // Bulleted like the reader lays lists out, so offsets line up
+        Block::List(items) => items
+            .iter()
+            .map(|item| format!("• {}", strip_style_markers(item)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Block::Code { text, .. } => text.clone(),
+        Block::Image(image) => image
+            .caption()
+            .or(image.alt())
+            .map(strip_style_markers)
+            .unwrap_or_default(),
+        Block::Table(table) => table
+            .rows()
+            .iter()
+            .flat_map(|row| row.iter().map(|cell| strip_style_markers(cell.text())))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Lowercased words of `text` with their byte offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.unicode_word_indices()
+        .map(|(idx, word)| (idx, word.to_lowercase()))
+}
+
+// Offset of `byte` in `text`, counted like `TextPosition::offset`: one unit
+// per grapheme with each whitespace run counted once.
+fn units_before(text: &str, byte: usize) -> usize {
+    let mut count = 0usize;
+    let mut prev_space = false;
+    for (idx, g) in text.grapheme_indices(true) {
+        if idx >= byte {
+            break;
+        }
+        let space = g.chars().all(char::is_whitespace);
+        if !(space && prev_space) {
+            count += 1;
+        }
+        prev_space = space;
+    }
+    count
+}
+
+#[derive(Serialize, Deserialize)]
+struct SegmentChapter {
+    href: String,
+    title: String,
+    blocks: Vec<String>,
+}
+
+// One book's postings: term -> (chapter, block, byte offset) triples.
+#[derive(Default, Serialize, Deserialize)]
+struct BookSegment {
+    chapters: Vec<SegmentChapter>,
+    terms: HashMap<String, Vec<[u32; 3]>>,
+}
+
+impl BookSegment {
+    fn build(chapters: Vec<IndexedChapter>) -> Self {
+        let mut terms: HashMap<String, Vec<[u32; 3]>> = HashMap::new();
+        for (c, chapter) in chapters.iter().enumerate() {
+            for (b, text) in chapter.blocks.iter().enumerate() {
+                for (offset, word) in words(text) {
+                    terms
+                        .entry(word)
+                        .or_default()
+                        .push([c as u32, b as u32, offset as u32]);
+                }
+            }
+        }
+        Self {
+            chapters: chapters
+                .into_iter()
+                .map(|chapter| SegmentChapter {
+                    href: chapter.href,
+                    title: chapter.title,
+                    blocks: chapter.blocks,
+                })
+                .collect(),
+            terms,
+        }
+    }
+
+    // Blocks containing every term, at the earliest occurrence of any of them
+    fn matches(&self, terms: &[String]) -> Vec<(usize, usize, usize)> {
+        let mut per_term: Vec<HashMap<(u32, u32), u32>> = Vec::with_capacity(terms.len());
+        for term in terms {
+            let Some(postings) = self.terms.get(term) else {
+                return Vec::new();
+            };
+            let mut blocks: HashMap<(u32, u32), u32> = HashMap::new();
+            for [c, b, offset] in postings {
+                let first = blocks.entry((*c, *b)).or_insert(*offset);
+                *first = (*first).min(*offset);
+            }
+            per_term.push(blocks);
+        }
+        per_term.sort_by_key(HashMap::len);
+        let Some((rarest, rest)) = per_term.split_first() else {
+            return Vec::new();
+        };
+        let mut found: Vec<(usize, usize, usize)> = rarest
+            .iter()
+            .filter_map(|(key, offset)| {
+                let mut earliest = *offset;
+                for blocks in rest {
+                    earliest = earliest.min(*blocks.get(key)?);
+                }
+                Some((key.0 as usize, key.1 as usize, earliest as usize))
+            })
+            .collect();
+        found.sort();
+        found
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    id: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    size: u64,
+    modified: u64,
+    segment: String,
+}
+
+/// One match: the book, the chapter, and a snippet around the hit.
+/// `highlight` is the byte range of the matched word inside `snippet`.
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub book_id: String,
+    pub book_path: String,
+    pub book_title: Option<String>,
+    pub chapter_index: usize,
+    pub chapter_href: String,
+    pub chapter_title: String,
+    pub block_index: usize,
+    pub char_offset: usize,
+    pub snippet: String,
+    pub highlight: Range<usize>,
+}
+
+impl SearchHit {
+    /// Where the reader should open to show this hit.
+    pub fn location(&self) -> Location {
+        Location::anchored(
+            self.chapter_index,
+            Some(self.chapter_href.clone()),
+            self.block_index,
+            self.char_offset,
+            0,
+        )
+    }
+}
+
+/// On-disk inverted index of the library, stored as `index/` in the config
+/// dir: a manifest of indexed books plus one postings segment per book.
+/// Books are re-indexed only when their file changed since.
+pub struct SearchIndex {
+    dir: PathBuf,
+    entries: Vec<IndexEntry>,
+    segments: HashMap<String, BookSegment>,
+}
+
+impl SearchIndex {
+    pub fn open() -> io::Result<Self> {
+        let dir = config::config_root()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config dir"))?
+            .join("index");
+        Ok(Self::open_in(&dir))
+    }
+
+    pub fn open_in(dir: &Path) -> Self {
+        let entries = fs::read(dir.join("manifest.json"))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            dir: dir.to_path_buf(),
+            entries,
+            segments: HashMap::new(),
+        }
+    }
+
+    pub fn book_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether `book` is indexed as its file currently is.
+    pub fn is_current(&self, book: &LibraryBook) -> bool {
+        self.entries.iter().any(|entry| {
+            entry.id == book.id()
+                && entry.path == book.path()
+                && (entry.size, entry.modified) == book.file_stamp()
+        })
+    }
+
+    /// Replace the postings of `book` with those of `chapters`.
+    pub fn add_book(
+        &mut self,
+        book: &LibraryBook,
+        chapters: Vec<IndexedChapter>,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let segment = segment_name(book.id());
+        let data = BookSegment::build(chapters);
+        write_atomic(
+            &self.dir.join(&segment),
+            serde_json::to_string(&data).unwrap_or_default().as_bytes(),
+        )?;
+        let (size, modified) = book.file_stamp();
+        self.entries.retain(|entry| entry.id != book.id());
+        self.entries.push(IndexEntry {
+            id: book.id().to_string(),
+            path: book.path().to_string(),
+            title: Some(book.display_title().to_string()),
+            size,
+            modified,
+            segment: segment.clone(),
+        });
+        self.segments.insert(segment, data);
+        self.save_manifest()
+    }
+
+    /// Drop books for which `keep` returns false; returns how many went.
+    pub fn retain_books(&mut self, keep: impl Fn(&str) -> bool) -> io::Result<usize> {
+        let before = self.entries.len();
+        let (kept, dropped): (Vec<IndexEntry>, Vec<IndexEntry>) =
+            self.entries.drain(..).partition(|entry| keep(&entry.id));
+        self.entries = kept;
+        for entry in &dropped {
+            self.segments.remove(&entry.segment);
+            let _ = fs::remove_file(self.dir.join(&entry.segment));
+        }
+        if self.entries.len() != before {
+            self.save_manifest()?;
+        }
+        Ok(dropped.len())
+    }
+
+    /// Blocks containing every word of `query`, in reading order per book,
+    /// at most `limit` hits.
+    pub fn search(&mut self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|entry| entry.title.as_deref().map(str::to_lowercase));
+        self.search_entries(entries, query, 0, limit)
+    }
+
+    /// Like `search`, limited to the book with id `book_id` and to its
+    /// chapters from `from_chapter` on.
+    pub fn search_book(
+        &mut self,
+        book_id: &str,
+        query: &str,
+        from_chapter: usize,
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.id == book_id)
+            .cloned()
+            .collect();
+        self.search_entries(entries, query, from_chapter, limit)
+    }
+
+    fn search_entries(
+        &mut self,
+        entries: Vec<IndexEntry>,
+        query: &str,
+        from_chapter: usize,
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        let terms: Vec<String> = words(query).map(|(_, word)| word).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut hits = Vec::new();
+        for entry in entries {
+            if !self.segments.contains_key(&entry.segment) {
+                let Some(segment) = fs::read(self.dir.join(&entry.segment))
+                    .ok()
+                    .and_then(|data| serde_json::from_slice::<BookSegment>(&data).ok())
+                else {
+                    continue;
+                };
+                self.segments.insert(entry.segment.clone(), segment);
+            }
+            let segment = &self.segments[&entry.segment];
+            for (c, b, offset) in segment.matches(&terms) {
+                if c < from_chapter {
+                    continue;
+                }
+                if hits.len() >= limit {
+                    return hits;
+                }
+                let chapter = &segment.chapters[c];
+                let text = &chapter.blocks[b];
+                let (snippet, highlight) = snippet(text, offset);
+                hits.push(SearchHit {
+                    book_id: entry.id.clone(),
+                    book_path: entry.path.clone(),
+                    book_title: entry.title.clone(),
+                    chapter_index: c,
+                    chapter_href: chapter.href.clone(),
+                    chapter_title: chapter.title.clone(),
+                    block_index: b,
+                    char_offset: units_before(text, offset),
+                    snippet,
+                    highlight,
+                });
+            }
+        }
+        hits
+    }
+
+    fn save_manifest(&self) -> io::Result<()> {
+        let data = serde_json::to_string_pretty(&self.entries).unwrap_or_else(|_| "[]".into());
+        write_atomic(&self.dir.join("manifest.json"), data.as_bytes())
+    }
+}
+
+fn segment_name(book_id: &str) -> String {
+    let key = bytes_key(book_id.as_bytes());
+    let hex = key.trim_start_matches("sha256:");
+    format!("{}.json", &hex[..16])
+}
+
+// Readers on other threads never see a half-written file. The temporary name
+// is unique to this process and call, so concurrent writers of the same file
+// (the background indexer and `librarian index`) never rename each other's.
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    static NEXT_TMP: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name,
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = fs::File::create(&tmp)
+        .and_then(|mut f| f.write_all(data))
+        .and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+// Up to SNIPPET_BEFORE chars before the word at `offset` and SNIPPET_AFTER
+// after it, on one line, with ellipses where the block was cut.
+fn snippet(text: &str, offset: usize) -> (String, Range<usize>) {
+    let word_len = text[offset..]
+        .unicode_word_indices()
+        .next()
+        .map(|(_, w)| w.len())
+        .unwrap_or(0);
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_BEFORE.saturating_sub(1))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0);
+    let end = text[offset..]
+        .char_indices()
+        .nth(SNIPPET_AFTER)
+        .map(|(idx, _)| offset + idx)
+        .unwrap_or(text.len());
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let flat = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let before = flat(&text[start..offset]);
+    out.push_str(&before);
+    if !before.is_empty() && text[..offset].ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+    let hl_start = out.len();
+    out.push_str(&text[offset..offset + word_len]);
+    let hl_end = out.len();
+    let after = &text[offset + word_len..end];
+    if after.starts_with(char::is_whitespace) {
+        out.push(' ');
+    }
+    out.push_str(&flat(after));
+    if end < text.len() {
+        out.push('…');
+    }
+    (out, hl_start..hl_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::Catalog;
+
+    fn chapter(href: &str, paragraphs: &[&str]) -> IndexedChapter {
+        let blocks: Vec<Block> = paragraphs
+            .iter()
+            .map(|p| Block::Paragraph(p.to_string()))
+            .collect();
+        IndexedChapter::from_blocks(href, href.to_uppercase(), &blocks)
+    }
+
+    #[test]
+    fn finds_blocks_with_every_term() {
+        let dir = tempfile::tempdir().unwrap();
+        let books = dir.path().join("books");
+        fs::create_dir_all(&books).unwrap();
+        fs::write(books.join("whale.txt"), "Call me Ishmael.").unwrap();
+        let mut catalog = Catalog::default();
+        catalog.add_root(&books);
+        catalog.scan();
+        let book = &catalog.books()[0];
+
+        let index_dir = dir.path().join("index");
+        let mut index = SearchIndex::open_in(&index_dir);
+        assert!(!index.is_current(book));
+        index
+            .add_book(
+                book,
+                vec![
+                    chapter("one", &["Call me Ishmael.", "Some  years ago, never mind"]),
+                    chapter("two", &["How long precisely, the whale"]),
+                ],
+            )
+            .unwrap();
+
+        // Postings survive a reload
+        let mut index = SearchIndex::open_in(&index_dir);
+        assert!(index.is_current(book));
+        let hits = index.search("MIND years", 10);
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!((hit.chapter_href.as_str(), hit.block_index), ("one", 1));
+        assert_eq!(hit.char_offset, 5);
+        assert_eq!(&hit.snippet[hit.highlight.clone()], "years");
+        assert_eq!(hit.location().chapter_href(), Some("one"));
+        assert!(index.search("whale ishmael", 10).is_empty());
+        assert_eq!(index.search_book(book.id(), "whale", 1, 10).len(), 1);
+        assert!(index.search_book(book.id(), "ishmael", 1, 10).is_empty());
+        assert!(index.search_book("dc:other", "whale", 0, 10).is_empty());
+
+        assert_eq!(index.retain_books(|_| false).unwrap(), 1);
+        assert!(index.search("whale", 10).is_empty());
+    }
+
+    #[test]
+    fn list_offsets_count_the_bullets() {
+        let list = Block::List(vec!["one".into(), "two words".into()]);
+        let text = block_text(&list);
+        // "• one" takes five units and one more separates it from the next item
+        assert_eq!(units_before(&text, text.find("words").unwrap()), 12);
+        assert_eq!(
+            words(&text).map(|(_, word)| word).collect::<Vec<_>>(),
+            ["one", "two", "words"]
+        );
+    }
+
+    #[test]
+    fn snippets_are_trimmed_around_the_hit() {
+        let text = format!("{} needle {}", "a ".repeat(50), "b ".repeat(60));
+        let offset = text.find("needle").unwrap();
+        let (snip, range) = snippet(&text, offset);
+        assert!(snip.starts_with('…') && snip.ends_with('…'));
+        assert_eq!(&snip[range], "needle");
+    }
+
+    #[test]
+    fn concurrent_writers_never_share_a_temporary_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        std::thread::scope(|scope| {
+            for writer in 0..4 {
+                let path = &path;
+                scope.spawn(move || {
+                    let data = format!("writer {}", writer).repeat(1000);
+                    for _ in 0..50 {
+                        write_atomic(path, data.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        let written = fs::read_to_string(&path).unwrap();
+        assert!((0..4).any(|writer| written == format!("writer {}", writer).repeat(1000)));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::Location;
+
+const MAX_JUMPS: usize = 100;
+
+/// Places the reader jumped away from, oldest first, stepped through like
+/// vim's Ctrl-O / Ctrl-I.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JumpList {
+    entries: Vec<Location>,
+    index: usize, // entry shown after stepping; `entries.len()` when not stepping
+}
+
+impl JumpList {
+    pub fn entries(&self) -> &[Location] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record `from` before jumping away from it. Entries ahead of the one
+    /// stepped back to are dropped, and an older entry for the same place
+    /// moves to the end.
+    pub fn push(&mut self, from: Location) {
+        self.entries.truncate(self.index);
+        self.entries.retain(|entry| !same_place(entry, &from));
+        self.entries.push(from);
+        let excess = self.entries.len().saturating_sub(MAX_JUMPS);
+        self.entries.drain(..excess);
+        self.index = self.entries.len();
+    }
+
+    /// Step back from `current`, which is kept so `forward` can return to it.
+    pub fn back(&mut self, current: Location) -> Option<Location> {
+        if self.index == self.entries.len() {
+            if !self
+                .entries
+                .last()
+                .is_some_and(|last| same_place(last, &current))
+            {
+                self.entries.push(current);
+            }
+            self.index = self.entries.len() - 1;
+        }
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+        self.entries.get(self.index).cloned()
+    }
+
+    pub fn forward(&mut self) -> Option<Location> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        self.entries.get(self.index).cloned()
+    }
+}
+
+// Locations taken at the top of the same page differ only in the page
+// index saved with them, which changes with the layout.
+fn same_place(a: &Location, b: &Location) -> bool {
+    if a.is_anchored() || b.is_anchored() {
+        a.chapter_href() == b.chapter_href()
+            && a.spine_index() == b.spine_index()
+            && a.block_index() == b.block_index()
+            && a.char_offset() == b.char_offset()
+    } else {
+        a.offset() == b.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(block: usize) -> Location {
+        Location::anchored(0, Some("ch1.xhtml".into()), block, 0, block)
+    }
+
+    #[test]
+    fn steps_back_and_forward_like_vim() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(at(0)), None);
+
+        jumps.push(at(1)); // jumped from 1 to 5
+        jumps.push(at(5)); // jumped from 5 to 9
+        assert_eq!(jumps.back(at(9)), Some(at(5)));
+        assert_eq!(jumps.back(at(5)), Some(at(1)));
+        assert_eq!(jumps.back(at(1)), None);
+        assert_eq!(jumps.forward(), Some(at(5)));
+        assert_eq!(jumps.forward(), Some(at(9)));
+        assert_eq!(jumps.forward(), None);
+
+        // A new jump from an older entry drops the ones ahead of it
+        jumps.back(at(9));
+        jumps.back(at(5));
+        jumps.push(at(1));
+        assert_eq!(jumps.entries(), [at(1)]);
+        assert_eq!(jumps.back(at(3)), Some(at(1)));
+    }
+
+    #[test]
+    fn keeps_one_entry_per_place() {
+        let mut jumps = JumpList::default();
+        jumps.push(at(1));
+        jumps.push(at(2));
+        let mut moved = at(1);
+        moved.set_offset(7); // same text, paginated at another width
+        jumps.push(moved.clone());
+        assert_eq!(jumps.entries(), [at(2), moved]);
+
+        for block in 0..MAX_JUMPS + 10 {
+            jumps.push(at(block + 10));
+        }
+        assert_eq!(jumps.entries().len(), MAX_JUMPS);
+        assert_eq!(jumps.entries()[0], at(20));
+    }
+}
@@ -1,12 +1,16 @@
 use crate::types::Block;
 use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
 
+mod hyphenate;
 mod inline;
 mod paginate;
 mod table;
 mod words;
 
-pub use paginate::{paginate, paginate_with_justify};
+pub use hyphenate::Hyphenator;
+pub(crate) use inline::strip_style_markers;
+pub use paginate::{paginate, paginate_with_justify, paginate_with_options};
 pub use words::extract_words;
 
 #[derive(Clone, Copy)]
@@ -15,9 +19,54 @@ pub struct Size {
     pub height: u16,
 }
 
-#[derive(Clone)]
+/// How paragraphs are broken into lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineBreaking {
+    /// Fill each line before moving on; fast and predictable.
+    #[default]
+    Greedy,
+    /// Knuth–Plass style total fit: choose breaks that minimize badness
+    /// over the whole paragraph.
+    Optimal,
+}
+
+impl LineBreaking {
+    pub fn name(self) -> &'static str {
+        match self {
+            LineBreaking::Greedy => "greedy",
+            LineBreaking::Optimal => "optimal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "greedy" => Some(LineBreaking::Greedy),
+            "optimal" | "knuth-plass" => Some(LineBreaking::Optimal),
+            _ => None,
+        }
+    }
+}
+
+/// Line-breaking choices for pagination.
+#[derive(Clone, Copy, Default)]
+pub struct LayoutOptions<'a> {
+    pub justify: bool,
+    pub hyphenator: Option<&'a Hyphenator>, // None disables hyphenation
+    pub line_breaking: LineBreaking,
+}
+
+#[derive(Clone, Default)]
 pub struct Page {
     pub lines: Vec<StyledLine>,
+    pub positions: Vec<Option<TextPosition>>, // content position of each line; None for spacers
+}
+
+/// A width-independent position in the block stream: the block index plus an
+/// offset counted in graphemes, with each whitespace run counted once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextPosition {
+    pub block: usize,
+    pub offset: usize,
 }
 
 #[derive(Clone)]
@@ -51,12 +100,15 @@ pub struct ImagePlacement {
     pub id: String,
     pub cols: u16,
     pub rows: u16,
+    /// Pixel size of a page image, which is scaled to fit its pane.
+    pub fit: Option<(u32, u32)>,
 }
 
 #[derive(Clone)]
 pub struct Pagination {
     pub pages: Vec<Page>,
     pub chapter_starts: Vec<usize>, // page indices where a chapter begins
+    pub heading_starts: Vec<usize>, // page indices where a heading begins, once each
     pub anchors: HashMap<String, usize>,
 }
 
@@ -83,6 +135,73 @@ impl StyledLine {
     }
 }
 
+impl StyledLine {
+    /// Offset units before each grapheme column, counted like `TextPosition::offset`:
+    /// one per grapheme, with a whitespace run sharing a single unit.
+    pub fn column_units(&self) -> Vec<usize> {
+        column_units(&self.segments).0
+    }
+
+    pub fn text_units(&self) -> usize {
+        column_units(&self.segments).1
+    }
+
+    /// First column at or after `units` into the line.
+    pub fn column_for_units(&self, units: usize) -> usize {
+        let cols = self.column_units();
+        cols.iter().position(|u| *u >= units).unwrap_or(cols.len())
+    }
+}
+
+pub(crate) fn column_units(segments: &[Segment]) -> (Vec<usize>, usize) {
+    let mut cols = Vec::new();
+    let mut count = 0usize;
+    let mut prev_space = false;
+    for seg in segments {
+        for g in seg.text.graphemes(true) {
+            let space = g.chars().all(char::is_whitespace);
+            if space && prev_space {
+                cols.push(count - 1);
+            } else {
+                cols.push(count);
+                count += 1;
+            }
+            prev_space = space;
+        }
+    }
+    (cols, count)
+}
+
+impl Page {
+    pub fn first_position(&self) -> Option<TextPosition> {
+        self.positions.iter().flatten().next().copied()
+    }
+}
+
+/// Page showing `pos`: the last page starting at or before it.
+pub fn page_for_position(pages: &[Page], pos: TextPosition) -> Option<usize> {
+    let mut found = None;
+    for (idx, page) in pages.iter().enumerate() {
+        match page.first_position() {
+            Some(first) if first <= pos => found = Some(idx),
+            Some(_) => break,
+            None => {}
+        }
+    }
+    found.or_else(|| pages.iter().position(|p| p.first_position().is_some()))
+}
+
+/// Index of the first block of each chapter in a concatenated block stream.
+pub fn chapter_block_starts(blocks: &[Block]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for idx in 0..blocks.len() {
+        if is_chapter_separator(blocks, idx) {
+            starts.push(idx + 2);
+        }
+    }
+    starts
+}
+
 pub(crate) fn is_chapter_separator(blocks: &[Block], idx: usize) -> bool {
     let Block::Paragraph(text) = &blocks[idx] else {
         return false;
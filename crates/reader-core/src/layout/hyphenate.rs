@@ -0,0 +1,116 @@
+use hyphenation::{Hyphenator as _, Language, Load, Standard};
+use unicode_segmentation::UnicodeSegmentation;
+
+// Shorter words are never broken
+const MIN_WORD_LEN: usize = 5;
+
+/// TeX-pattern hyphenation for one language, used by the line wrapper to
+/// break long words instead of leaving wide gaps.
+pub struct Hyphenator {
+    dict: Standard,
+}
+
+impl Hyphenator {
+    /// Dictionary for a `dc:language` tag such as `en`, `en-GB` or `de-DE`.
+    /// Books without a language are assumed to be US English; unsupported
+    /// languages get no hyphenation.
+    pub fn for_language(tag: Option<&str>) -> Option<Self> {
+        let lang = match tag.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(tag) => language_for_tag(tag)?,
+            None => Language::EnglishUS,
+        };
+        let dict = Standard::from_embedded(lang).ok()?;
+        Some(Self { dict })
+    }
+
+    pub fn language(&self) -> &'static str {
+        self.dict.language().code()
+    }
+
+    /// Grapheme indices inside `word` where it may be broken with a hyphen.
+    /// Surrounding punctuation is ignored; words containing digits, hyphens
+    /// or other symbols are left whole.
+    pub(crate) fn breaks(&self, word: &str) -> Vec<usize> {
+        let graphemes: Vec<&str> = word.graphemes(true).collect();
+        let is_letter = |g: &str| g.chars().next().is_some_and(char::is_alphabetic);
+        let Some(first) = graphemes.iter().position(|g| is_letter(g)) else {
+            return Vec::new();
+        };
+        let last = graphemes
+            .iter()
+            .rposition(|g| is_letter(g))
+            .unwrap_or(first);
+        let core = &graphemes[first..=last];
+        if core.len() < MIN_WORD_LEN || !core.iter().all(|g| is_letter(g)) {
+            return Vec::new();
+        }
+        let core_text = core.concat();
+        let mut byte_to_grapheme = Vec::with_capacity(core.len() + 1);
+        let mut bytes = 0usize;
+        for (idx, g) in core.iter().enumerate() {
+            byte_to_grapheme.push((bytes, idx));
+            bytes += g.len();
+        }
+        self.dict
+            .hyphenate(&core_text)
+            .breaks
+            .into_iter()
+            .filter_map(|b| {
+                byte_to_grapheme
+                    .iter()
+                    .find(|(offset, _)| *offset == b)
+                    .map(|(_, idx)| first + idx)
+            })
+            .collect()
+    }
+}
+
+fn language_for_tag(tag: &str) -> Option<Language> {
+    let tag = tag.to_ascii_lowercase().replace('_', "-");
+    if let Some(lang) = Language::try_from_code(&tag) {
+        return Some(lang);
+    }
+    let primary = tag.split('-').next().unwrap_or(&tag);
+    match primary {
+        "en" if tag.ends_with("-gb") => Some(Language::EnglishGB),
+        "en" => Some(Language::EnglishUS),
+        "de" if tag.ends_with("-ch") => Some(Language::GermanSwiss),
+        "de" => Some(Language::German1996),
+        "el" => Some(Language::GreekMono),
+        "mn" => Some(Language::Mongolian),
+        "sr" => Some(Language::SerbianCyrillic),
+        _ => Language::try_from_code(primary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_tags_map_to_dictionaries() {
+        let en = Hyphenator::for_language(Some("en")).unwrap();
+        assert_eq!(en.language(), "en-us");
+        let gb = Hyphenator::for_language(Some("en-GB")).unwrap();
+        assert_eq!(gb.language(), "en-gb");
+        let de = Hyphenator::for_language(Some("de-DE")).unwrap();
+        assert_eq!(de.language(), "de-1996");
+        assert_eq!(
+            Hyphenator::for_language(None).map(|h| h.language()),
+            Some("en-us")
+        );
+        assert!(Hyphenator::for_language(Some("tlh")).is_none());
+    }
+
+    #[test]
+    fn breaks_skip_punctuation_and_short_words() {
+        let en = Hyphenator::for_language(Some("en")).unwrap();
+        let plain = en.breaks("hyphenation");
+        assert!(!plain.is_empty());
+        let quoted = en.breaks("\u{201c}hyphenation,");
+        assert_eq!(quoted, plain.iter().map(|b| b + 1).collect::<Vec<_>>());
+        assert!(en.breaks("cat").is_empty());
+        assert!(en.breaks("well-known").is_empty());
+        assert!(en.breaks("1990s").is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Segment, StyledLine, TextStyle};
+use super::{Hyphenator, LineBreaking, Segment, StyledLine, TextStyle};
 use unicode_segmentation::UnicodeSegmentation;
 
 pub(crate) const STYLE_START: char = '\x1E';
@@ -46,6 +46,9 @@ enum InlineToken {
 pub(crate) struct WrappedLines {
     pub(crate) lines: Vec<StyledLine>,
     pub(crate) anchors: Vec<Vec<String>>,
+    // Content offset (in graphemes, whitespace runs count once) where each line starts
+    pub(crate) offsets: Vec<usize>,
+    pub(crate) text_len: usize,
 }
 
 pub(crate) fn strip_style_markers(input: &str) -> String {
@@ -85,10 +88,22 @@ pub(crate) fn strip_style_markers(input: &str) -> String {
 }
 
 pub(crate) fn wrap_styled_text(text: &str, width: usize) -> WrappedLines {
+    wrap_styled_text_with(text, width, None, LineBreaking::Greedy)
+}
+
+pub(crate) fn wrap_styled_text_with(
+    text: &str,
+    width: usize,
+    hyphenator: Option<&Hyphenator>,
+    breaking: LineBreaking,
+) -> WrappedLines {
     let width = width.max(1);
     let pieces = parse_inline_pieces(text);
     let tokens = tokenize_pieces(pieces);
-    wrap_tokens(tokens, width)
+    match breaking {
+        LineBreaking::Greedy => wrap_tokens(tokens, width, hyphenator),
+        LineBreaking::Optimal => wrap_tokens_optimal(tokens, width, hyphenator),
+    }
 }
 
 pub(crate) fn segments_from_text_with_anchors(text: &str) -> (Vec<Segment>, Vec<String>) {
@@ -245,24 +260,34 @@ fn space_segment(style: TextStyle, link: Option<String>) -> Segment {
     }
 }
 
-fn wrap_tokens(tokens: Vec<InlineToken>, width: usize) -> WrappedLines {
+fn wrap_tokens(
+    tokens: Vec<InlineToken>,
+    width: usize,
+    hyphenator: Option<&Hyphenator>,
+) -> WrappedLines {
     let mut lines: Vec<StyledLine> = Vec::new();
     let mut anchors: Vec<Vec<String>> = Vec::new();
+    let mut offsets: Vec<usize> = Vec::new();
     let mut current: Vec<Segment> = Vec::new();
     let mut current_anchors: Vec<String> = Vec::new();
     let mut line_width = 0usize;
     let mut pending_space: Option<(TextStyle, Option<String>)> = None;
+    let mut consumed = 0usize;
+    let mut current_start = 0usize;
 
     let push_current = |lines: &mut Vec<StyledLine>,
                         anchors: &mut Vec<Vec<String>>,
+                        offsets: &mut Vec<usize>,
                         current: &mut Vec<Segment>,
                         current_anchors: &mut Vec<String>,
-                        line_width: &mut usize| {
+                        line_width: &mut usize,
+                        start: usize| {
         lines.push(StyledLine {
             segments: std::mem::take(current),
             image: None,
         });
         anchors.push(std::mem::take(current_anchors));
+        offsets.push(start);
         *line_width = 0;
     };
 
@@ -270,6 +295,7 @@ fn wrap_tokens(tokens: Vec<InlineToken>, width: usize) -> WrappedLines {
         match token {
             InlineToken::Space(style, link) => {
                 pending_space = Some((style, link));
+                consumed += 1;
             }
             InlineToken::Anchor(target) => {
                 if !target.is_empty() {
@@ -281,56 +307,99 @@ fn wrap_tokens(tokens: Vec<InlineToken>, width: usize) -> WrappedLines {
                 push_current(
                     &mut lines,
                     &mut anchors,
+                    &mut offsets,
                     &mut current,
                     &mut current_anchors,
                     &mut line_width,
+                    current_start,
                 );
+                consumed += 1;
+                current_start = consumed;
             }
             InlineToken::Word(word) => {
-                let space_style = pending_space.take();
-                let space_width = if space_style.is_some() && !current.is_empty() {
-                    1
-                } else {
-                    0
-                };
-                if line_width + space_width + word.width <= width {
-                    if let Some((style, link)) = space_style {
-                        if !current.is_empty() {
-                            current.push(space_segment(style, link));
-                            line_width += 1;
+                let mut word = word;
+                let mut word_start = consumed;
+                consumed += word.width;
+                let mut space_style = pending_space.take();
+                loop {
+                    let space_width = if space_style.is_some() && !current.is_empty() {
+                        1
+                    } else {
+                        0
+                    };
+                    if line_width + space_width + word.width <= width {
+                        if current.is_empty() {
+                            current_start = word_start;
                         }
+                        if let Some((style, link)) = space_style.take() {
+                            if !current.is_empty() {
+                                current.push(space_segment(style, link));
+                                line_width += 1;
+                            }
+                        }
+                        current.extend(word.segments);
+                        line_width += word.width;
+                        break;
+                    }
+                    let room = width.saturating_sub(line_width + space_width);
+                    if let Some((head, tail)) =
+                        hyphenator.and_then(|h| hyphenate_word(h, &word, room))
+                    {
+                        if current.is_empty() {
+                            current_start = word_start;
+                        }
+                        if let Some((style, link)) = space_style.take() {
+                            if !current.is_empty() {
+                                current.push(space_segment(style, link));
+                            }
+                        }
+                        // The hyphen is display only and takes no content offset
+                        word_start += head.width - 1;
+                        current.extend(head.segments);
+                        push_current(
+                            &mut lines,
+                            &mut anchors,
+                            &mut offsets,
+                            &mut current,
+                            &mut current_anchors,
+                            &mut line_width,
+                            current_start,
+                        );
+                        word = tail;
+                        continue;
                     }
-                    current.extend(word.segments);
-                    line_width += word.width;
-                } else {
                     if !current.is_empty() {
                         push_current(
                             &mut lines,
                             &mut anchors,
+                            &mut offsets,
                             &mut current,
                             &mut current_anchors,
                             &mut line_width,
+                            current_start,
                         );
+                        space_style = None;
+                        continue;
                     }
-                    if word.width > width {
-                        let parts = split_word_segments(&word.segments, width);
-                        let parts_len = parts.len();
-                        for (idx, part) in parts.into_iter().enumerate() {
-                            if idx + 1 == parts_len {
-                                current = part.segments;
-                                line_width = part.width;
-                            } else {
-                                lines.push(StyledLine {
-                                    segments: part.segments,
-                                    image: None,
-                                });
-                                anchors.push(Vec::new());
-                            }
+                    let parts = split_word_segments(&word.segments, width);
+                    let parts_len = parts.len();
+                    let mut part_start = word_start;
+                    for (idx, part) in parts.into_iter().enumerate() {
+                        if idx + 1 == parts_len {
+                            current = part.segments;
+                            line_width = part.width;
+                            current_start = part_start;
+                        } else {
+                            lines.push(StyledLine {
+                                segments: part.segments,
+                                image: None,
+                            });
+                            anchors.push(Vec::new());
+                            offsets.push(part_start);
+                            part_start += part.width;
                         }
-                    } else {
-                        current = word.segments;
-                        line_width = word.width;
                     }
+                    break;
                 }
                 pending_space = None;
             }
@@ -343,8 +412,274 @@ fn wrap_tokens(tokens: Vec<InlineToken>, width: usize) -> WrappedLines {
             image: None,
         });
         anchors.push(current_anchors);
+        offsets.push(current_start);
     }
-    WrappedLines { lines, anchors }
+    WrappedLines {
+        lines,
+        anchors,
+        offsets,
+        text_len: consumed,
+    }
+}
+
+// Demerits for a line ending in a hyphen, and the cap on line badness
+const HYPHEN_PENALTY: f64 = 50.0;
+const MAX_BADNESS: f64 = 10_000.0;
+
+// What a line break before a fragment removes or adds
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Boundary {
+    Space,  // a space, dropped at a break
+    Hyphen, // inside a word; a hyphen is shown at a break
+    Plain,  // nothing either way
+}
+
+struct Fragment {
+    segments: Vec<Segment>,
+    width: usize,
+    start: usize, // content offset
+    before: Boundary,
+    space: Option<(TextStyle, Option<String>)>,
+}
+
+impl Boundary {
+    fn joined_width(self) -> usize {
+        match self {
+            Boundary::Space => 1,
+            Boundary::Hyphen | Boundary::Plain => 0,
+        }
+    }
+}
+
+// Total-fit line breaking in the manner of Knuth and Plass: every space and
+// hyphenation point is a candidate break, and the chosen set minimizes the
+// summed demerits of all lines in the paragraph. Offsets and anchors follow
+// the same rules as the greedy wrapper.
+fn wrap_tokens_optimal(
+    tokens: Vec<InlineToken>,
+    width: usize,
+    hyphenator: Option<&Hyphenator>,
+) -> WrappedLines {
+    let mut out = WrappedLines {
+        lines: Vec::new(),
+        anchors: Vec::new(),
+        offsets: Vec::new(),
+        text_len: 0,
+    };
+    let mut fragments: Vec<Fragment> = Vec::new();
+    let mut chunk_anchors: Vec<(usize, String)> = Vec::new();
+    let mut pending_space: Option<(TextStyle, Option<String>)> = None;
+    let mut consumed = 0usize;
+    let mut chunk_start = 0usize;
+
+    for token in tokens {
+        match token {
+            InlineToken::Space(style, link) => {
+                pending_space = Some((style, link));
+                consumed += 1;
+            }
+            InlineToken::Anchor(target) => {
+                if !target.is_empty() {
+                    chunk_anchors.push((fragments.len(), target));
+                }
+            }
+            InlineToken::Newline => {
+                let fragments = std::mem::take(&mut fragments);
+                let anchors = std::mem::take(&mut chunk_anchors);
+                break_chunk(&mut out, fragments, anchors, chunk_start, width);
+                pending_space = None;
+                consumed += 1;
+                chunk_start = consumed;
+            }
+            InlineToken::Word(word) => {
+                let word_width = word.width;
+                let space = pending_space.take().filter(|_| !fragments.is_empty());
+                let before = if space.is_some() {
+                    Boundary::Space
+                } else {
+                    Boundary::Plain
+                };
+                push_word_fragments(
+                    &mut fragments,
+                    word,
+                    consumed,
+                    before,
+                    space,
+                    width,
+                    hyphenator,
+                );
+                consumed += word_width;
+            }
+        }
+    }
+    if !fragments.is_empty() || out.lines.is_empty() || !chunk_anchors.is_empty() {
+        break_chunk(&mut out, fragments, chunk_anchors, chunk_start, width);
+    }
+    out.text_len = consumed;
+    out
+}
+
+fn push_word_fragments(
+    fragments: &mut Vec<Fragment>,
+    word: InlineWord,
+    start: usize,
+    before: Boundary,
+    space: Option<(TextStyle, Option<String>)>,
+    width: usize,
+    hyphenator: Option<&Hyphenator>,
+) {
+    let text: String = word.segments.iter().map(|s| s.text.as_str()).collect();
+    let breaks = hyphenator.map(|h| h.breaks(&text)).unwrap_or_default();
+    let mut rest = word.segments;
+    let mut taken = 0usize;
+    let mut boundary = before;
+    let mut space = space;
+    let ends = breaks.into_iter().chain(std::iter::once(word.width));
+    for end in ends {
+        let (head, tail) = split_segments_at(&rest, end - taken);
+        rest = tail;
+        // Pieces wider than a line are cut like the greedy wrapper does
+        for (idx, part) in split_word_segments(&head, width).into_iter().enumerate() {
+            fragments.push(Fragment {
+                width: part.width,
+                segments: part.segments,
+                start: start + taken,
+                before: if idx == 0 { boundary } else { Boundary::Plain },
+                space: space.take(),
+            });
+            taken += part.width;
+        }
+        boundary = Boundary::Hyphen;
+    }
+}
+
+fn break_chunk(
+    out: &mut WrappedLines,
+    fragments: Vec<Fragment>,
+    anchors: Vec<(usize, String)>,
+    chunk_start: usize,
+    width: usize,
+) {
+    if fragments.is_empty() {
+        out.lines.push(StyledLine {
+            segments: Vec::new(),
+            image: None,
+        });
+        out.anchors
+            .push(anchors.into_iter().map(|(_, a)| a).collect());
+        out.offsets.push(chunk_start);
+        return;
+    }
+    let breaks = optimal_breaks(&fragments, width);
+    let mut line_anchors: Vec<Vec<String>> = vec![Vec::new(); breaks.len()];
+    for (at, anchor) in anchors {
+        let line = breaks
+            .iter()
+            .position(|(_, end)| at < *end)
+            .unwrap_or(breaks.len() - 1);
+        line_anchors[line].push(anchor);
+    }
+    let hyphenated: Vec<bool> = breaks
+        .iter()
+        .map(|(_, end)| {
+            fragments
+                .get(*end)
+                .is_some_and(|f| f.before == Boundary::Hyphen)
+        })
+        .collect();
+    let mut fragments = fragments.into_iter();
+    for (((start, end), anchors), hyphenated) in
+        breaks.into_iter().zip(line_anchors).zip(hyphenated)
+    {
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut offset = 0usize;
+        for (idx, frag) in fragments.by_ref().take(end - start).enumerate() {
+            if idx == 0 {
+                offset = frag.start;
+            } else if let Some((style, link)) = frag.space {
+                segments.push(space_segment(style, link));
+            }
+            segments.extend(frag.segments);
+        }
+        if hyphenated {
+            if let Some(last) = segments.last_mut() {
+                last.text.push('-');
+            }
+        }
+        out.lines.push(StyledLine {
+            segments,
+            image: None,
+        });
+        out.anchors.push(anchors);
+        out.offsets.push(offset);
+    }
+}
+
+// Fragment ranges `[start, end)` of each line with the least total demerits
+fn optimal_breaks(fragments: &[Fragment], width: usize) -> Vec<(usize, usize)> {
+    let n = fragments.len();
+    // Width of fragments[..k] laid out on one line, and spaces among them
+    let mut prefix = vec![0usize; n + 1];
+    let mut spaces = vec![0usize; n + 1];
+    for (k, frag) in fragments.iter().enumerate() {
+        let join = if k == 0 {
+            0
+        } else {
+            frag.before.joined_width()
+        };
+        prefix[k + 1] = prefix[k] + join + frag.width;
+        spaces[k + 1] = spaces[k] + usize::from(k > 0 && frag.before == Boundary::Space);
+    }
+    let mut best = vec![f64::INFINITY; n + 1];
+    let mut from = vec![0usize; n + 1];
+    best[0] = 0.0;
+    for end in 1..=n {
+        let hyphen = end < n && fragments[end].before == Boundary::Hyphen;
+        for start in (0..end).rev() {
+            if !best[start].is_finite() {
+                continue;
+            }
+            let first = &fragments[start];
+            let leading = if start == 0 {
+                0
+            } else {
+                first.before.joined_width()
+            };
+            let line_width = prefix[end] - prefix[start] - leading + usize::from(hyphen);
+            let gaps = spaces[end] - spaces[start + 1];
+            let overfull = line_width > width;
+            if overfull && start + 1 < end {
+                break;
+            }
+            let mut demerits = if overfull {
+                MAX_BADNESS * MAX_BADNESS
+            } else if end == n {
+                0.0
+            } else {
+                let slack = (width - line_width) as f64;
+                let ratio = slack / gaps.max(1) as f64;
+                let badness = (100.0 * ratio.powi(3)).min(MAX_BADNESS);
+                (1.0 + badness).powi(2)
+            };
+            if hyphen {
+                demerits += HYPHEN_PENALTY * HYPHEN_PENALTY;
+            }
+            let total = best[start] + demerits;
+            if total < best[end] {
+                best[end] = total;
+                from[end] = start;
+            }
+        }
+    }
+    let mut breaks = Vec::new();
+    let mut end = n;
+    while end > 0 {
+        let start = from[end];
+        breaks.push((start, end));
+        end = start;
+    }
+    breaks.reverse();
+    breaks
 }
 
 fn parse_inline_pieces(text: &str) -> Vec<InlinePiece> {
@@ -612,3 +947,52 @@ fn split_word_segments(segments: &[Segment], width: usize) -> Vec<InlineWord> {
     }
     parts
 }
+
+// Split `word` at the last hyphenation point whose head, plus a hyphen,
+// fits in `room` columns. The head carries the hyphen.
+fn hyphenate_word(
+    hyphenator: &Hyphenator,
+    word: &InlineWord,
+    room: usize,
+) -> Option<(InlineWord, InlineWord)> {
+    if room < 2 {
+        return None;
+    }
+    let text: String = word.segments.iter().map(|s| s.text.as_str()).collect();
+    let split = hyphenator.breaks(&text).into_iter().rfind(|b| *b < room)?;
+    let (mut head, tail) = split_segments_at(&word.segments, split);
+    let last = head.last_mut()?;
+    last.text.push('-');
+    Some((
+        InlineWord {
+            segments: head,
+            width: split + 1,
+        },
+        InlineWord {
+            segments: tail,
+            width: word.width - split,
+        },
+    ))
+}
+
+fn split_segments_at(segments: &[Segment], at: usize) -> (Vec<Segment>, Vec<Segment>) {
+    let mut head: Vec<Segment> = Vec::new();
+    let mut tail: Vec<Segment> = Vec::new();
+    let mut used = 0usize;
+    for seg in segments {
+        let graphemes: Vec<&str> = seg.text.graphemes(true).collect();
+        let take = at.saturating_sub(used).min(graphemes.len());
+        used += graphemes.len();
+        for (part, out) in [
+            (graphemes[..take].concat(), &mut head),
+            (graphemes[take..].concat(), &mut tail),
+        ] {
+            if !part.is_empty() {
+                let mut piece = seg.clone();
+                piece.text = part;
+                out.push(piece);
+            }
+        }
+    }
+    (head, tail)
+}
@@ -4,24 +4,45 @@ use std::collections::HashMap;
 
 use super::inline::{
     clip_segments, justify_styled_line, segments_from_text_with_anchors, uppercase_segments,
-    wrap_styled_text,
+    wrap_styled_text, wrap_styled_text_with,
 };
-use super::is_chapter_separator;
 use super::table::render_table;
-use super::{ImagePlacement, Page, Pagination, Segment, Size, StyledLine, TextStyle};
+use super::{column_units, is_chapter_separator};
+use super::{
+    ImagePlacement, LayoutOptions, Page, Pagination, Segment, Size, StyledLine, TextPosition,
+    TextStyle,
+};
 
 pub fn paginate(blocks: &[Block], size: Size) -> Vec<Page> {
     paginate_with_justify(blocks, size, false).pages
 }
 
 pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pagination {
-    // Greedy wrap with optional full justification
+    paginate_with_options(
+        blocks,
+        size,
+        LayoutOptions {
+            justify,
+            ..LayoutOptions::default()
+        },
+    )
+}
+
+pub fn paginate_with_options(blocks: &[Block], size: Size, options: LayoutOptions) -> Pagination {
+    // Greedy wrap with optional hyphenation and full justification
+    let LayoutOptions {
+        justify,
+        hyphenator,
+        line_breaking,
+    } = options;
     let mut pages: Vec<Page> = Vec::new();
-    let mut current = Page { lines: Vec::new() };
+    let mut current = Page::default();
     let mut chapter_starts: Vec<usize> = Vec::new();
+    let mut heading_starts: Vec<usize> = Vec::new();
     let mut anchors: HashMap<String, usize> = HashMap::new();
     let mut at_page_index: usize = pages.len();
     let push_line = |line: StyledLine,
+                     position: Option<TextPosition>,
                      line_anchors: &[String],
                      pages: &mut Vec<Page>,
                      current: &mut Page,
@@ -31,25 +52,30 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
             anchors.entry(anchor.clone()).or_insert(*at_page_index);
         }
         current.lines.push(line);
+        current.positions.push(position);
         if current.lines.len() as u16 >= size.height {
-            pages.push(current.clone());
-            *current = Page { lines: Vec::new() };
+            pages.push(std::mem::take(current));
             *at_page_index += 1;
         }
     };
     let mut pending_chapter_start: Option<usize> = Some(0); // initial chapter starts at page 0
     for (idx, block) in blocks.iter().enumerate() {
+        let at = |offset: usize| Some(TextPosition { block: idx, offset });
         match block {
             Block::Paragraph(text) => {
-                // If a separator was seen, mark the next content start as a chapter start
-                if let Some(start_idx) = pending_chapter_start.take() {
-                    chapter_starts.push(start_idx);
+                // If a separator was seen, mark the next content start as a chapter start;
+                // the blank line after the separator is not content yet
+                if !text.trim().is_empty() {
+                    if let Some(start_idx) = pending_chapter_start.take() {
+                        chapter_starts.push(start_idx);
+                    }
                 }
                 // Detect separator and set the next start index
                 if is_chapter_separator(blocks, idx) {
                     pending_chapter_start = Some(at_page_index);
                 }
-                let wrapped = wrap_styled_text(text, size.width as usize);
+                let wrapped =
+                    wrap_styled_text_with(text, size.width as usize, hyphenator, line_breaking);
                 for i in 0..wrapped.lines.len() {
                     let is_last = i == wrapped.lines.len().saturating_sub(1);
                     let line = if justify && !is_last {
@@ -60,6 +86,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                     let anchors_for_line = &wrapped.anchors[i];
                     push_line(
                         line,
+                        at(wrapped.offsets[i]),
                         anchors_for_line,
                         &mut pages,
                         &mut current,
@@ -70,6 +97,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 // blank line between paragraphs
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -86,8 +114,10 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 let prefix = if show_rule { "│ " } else { "  " };
                 let max_width = size.width.max(4) as usize;
                 // Preserve line breaks like a code/pre block; truncate when too long
+                let mut offset = 0usize;
                 for raw_line in text.lines() {
                     let (mut segs, line_anchors) = segments_from_text_with_anchors(raw_line);
+                    let line_start = offset;
                     let mut prefixed = Vec::with_capacity(segs.len() + 1);
                     prefixed.push(Segment {
                         text: prefix.to_string(),
@@ -97,9 +127,12 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                         link: None,
                     });
                     prefixed.append(&mut segs);
+                    // Lines are clipped, not wrapped, so whole-line units are width independent
+                    offset += column_units(&prefixed).1 + 1;
                     let clipped = clip_segments(prefixed, max_width);
                     push_line(
                         clipped,
+                        at(line_start),
                         &line_anchors,
                         &mut pages,
                         &mut current,
@@ -109,6 +142,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -120,12 +154,16 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 if let Some(start_idx) = pending_chapter_start.take() {
                     chapter_starts.push(start_idx);
                 }
+                if heading_starts.last() != Some(&at_page_index) {
+                    heading_starts.push(at_page_index);
+                }
                 let mut wrapped = wrap_styled_text(text, size.width as usize);
                 for i in 0..wrapped.lines.len() {
                     uppercase_segments(&mut wrapped.lines[i].segments);
                     let anchors_for_line = &wrapped.anchors[i];
                     push_line(
                         wrapped.lines[i].clone(),
+                        at(wrapped.offsets[i]),
                         anchors_for_line,
                         &mut pages,
                         &mut current,
@@ -135,6 +173,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -146,9 +185,18 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 if let Some(start_idx) = pending_chapter_start.take() {
                     chapter_starts.push(start_idx);
                 }
+                // Item offsets accumulate so positions stay ordered across the whole list
+                let mut base = 0usize;
                 for item in items {
                     let line = format!("• {}", item);
-                    let wrapped = wrap_styled_text(&line, size.width as usize);
+                    let wrapped = wrap_styled_text_with(
+                        &line,
+                        size.width as usize,
+                        hyphenator,
+                        line_breaking,
+                    );
+                    let item_base = base;
+                    base += wrapped.text_len + 1;
                     for i in 0..wrapped.lines.len() {
                         let is_last = i == wrapped.lines.len().saturating_sub(1);
                         let out = if justify && !is_last {
@@ -159,6 +207,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                         let anchors_for_line = &wrapped.anchors[i];
                         push_line(
                             out,
+                            at(item_base + wrapped.offsets[i]),
                             anchors_for_line,
                             &mut pages,
                             &mut current,
@@ -169,6 +218,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -181,9 +231,10 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                     chapter_starts.push(start_idx);
                 }
                 let table_lines = render_table(table, size.width as usize);
-                for (line, line_anchors) in table_lines {
+                for (row, (line, line_anchors)) in table_lines.into_iter().enumerate() {
                     push_line(
                         line,
+                        at(row),
                         &line_anchors,
                         &mut pages,
                         &mut current,
@@ -193,6 +244,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -208,7 +260,9 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 let prefix = if show_rule { "│ " } else { "  " };
                 let max_width = size.width as usize;
                 let highlighted = highlight::highlight_code(lang.as_deref(), text);
+                let mut offset = 0usize;
                 for line in highlighted {
+                    let line_start = offset;
                     let mut segs = Vec::new();
                     segs.push(Segment {
                         text: prefix.to_string(),
@@ -226,9 +280,11 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                             link: None,
                         });
                     }
+                    offset += column_units(&segs).1 + 1;
                     let clipped = clip_segments(segs, max_width.max(4));
                     push_line(
                         clipped,
+                        at(line_start),
                         &[],
                         &mut pages,
                         &mut current,
@@ -238,6 +294,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -246,6 +303,39 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 );
             }
             Block::Image(image) => {
+                if image.is_page() && image.data().is_some() {
+                    // A page image gets a page of its own and is scaled to fit it
+                    if !current.lines.is_empty() {
+                        pages.push(std::mem::take(&mut current));
+                        at_page_index += 1;
+                    }
+                    // so a chapter opening on it starts at that page
+                    if pending_chapter_start.take().is_some() {
+                        chapter_starts.push(at_page_index);
+                    }
+                    let blank = " ".repeat(size.width as usize);
+                    for row in 0..size.height.max(1) {
+                        let mut line = StyledLine::from_plain(blank.clone());
+                        if row == 0 {
+                            line.image = Some(ImagePlacement {
+                                id: image.id().to_string(),
+                                cols: size.width.max(1),
+                                rows: size.height.max(1),
+                                fit: image.width().zip(image.height()),
+                            });
+                        }
+                        push_line(
+                            line,
+                            at(0),
+                            &[],
+                            &mut pages,
+                            &mut current,
+                            &mut at_page_index,
+                            &mut anchors,
+                        );
+                    }
+                    continue;
+                }
                 if let Some(start_idx) = pending_chapter_start.take() {
                     chapter_starts.push(start_idx);
                 }
@@ -268,10 +358,12 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                                 id: image.id().to_string(),
                                 cols,
                                 rows,
+                                fit: None,
                             });
                         }
                         push_line(
                             line,
+                            at(0),
                             &[],
                             &mut pages,
                             &mut current,
@@ -286,6 +378,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                         let anchors_for_line = &wrapped.anchors[i];
                         push_line(
                             wrapped.lines[i].clone(),
+                            at(wrapped.offsets[i]),
                             anchors_for_line,
                             &mut pages,
                             &mut current,
@@ -296,6 +389,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
                 }
                 push_line(
                     StyledLine::from_plain(String::new()),
+                    None,
                     &[],
                     &mut pages,
                     &mut current,
@@ -311,6 +405,7 @@ pub fn paginate_with_justify(blocks: &[Block], size: Size, justify: bool) -> Pag
     Pagination {
         pages,
         chapter_starts,
+        heading_starts,
         anchors,
     }
 }
@@ -326,3 +421,312 @@ fn image_rows_from_dims(width: Option<u32>, height: Option<u32>, cols: u16, max_
     rows = rows.max(3);
     rows.min(max_rows.max(3))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{chapter_block_starts, page_for_position, Hyphenator, LineBreaking};
+    use crate::types::ImageBlock;
+
+    fn sample_blocks() -> Vec<Block> {
+        let sentence = "The quick brown fox jumps over the lazy dog. ";
+        vec![
+            Block::Heading("Opening".to_string(), 1),
+            Block::Paragraph(sentence.repeat(12)),
+            Block::Paragraph(String::new()),
+            Block::Paragraph("───".to_string()),
+            Block::Paragraph(String::new()),
+            Block::List(vec![sentence.repeat(3), sentence.repeat(4)]),
+            Block::Paragraph(sentence.repeat(8)),
+        ]
+    }
+
+    #[test]
+    fn wrapped_lines_carry_increasing_positions() {
+        let blocks = sample_blocks();
+        let pagination = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 30,
+                height: 10,
+            },
+            false,
+        );
+        let positions: Vec<TextPosition> = pagination
+            .pages
+            .iter()
+            .flat_map(|page| page.positions.iter().flatten().copied())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(positions.first(), Some(&TextPosition::default()));
+        for page in &pagination.pages {
+            assert_eq!(page.lines.len(), page.positions.len());
+        }
+    }
+
+    #[test]
+    fn position_survives_reflow_at_another_width() {
+        let blocks = sample_blocks();
+        let wide = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 60,
+                height: 8,
+            },
+            true,
+        );
+        let narrow = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 25,
+                height: 8,
+            },
+            false,
+        );
+        for page in &wide.pages {
+            let Some(pos) = page.first_position() else {
+                continue;
+            };
+            let idx = page_for_position(&narrow.pages, pos).expect("page for position");
+            let target = &narrow.pages[idx];
+            let first = target.first_position().unwrap();
+            assert!(first <= pos);
+            if let Some(next) = narrow.pages.get(idx + 1).and_then(|p| p.first_position()) {
+                assert!(next > pos);
+            }
+            // The anchored block is on the resolved page
+            let line = target
+                .positions
+                .iter()
+                .rposition(|p| p.is_some_and(|p| p <= pos))
+                .unwrap();
+            assert_eq!(target.positions[line].unwrap().block, pos.block);
+        }
+    }
+
+    #[test]
+    fn chapter_block_starts_follow_separators() {
+        let blocks = sample_blocks();
+        assert_eq!(chapter_block_starts(&blocks), vec![0, 5]);
+    }
+
+    #[test]
+    fn heading_starts_record_each_page_once() {
+        let sentence = "The quick brown fox jumps over the lazy dog. ";
+        let blocks = vec![
+            Block::Heading("One".to_string(), 1),
+            Block::Paragraph(sentence.repeat(12)),
+            Block::Heading("Two".to_string(), 2),
+            Block::Heading("Three".to_string(), 2),
+            Block::Paragraph(sentence.repeat(12)),
+        ];
+        let pagination = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 30,
+                height: 10,
+            },
+            false,
+        );
+        let heading_page = |block: usize| {
+            pagination
+                .pages
+                .iter()
+                .position(|page| page.positions.iter().flatten().any(|p| p.block == block))
+                .unwrap()
+        };
+        assert_eq!(heading_page(2), heading_page(3));
+        assert_eq!(
+            pagination.heading_starts,
+            vec![heading_page(0), heading_page(2)]
+        );
+        assert!(heading_page(2) > 0);
+    }
+
+    #[test]
+    fn hyphenation_breaks_long_words_and_keeps_offsets() {
+        let text = "Extraordinary representations of incomprehensible circumstances.";
+        let blocks = vec![Block::Paragraph(text.to_string())];
+        let size = Size {
+            width: 16,
+            height: 20,
+        };
+        let hyphenator = Hyphenator::for_language(Some("en")).unwrap();
+        let plain = paginate_with_justify(&blocks, size, false);
+        let hyphenated = paginate_with_options(
+            &blocks,
+            size,
+            LayoutOptions {
+                justify: false,
+                hyphenator: Some(&hyphenator),
+                ..LayoutOptions::default()
+            },
+        );
+        let line_texts = |p: &Pagination| -> Vec<String> {
+            p.pages[0]
+                .lines
+                .iter()
+                .map(|l| l.segments.iter().map(|s| s.text.as_str()).collect())
+                .filter(|t: &String| !t.is_empty())
+                .collect()
+        };
+        let lines = line_texts(&hyphenated);
+        assert_eq!(line_texts(&plain)[2], "of");
+        assert_eq!(lines[2], "of incomprehen-");
+        assert!(lines.iter().any(|l| l.ends_with('-')));
+        assert!(lines.iter().all(|l| l.chars().count() <= 16));
+        let joined: String = lines
+            .iter()
+            .map(|l| {
+                l.strip_suffix('-')
+                    .map_or(format!("{} ", l), str::to_string)
+            })
+            .collect();
+        assert_eq!(joined.trim_end(), text);
+        // Each line starts at the content offset of its first grapheme
+        let page = &hyphenated.pages[0];
+        for (line, pos) in page.lines.iter().zip(&page.positions) {
+            let Some(pos) = pos else {
+                continue;
+            };
+            let line_text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+            let head: String = line_text.chars().take(3).collect();
+            let expected: String = text.chars().skip(pos.offset).take(3).collect();
+            assert_eq!(head, expected);
+        }
+    }
+
+    fn text_lines(pagination: &Pagination) -> Vec<(String, Option<TextPosition>)> {
+        pagination
+            .pages
+            .iter()
+            .flat_map(|page| page.lines.iter().zip(&page.positions))
+            .map(|(line, pos)| {
+                (
+                    line.segments.iter().map(|s| s.text.as_str()).collect(),
+                    *pos,
+                )
+            })
+            .filter(|(text, _): &(String, _)| !text.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn optimal_breaking_evens_out_ragged_lines() {
+        // Greedy fills "aaa bb" and strands "cc" on a line of its own
+        let text = "aaa bb cc ddddd";
+        let blocks = vec![Block::Paragraph(text.to_string())];
+        let size = Size {
+            width: 6,
+            height: 40,
+        };
+        let greedy = text_lines(&paginate_with_justify(&blocks, size, false));
+        let optimal = text_lines(&paginate_with_options(
+            &blocks,
+            size,
+            LayoutOptions {
+                line_breaking: LineBreaking::Optimal,
+                ..LayoutOptions::default()
+            },
+        ));
+        let words = |lines: &[(String, Option<TextPosition>)]| -> Vec<String> {
+            lines
+                .iter()
+                .flat_map(|(l, _)| l.split_whitespace().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(words(&optimal), words(&greedy));
+        assert!(optimal.iter().all(|(l, _)| l.chars().count() <= 6));
+        let raggedness = |lines: &[(String, Option<TextPosition>)]| -> usize {
+            lines[..lines.len() - 1]
+                .iter()
+                .map(|(l, _)| (6 - l.chars().count()).pow(2))
+                .sum()
+        };
+        assert!(raggedness(&optimal) < raggedness(&greedy));
+        assert_eq!(optimal[0].0, "aaa");
+        for (line, pos) in &optimal {
+            let expected: String = text.chars().skip(pos.unwrap().offset).take(3).collect();
+            assert_eq!(&line[..3], expected);
+        }
+    }
+
+    #[test]
+    fn optimal_breaking_uses_hyphenation_points() {
+        let text = "Extraordinary representations of incomprehensible circumstances.";
+        let blocks = vec![Block::Paragraph(text.to_string())];
+        let hyphenator = Hyphenator::for_language(Some("en")).unwrap();
+        let lines = text_lines(&paginate_with_options(
+            &blocks,
+            Size {
+                width: 16,
+                height: 20,
+            },
+            LayoutOptions {
+                hyphenator: Some(&hyphenator),
+                line_breaking: LineBreaking::Optimal,
+                ..LayoutOptions::default()
+            },
+        ));
+        assert!(lines.iter().any(|(l, _)| l.ends_with('-')));
+        assert!(lines.iter().all(|(l, _)| l.chars().count() <= 16));
+        let joined: String = lines
+            .iter()
+            .map(|(l, _)| {
+                l.strip_suffix('-')
+                    .map_or(format!("{} ", l), str::to_string)
+            })
+            .collect();
+        assert_eq!(joined.trim_end(), text);
+    }
+
+    #[test]
+    fn page_images_fill_a_page_each() {
+        let page =
+            |id: &str| Block::Image(ImageBlock::full_page(id, vec![0], Some(600), Some(900)));
+        let blocks = vec![
+            Block::Paragraph("Cover note.".to_string()),
+            page("p1"),
+            page("p2"),
+        ];
+        let pagination = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 40,
+                height: 12,
+            },
+            false,
+        );
+        assert_eq!(pagination.pages.len(), 3);
+        for (page, id) in pagination.pages[1..].iter().zip(["p1", "p2"]) {
+            assert_eq!(page.lines.len(), 12);
+            let placement = page.lines[0].image.as_ref().unwrap();
+            assert_eq!(placement.id, id);
+            assert_eq!((placement.cols, placement.rows), (40, 12));
+            assert_eq!(placement.fit, Some((600, 900)));
+        }
+    }
+
+    #[test]
+    fn chapters_opening_on_a_page_image_start_at_its_page() {
+        let blank = || Block::Paragraph(String::new());
+        let blocks = vec![
+            Block::Paragraph("Cover note.".to_string()),
+            blank(),
+            Block::Paragraph("───".to_string()),
+            blank(),
+            Block::Image(ImageBlock::full_page("p1", vec![0], Some(600), Some(900))),
+        ];
+        let pagination = paginate_with_justify(
+            &blocks,
+            Size {
+                width: 40,
+                height: 12,
+            },
+            false,
+        );
+        assert_eq!(pagination.pages.len(), 2);
+        assert!(pagination.pages[1].lines[0].image.is_some());
+        assert_eq!(pagination.chapter_starts, vec![0, 1]);
+    }
+}
@@ -1,9 +1,20 @@
+pub mod annotations;
+pub mod cbz;
 pub mod config;
 pub mod epub;
+pub mod export;
+pub mod fb2;
+pub mod html;
+pub mod identity;
+pub mod index;
+pub mod jumps;
 pub mod layout;
+pub mod library;
+pub mod mobi;
 pub mod nav;
 pub mod normalize;
 pub mod pdf;
+pub mod search;
 pub mod types;
 
 pub mod state;
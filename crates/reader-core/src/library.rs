@@ -0,0 +1,520 @@
+use crate::{
+    cbz::{self, CbzBook},
+    config,
+    epub::EpubBook,
+    fb2::{self, Fb2Book},
+    html::HtmlBook,
+    identity::book_key,
+    mobi::{self, MobiBook},
+    pdf::PdfLoader,
+    text,
+    types::{BookId, DocumentFormat},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// One catalogued book. `size` and `modified` are what the file looked like
+/// when its metadata was read; a scan only re-reads files where they differ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryBook {
+    id: String,
+    path: String,
+    format: DocumentFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    series: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    series_index: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chapter_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page_count: Option<usize>,
+    size: u64,
+    modified: u64,
+}
+
+impl LibraryBook {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn format(&self) -> DocumentFormat {
+        self.format
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn series(&self) -> Option<&str> {
+        self.series.as_deref()
+    }
+
+    pub fn series_index(&self) -> Option<f32> {
+        self.series_index
+    }
+
+    /// Spine length of an EPUB.
+    pub fn chapter_count(&self) -> Option<usize> {
+        self.chapter_count
+    }
+
+    pub fn page_count(&self) -> Option<usize> {
+        self.page_count
+    }
+
+    /// File size and modification time (ms) when the book was catalogued.
+    pub fn file_stamp(&self) -> (u64, u64) {
+        (self.size, self.modified)
+    }
+
+    /// Title for lists: the catalogued title, else the file name.
+    pub fn display_title(&self) -> &str {
+        self.title().unwrap_or_else(|| {
+            Path::new(&self.path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&self.path)
+        })
+    }
+
+    /// Identity used by saved state and annotations for this book.
+    pub fn book_id(&self) -> BookId {
+        BookId::new(
+            self.id.clone(),
+            self.path.clone(),
+            Some(self.display_title().to_string()),
+            self.format,
+        )
+    }
+
+    fn read(path: &Path, format: DocumentFormat, size: u64, modified: u64) -> Option<Self> {
+        let mut book = Self {
+            id: String::new(),
+            path: path.display().to_string(),
+            format,
+            title: None,
+            author: None,
+            series: None,
+            series_index: None,
+            chapter_count: None,
+            page_count: None,
+            size,
+            modified,
+        };
+        let mut identifier = None;
+        match format {
+            DocumentFormat::Epub3 | DocumentFormat::Epub2 => {
+                let epub = EpubBook::open(path).ok()?;
+                book.chapter_count = Some(epub.chapter_count());
+                let meta = &epub.metadata;
+                book.title = meta.main_title().map(str::to_string).or(epub.title);
+                book.author = meta.author_string().or(epub.author);
+                if let Some(series) = meta.series() {
+                    book.series = Some(series.name().to_string());
+                    book.series_index = series.index();
+                }
+                identifier = meta.identifier().map(str::to_string);
+            }
+            DocumentFormat::Pdf => {
+                let pdf = PdfLoader::open(path).ok()?;
+                let summary = pdf.summary();
+                book.title = summary.title.clone().filter(|t| !t.trim().is_empty());
+                book.author = summary.author.clone().filter(|a| !a.trim().is_empty());
+                book.page_count = Some(summary.page_count);
+            }
+            DocumentFormat::Fb2 => {
+                let fb2 = Fb2Book::open(path).ok()?;
+                book.chapter_count = Some(fb2.chapter_count());
+                let meta = &fb2.metadata;
+                book.title = meta.main_title().map(str::to_string);
+                book.author = meta.author_string();
+                if let Some(series) = meta.series() {
+                    book.series = Some(series.name().to_string());
+                    book.series_index = series.index();
+                }
+                identifier = meta.identifier().map(str::to_string);
+            }
+            DocumentFormat::Mobi => {
+                let mobi = MobiBook::open(path).ok()?;
+                book.chapter_count = Some(mobi.chapter_count());
+                let meta = &mobi.metadata;
+                book.title = meta.main_title().map(str::to_string);
+                book.author = meta.author_string();
+                identifier = meta.identifier().map(str::to_string);
+            }
+            DocumentFormat::Cbz => {
+                let (meta, page_count) = CbzBook::read_metadata(path).ok()?;
+                book.page_count = Some(page_count);
+                book.title = meta
+                    .main_title()
+                    .map(str::to_string)
+                    .or_else(|| text::title_from_path(path));
+                book.author = meta.author_string();
+                if let Some(series) = meta.series() {
+                    book.series = Some(series.name().to_string());
+                    book.series_index = series.index();
+                }
+                identifier = meta.identifier().map(str::to_string);
+            }
+            DocumentFormat::Html => {
+                let html = HtmlBook::open(path).ok()?;
+                let meta = &html.metadata;
+                book.title = meta
+                    .main_title()
+                    .map(str::to_string)
+                    .or_else(|| text::title_from_path(path));
+                book.author = meta.author_string();
+                if let Some(series) = meta.series() {
+                    book.series = Some(series.name().to_string());
+                    book.series_index = series.index();
+                }
+                identifier = meta.identifier().map(str::to_string);
+            }
+            DocumentFormat::Text | DocumentFormat::Markdown => {
+                book.title = text::title_from_path(path);
+            }
+            DocumentFormat::Other => return None,
+        }
+        book.id = book_key(path, identifier.as_deref()).ok()?;
+        Some(book)
+    }
+}
+
+/// Counts from one scan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// Folders to scan and the books found in them, persisted as
+/// `library.json` in the config dir.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Catalog {
+    #[serde(default)]
+    roots: Vec<String>,
+    #[serde(default)]
+    books: Vec<LibraryBook>,
+}
+
+fn catalog_path() -> Option<PathBuf> {
+    config::config_root().map(|dir| dir.join("library.json"))
+}
+
+impl Catalog {
+    pub fn load() -> Self {
+        catalog_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = catalog_path()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config dir"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        let mut f = fs::File::create(path)?;
+        let s = serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".into());
+        f.write_all(s.as_bytes())
+    }
+
+    pub fn roots(&self) -> &[String] {
+        &self.roots
+    }
+
+    /// Add a folder to scan; returns false if it is already configured.
+    pub fn add_root(&mut self, dir: &Path) -> bool {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let dir = dir.display().to_string();
+        if self.roots.contains(&dir) {
+            return false;
+        }
+        self.roots.push(dir);
+        true
+    }
+
+    pub fn remove_root(&mut self, dir: &str) -> bool {
+        let before = self.roots.len();
+        self.roots.retain(|root| root != dir);
+        self.roots.len() != before
+    }
+
+    pub fn books(&self) -> &[LibraryBook] {
+        &self.books
+    }
+
+    pub fn find_by_path(&self, path: &str) -> Option<&LibraryBook> {
+        self.books.iter().find(|book| book.path == path)
+    }
+
+    /// Books whose title contains `query`, ignoring case; an exact title
+    /// match comes first.
+    pub fn find_by_title(&self, query: &str) -> Vec<&LibraryBook> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<&LibraryBook> = self
+            .books
+            .iter()
+            .filter(|book| book.display_title().to_lowercase().contains(&query))
+            .collect();
+        found.sort_by_key(|book| book.display_title().to_lowercase() != query);
+        found
+    }
+
+    /// Walk every root for supported files. Files whose size and mtime are
+    /// unchanged keep their entry; others are re-read, and entries for
+    /// files that are gone are dropped.
+    pub fn scan(&mut self) -> ScanReport {
+        let mut known: HashMap<String, LibraryBook> = self
+            .books
+            .drain(..)
+            .map(|book| (book.path.clone(), book))
+            .collect();
+        let mut files = Vec::new();
+        for root in &self.roots {
+            collect_files(Path::new(root), &mut files);
+        }
+        files.sort();
+        files.dedup();
+
+        let mut report = ScanReport::default();
+        for (path, format) in files.into_iter().filter_map(|p| {
+            let format = format_for_path(&p)?;
+            Some((p, format))
+        }) {
+            let Ok(meta) = fs::metadata(&path) else {
+                continue;
+            };
+            let size = meta.len();
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            let key = path.display().to_string();
+            match known.remove(&key) {
+                Some(book) if book.size == size && book.modified == modified => {
+                    report.unchanged += 1;
+                    self.books.push(book);
+                }
+                previous => {
+                    if let Some(book) = LibraryBook::read(&path, format, size, modified) {
+                        if previous.is_some() {
+                            report.updated += 1;
+                        } else {
+                            report.added += 1;
+                        }
+                        self.books.push(book);
+                    } else if previous.is_some() {
+                        report.removed += 1;
+                    }
+                }
+            }
+        }
+        report.removed += known.len();
+        report
+    }
+}
+
+/// Formats the catalog picks up, by file extension.
+pub fn format_for_path(path: &Path) -> Option<DocumentFormat> {
+    if fb2::is_fb2_path(path) {
+        return Some(DocumentFormat::Fb2);
+    }
+    if mobi::is_mobi_path(path) {
+        return Some(DocumentFormat::Mobi);
+    }
+    if cbz::is_cbz_path(path) {
+        return Some(DocumentFormat::Cbz);
+    }
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "epub" => Some(DocumentFormat::Epub3),
+        // Loose .html files are mostly site assets, so only archives are books
+        "htmlz" => Some(DocumentFormat::Html),
+        "pdf" => Some(DocumentFormat::Pdf),
+        "txt" | "text" => Some(DocumentFormat::Text),
+        "md" | "markdown" => Some(DocumentFormat::Markdown),
+        _ => None,
+    }
+}
+
+// Hidden directories are skipped and directory symlinks are not followed,
+// so a scan cannot loop.
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|n| n.starts_with('.'));
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_dir() {
+            if !hidden {
+                collect_files(&path, out);
+            }
+        } else if !hidden && format_for_path(&path).is_some() {
+            out.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, text: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn scan_is_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("books");
+        write(&root.join("moby_dick.txt"), "Call me Ishmael.");
+        write(&root.join("notes/field-notes.md"), "# Notes\n\nSome text.");
+        write(&root.join(".cache/hidden.txt"), "skip me");
+        write(&root.join("cover.jpg"), "not a book");
+
+        let mut catalog = Catalog::default();
+        assert!(catalog.add_root(&root));
+        assert!(!catalog.add_root(&root));
+        let report = catalog.scan();
+        assert_eq!(report.added, 2);
+        assert_eq!(catalog.books().len(), 2);
+        let moby = catalog.find_by_title("moby").pop().unwrap().clone();
+        assert_eq!(moby.display_title(), "moby dick");
+        assert_eq!(moby.format(), DocumentFormat::Text);
+        assert!(moby.id().starts_with("sha256:"));
+
+        let report = catalog.scan();
+        assert_eq!(report.unchanged, 2);
+        assert_eq!(report.added + report.updated + report.removed, 0);
+
+        write(
+            &root.join("moby_dick.txt"),
+            "Call me Ishmael. Some years ago.",
+        );
+        fs::remove_file(root.join("notes/field-notes.md")).unwrap();
+        let report = catalog.scan();
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.removed, 1);
+        assert_ne!(catalog.books()[0].id(), moby.id());
+
+        let saved = dir.path().join("library.json");
+        catalog.save_to(&saved).unwrap();
+        let loaded = Catalog::load_from(&saved);
+        assert_eq!(loaded.books(), catalog.books());
+        assert_eq!(loaded.roots(), catalog.roots());
+    }
+
+    #[test]
+    fn catalog_ids_match_opened_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let fb2 = |id: &str| {
+            format!(
+                "<FictionBook><description><title-info><book-title>Tale</book-title></title-info>\
+                 <document-info>{}</document-info></description>\
+                 <body><section><p>Once upon a time.</p></section></body></FictionBook>",
+                id
+            )
+        };
+        write(&dir.path().join("with-id.fb2"), &fb2("<id>tale-1</id>"));
+        write(&dir.path().join("without-id.fb2"), &fb2(""));
+
+        let mut catalog = Catalog::default();
+        catalog.add_root(dir.path());
+        catalog.scan();
+        assert_eq!(catalog.books().len(), 2);
+        for book in catalog.books() {
+            let document = Fb2Book::open(Path::new(book.path())).unwrap().to_document();
+            assert_eq!(book.id(), document.info().id(), "{}", book.path());
+        }
+        let with_id = catalog.find_by_path(&dir.path().join("with-id.fb2").to_string_lossy());
+        assert_eq!(with_id.map(LibraryBook::id), Some("dc:tale-1"));
+    }
+
+    #[test]
+    fn epub_chapter_count_leaves_out_cover_and_navigation() {
+        use std::io::{Cursor, Write};
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        let page = |text: &str| format!("<html><body><p>{}</p></body></html>", text);
+        let files = [
+            ("mimetype", "application/epub+zip".to_string()),
+            (
+                "META-INF/container.xml",
+                "<container><rootfiles><rootfile full-path=\"OEBPS/content.opf\"/>\
+                 </rootfiles></container>"
+                    .to_string(),
+            ),
+            (
+                "OEBPS/content.opf",
+                "<package><metadata><dc:title>Tale</dc:title></metadata><manifest>\
+                 <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\
+                 <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\
+                 <item id=\"one\" href=\"one.xhtml\" media-type=\"application/xhtml+xml\"/>\
+                 <item id=\"two\" href=\"two.xhtml\" media-type=\"application/xhtml+xml\"/>\
+                 </manifest><spine><itemref idref=\"cover\"/><itemref idref=\"nav\"/>\
+                 <itemref idref=\"one\"/><itemref idref=\"two\"/></spine></package>"
+                    .to_string(),
+            ),
+            ("OEBPS/cover.xhtml", page("Cover")),
+            ("OEBPS/nav.xhtml", page("Contents")),
+            ("OEBPS/one.xhtml", page("Once upon a time.")),
+            ("OEBPS/two.xhtml", page("The end.")),
+        ];
+        for (name, text) in files {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(text.as_bytes()).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tale.epub");
+        fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+
+        let book = LibraryBook::read(&path, DocumentFormat::Epub3, 0, 0).unwrap();
+        assert_eq!(book.chapter_count(), Some(2));
+    }
+}
@@ -0,0 +1,16 @@
+//! MOBI, AZW and AZW3 (KF8) loading for unencrypted Kindle files. Text
+//! records are decompressed and the HTML they hold, rebuilt from KF8
+//! skeletons and fragments where present, goes through the same normalizer
+//! as EPUB chapters.
+
+mod book;
+mod error;
+mod header;
+mod huffcdic;
+mod index;
+mod palmdoc;
+#[cfg(test)]
+mod tests;
+
+pub use book::{is_mobi_path, MobiBook};
+pub use error::MobiError;
@@ -0,0 +1,460 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use regex::bytes::{Captures, Regex};
+
+use crate::identity::book_key_for_bytes;
+use crate::normalize::{html_to_blocks_with_assets, postprocess_blocks};
+use crate::text::title_from_path;
+use crate::types::{Block, BookMetadata, Document, DocumentFormat, DocumentInfo, TocEntry};
+
+use super::error::MobiError;
+use super::header::{
+    record_ranges, trailing_size, MobiHeader, COMPRESSION_HUFF_CDIC, COMPRESSION_NONE,
+    COMPRESSION_PALMDOC, EXTH_KF8_BOUNDARY, NULL_INDEX,
+};
+use super::huffcdic::HuffCdic;
+use super::index::read_index;
+use super::palmdoc;
+
+// Text records hold at most this much text once decompressed
+const TEXT_RECORD_SIZE: usize = 4096;
+
+/// Whether `path` names a MOBI, AZW or AZW3 file.
+pub fn is_mobi_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "mobi" | "azw" | "azw3"))
+}
+
+/// One XHTML file rebuilt from a KF8 skeleton and its fragments, or one
+/// page-break separated stretch of MOBI 6 markup.
+struct Part {
+    href: String,
+    html: String,
+}
+
+/// An NCX row: a label, its depth and where it points.
+struct NcxEntry {
+    label: String,
+    level: usize,
+    pos: Option<usize>,
+    fragment: Option<(usize, usize)>,
+}
+
+pub struct MobiBook {
+    pub path: PathBuf,
+    pub metadata: BookMetadata,
+    id: String,
+    data: Vec<u8>,
+    records: Vec<Range<usize>>,
+    first_image: Option<usize>,
+    parts: Vec<Part>,
+    toc: Vec<TocEntry>,
+}
+
+impl MobiBook {
+    pub fn open(path: &Path) -> Result<Self, MobiError> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(path, data)
+    }
+
+    pub(super) fn from_bytes(path: &Path, data: Vec<u8>) -> Result<Self, MobiError> {
+        let records = record_ranges(&data)?;
+        let record = |i: usize| records.get(i).and_then(|range| data.get(range.clone()));
+        let first = MobiHeader::parse(record(0).ok_or_else(|| no_record(0))?)?;
+        if first.encryption != 0 {
+            return Err(MobiError::Encrypted);
+        }
+        let metadata = first.metadata();
+        // Images are numbered from the first record 0 in combined files too
+        let first_image = first.first_image;
+
+        // A combined MOBI 6 / KF8 file names the record where KF8 starts
+        let boundary = first
+            .exth_u32(EXTH_KF8_BOUNDARY)
+            .filter(|_| first.version < 8)
+            .filter(|index| *index != NULL_INDEX)
+            .and_then(|index| {
+                let index = index as usize;
+                [index, index + 1]
+                    .into_iter()
+                    .find(|i| record(*i).is_some_and(|r| r.get(16..20) == Some(b"MOBI")))
+            });
+        let (header, base) = match boundary {
+            Some(base) => (MobiHeader::parse(record(base).unwrap_or_default())?, base),
+            None => (first, 0),
+        };
+        let local = |i: usize| record(base + i);
+        let text = extract_text(&header, local)?;
+        let (parts, toc) = if header.version >= 8 {
+            kf8_parts(&header, local, &text)?
+        } else {
+            mobi6_parts(&header, local, &text)
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            id: book_key_for_bytes(&data, metadata.identifier()),
+            metadata,
+            data,
+            records,
+            first_image,
+            parts,
+            toc,
+        })
+    }
+
+    pub fn chapter_count(&self) -> usize {
+        self.parts.len()
+    }
+
+    pub fn to_document(&self) -> Document {
+        let mut blocks = Vec::new();
+        let mut chapter_titles = Vec::new();
+        let mut chapter_hrefs = Vec::new();
+        for part in &self.parts {
+            let mut part_blocks = postprocess_blocks(html_to_blocks_with_assets(
+                &part.html,
+                Some(part.href.as_str()),
+                |src| self.image(src),
+                |href| self.link_target(href),
+            ));
+            if part_blocks.is_empty() {
+                continue;
+            }
+            if !chapter_hrefs.is_empty() {
+                blocks.push(Block::Paragraph(String::new()));
+                blocks.push(Block::Paragraph("───".into()));
+                blocks.push(Block::Paragraph(String::new()));
+            }
+            let title = self
+                .toc
+                .iter()
+                .find(|entry| entry.href().split('#').next() == Some(part.href.as_str()))
+                .map(|entry| entry.label().to_string())
+                .or_else(|| first_heading(&part_blocks))
+                .unwrap_or_else(|| format!("Section {}", chapter_titles.len() + 1));
+            blocks.append(&mut part_blocks);
+            chapter_titles.push(title);
+            chapter_hrefs.push(part.href.clone());
+        }
+        let title = self
+            .metadata
+            .main_title()
+            .map(str::to_string)
+            .or_else(|| title_from_path(&self.path));
+        let info = DocumentInfo::new(
+            self.id.clone(),
+            self.path.to_string_lossy().into_owned(),
+            title,
+            None,
+            self.metadata.author_string(),
+            Some(self.metadata.clone()),
+            DocumentFormat::Mobi,
+        );
+        Document::new(
+            info,
+            blocks,
+            chapter_titles,
+            chapter_hrefs,
+            self.toc.clone(),
+        )
+    }
+
+    // `kindle:embed:0003` (KF8, base 32) and `recindex:3` (MOBI 6) both
+    // count image records from one
+    fn image(&self, src: &str) -> Option<(String, Vec<u8>)> {
+        let number = if let Some(code) = src.strip_prefix("kindle:embed:") {
+            usize::from_str_radix(code, 32).ok()?
+        } else {
+            src.strip_prefix("recindex:")?.parse().ok()?
+        };
+        let index = self.first_image? + number.checked_sub(1)?;
+        let data = self.data.get(self.records.get(index)?.clone())?;
+        Some((src.to_string(), data.to_vec()))
+    }
+
+    // Internal links were rewritten to `part0003#id` while loading
+    fn link_target(&self, href: &str) -> Option<String> {
+        let path = href.split('#').next()?;
+        self.parts
+            .iter()
+            .any(|part| part.href == path)
+            .then(|| href.to_string())
+    }
+}
+
+fn no_record(index: usize) -> MobiError {
+    MobiError::Parse(format!("missing record {}", index))
+}
+
+fn part_href(number: usize) -> String {
+    format!("part{:04}", number)
+}
+
+fn first_heading(blocks: &[Block]) -> Option<String> {
+    blocks.iter().find_map(|block| match block {
+        Block::Heading(text, _) => Some(crate::layout::strip_style_markers(text)),
+        _ => None,
+    })
+}
+
+fn extract_text<'a>(
+    header: &MobiHeader,
+    record: impl Fn(usize) -> Option<&'a [u8]>,
+) -> Result<Vec<u8>, MobiError> {
+    let mut huff = match (header.compression, &header.huff_records) {
+        (COMPRESSION_HUFF_CDIC, Some(range)) => {
+            let records = range
+                .clone()
+                .map(|i| record(i).ok_or_else(|| no_record(i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(HuffCdic::new(&records)?)
+        }
+        (COMPRESSION_HUFF_CDIC, None) => {
+            return Err(MobiError::Parse("missing HUFF records".into()))
+        }
+        (COMPRESSION_NONE | COMPRESSION_PALMDOC, _) => None,
+        (other, _) => return Err(MobiError::Compression(other)),
+    };
+    // The length comes from the file, so it only bounds what is kept
+    let mut text = Vec::with_capacity(
+        header
+            .text_length
+            .min(header.text_records.saturating_mul(TEXT_RECORD_SIZE)),
+    );
+    for i in 1..=header.text_records {
+        let data = record(i).ok_or_else(|| no_record(i))?;
+        let data = &data[..data.len() - trailing_size(data, header.extra_flags)];
+        match &mut huff {
+            Some(huff) => huff.decompress(data, &mut text)?,
+            None if header.compression == COMPRESSION_PALMDOC => {
+                palmdoc::decompress(data, &mut text)
+            }
+            None => text.extend_from_slice(data),
+        }
+    }
+    text.truncate(header.text_length);
+    Ok(text)
+}
+
+fn read_ncx<'a>(header: &MobiHeader, record: impl Fn(usize) -> Option<&'a [u8]>) -> Vec<NcxEntry> {
+    let Some(index) = header.ncx_index else {
+        return Vec::new();
+    };
+    let Ok((entries, cncx)) = read_index(record, index) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let label = cncx.get(entry.tag(3, 0)?).map(|raw| header.decode(raw))?;
+            let fragment = entry
+                .tag(6, 0)
+                .zip(entry.tag(6, 1))
+                .map(|(fid, off)| (fid as usize, off as usize));
+            Some(NcxEntry {
+                label: label.trim().to_string(),
+                level: entry.tag(4, 0).unwrap_or(0) as usize,
+                pos: entry.tag(1, 0).map(|pos| pos as usize),
+                fragment,
+            })
+        })
+        .filter(|entry| !entry.label.is_empty())
+        .collect()
+}
+
+// Where an `id` before `pos` is looked for: a position inside a tag (or at
+// its `<`) counts that tag too
+fn anchor_search_end(html: &[u8], pos: usize) -> usize {
+    let pos = pos.min(html.len());
+    let rest = &html[pos..];
+    let next_open = rest.iter().position(|&b| b == b'<');
+    let next_close = rest.iter().position(|&b| b == b'>');
+    match (next_open, next_close) {
+        (open, Some(close)) if open == Some(0) || open.is_none_or(|open| close < open) => {
+            pos + close + 1
+        }
+        _ => pos,
+    }
+}
+
+fn id_regex() -> Regex {
+    Regex::new(r#"(?i)<[^>]+\s(?:id|name)\s*=\s*['"]([^'"]+)['"]"#).expect("valid regex")
+}
+
+/// KF8 text is a run of skeleton files, each followed by the fragments
+/// to insert into it. Positions in links and the NCX count through the
+/// assembled files.
+fn kf8_parts<'a>(
+    header: &MobiHeader,
+    record: impl Fn(usize) -> Option<&'a [u8]> + Copy,
+    text: &[u8],
+) -> Result<(Vec<Part>, Vec<TocEntry>), MobiError> {
+    let missing = || MobiError::Parse("KF8 file without skeleton and fragment indexes".into());
+    let (skeletons, _) = read_index(record, header.skeleton_index.ok_or_else(missing)?)?;
+    let (fragments, _) = read_index(record, header.fragment_index.ok_or_else(missing)?)?;
+    let inserts: Vec<usize> = fragments
+        .iter()
+        .map(|fragment| fragment.ident.parse().unwrap_or(0))
+        .collect();
+
+    let mut files: Vec<(Range<usize>, Vec<u8>)> = Vec::with_capacity(skeletons.len());
+    let mut next_fragment = 0;
+    for skeleton in &skeletons {
+        let start = skeleton.tag(6, 0).unwrap_or(0) as usize;
+        let len = skeleton.tag(6, 1).unwrap_or(0) as usize;
+        let mut html = text.get(start..start + len).unwrap_or_default().to_vec();
+        let mut end = start + len;
+        for _ in 0..skeleton.tag(1, 0).unwrap_or(0) {
+            let Some(fragment) = fragments.get(next_fragment) else {
+                break;
+            };
+            let len = fragment.tag(6, 1).unwrap_or(0) as usize;
+            let piece = text.get(end..end + len).unwrap_or_default();
+            let at = inserts[next_fragment].saturating_sub(start).min(html.len());
+            html.splice(at..at, piece.iter().copied());
+            end += len;
+            next_fragment += 1;
+        }
+        files.push((start..end, html));
+    }
+
+    let ids = id_regex();
+    let target = |pos: usize| -> Option<String> {
+        let (number, (range, html)) = files
+            .iter()
+            .enumerate()
+            .find(|(_, (range, _))| range.contains(&pos))?;
+        let end = anchor_search_end(html, pos - range.start);
+        let id = ids
+            .captures_iter(&html[..end])
+            .last()
+            .map(|caps| String::from_utf8_lossy(&caps[1]).into_owned());
+        Some(match id {
+            Some(id) => format!("{}#{}", part_href(number), id),
+            None => part_href(number),
+        })
+    };
+    let fragment_pos = |fid: usize, off: usize| inserts.get(fid).map(|insert| insert + off);
+
+    let link =
+        Regex::new(r"(?i)kindle:pos:fid:([0-9a-v]{4}):off:([0-9a-v]{10})").expect("valid regex");
+    let parts = files
+        .iter()
+        .enumerate()
+        .map(|(number, (_, html))| {
+            let html = link.replace_all(html, |caps: &Captures| {
+                let base32 = |i: usize| {
+                    std::str::from_utf8(&caps[i])
+                        .ok()
+                        .and_then(|code| usize::from_str_radix(code, 32).ok())
+                };
+                base32(1)
+                    .zip(base32(2))
+                    .and_then(|(fid, off)| fragment_pos(fid, off))
+                    .and_then(target)
+                    .unwrap_or_default()
+                    .into_bytes()
+            });
+            Part {
+                href: part_href(number),
+                html: header.decode(&html),
+            }
+        })
+        .collect();
+
+    let toc = read_ncx(header, record)
+        .into_iter()
+        .filter_map(|entry| {
+            let pos = match entry.fragment {
+                Some((fid, off)) => fragment_pos(fid, off)?,
+                None => entry.pos?,
+            };
+            Some(TocEntry::new(target(pos)?, entry.label, entry.level))
+        })
+        .collect();
+    Ok((parts, toc))
+}
+
+/// MOBI 6 text is one HTML-like stream: links and the NCX point at byte
+/// offsets (`filepos`), images name records (`recindex`) and
+/// `<mbp:pagebreak/>` separates chapters.
+fn mobi6_parts<'a>(
+    header: &MobiHeader,
+    record: impl Fn(usize) -> Option<&'a [u8]>,
+    text: &[u8],
+) -> (Vec<Part>, Vec<TocEntry>) {
+    let ncx = read_ncx(header, record);
+    let filepos = Regex::new(r#"(?i)\bfilepos\s*=\s*['"]?0*(\d+)['"]?"#).expect("valid regex");
+    let mut positions: BTreeSet<usize> = filepos
+        .captures_iter(text)
+        .filter_map(|caps| std::str::from_utf8(&caps[1]).ok()?.parse().ok())
+        .collect();
+    positions.extend(ncx.iter().filter_map(|entry| entry.pos));
+
+    // Anchor every target in one pass over the text
+    let mut anchors: Vec<(usize, usize)> = positions
+        .iter()
+        .filter(|pos| **pos <= text.len())
+        .map(|&pos| (anchor_search_end(text, pos), pos))
+        .collect();
+    anchors.sort_unstable();
+    let mut html = Vec::with_capacity(text.len() + anchors.len() * 32);
+    let mut copied = 0;
+    for (at, pos) in anchors {
+        html.extend_from_slice(&text[copied..at]);
+        html.extend_from_slice(format!("<a id=\"filepos{}\"></a>", pos).as_bytes());
+        copied = at;
+    }
+    html.extend_from_slice(&text[copied..]);
+    let html = filepos.replace_all(&html, &b"href=\"#filepos$1\""[..]);
+    let recindex = Regex::new(r#"(?i)\brecindex\s*=\s*['"]?0*(\d+)['"]?"#).expect("valid regex");
+    let html = recindex.replace_all(&html, &b"src=\"recindex:$1\""[..]);
+
+    let pagebreak = Regex::new(r"(?i)<mbp:pagebreak[^>]*>").expect("valid regex");
+    let pieces: Vec<&[u8]> = pagebreak
+        .split(&html)
+        .filter(|piece| !piece.trim_ascii().is_empty())
+        .collect();
+    let anchor = Regex::new(r#"id="filepos(\d+)""#).expect("valid regex");
+    let mut owners: HashMap<Vec<u8>, String> = HashMap::new();
+    for (number, piece) in pieces.iter().enumerate() {
+        for caps in anchor.captures_iter(piece) {
+            owners.insert(caps[1].to_vec(), part_href(number));
+        }
+    }
+    let link = Regex::new(r##"href="#filepos(\d+)""##).expect("valid regex");
+    let parts = pieces
+        .iter()
+        .enumerate()
+        .map(|(number, piece)| {
+            let html = link.replace_all(piece, |caps: &Captures| match owners.get(&caps[1]) {
+                Some(owner) => {
+                    let mut href = format!("href=\"{}#filepos", owner).into_bytes();
+                    href.extend_from_slice(&caps[1]);
+                    href.push(b'"');
+                    href
+                }
+                None => caps[0].to_vec(),
+            });
+            Part {
+                href: part_href(number),
+                html: header.decode(&html),
+            }
+        })
+        .collect();
+
+    let toc = ncx
+        .into_iter()
+        .filter_map(|entry| {
+            let pos = entry.pos?.to_string();
+            let owner = owners.get(pos.as_bytes())?;
+            let href = format!("{}#filepos{}", owner, pos);
+            Some(TocEntry::new(href, entry.label, entry.level))
+        })
+        .collect();
+    (parts, toc)
+}
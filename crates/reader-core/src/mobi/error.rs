@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MobiError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("file is DRM protected")]
+    Encrypted,
+    #[error("unsupported compression type {0}")]
+    Compression(u16),
+}
@@ -0,0 +1,243 @@
+use std::ops::Range;
+
+use crate::types::{BookMetadata, CreatorEntry, TitleEntry, TitleKind};
+
+use super::error::MobiError;
+
+pub(super) const NULL_INDEX: u32 = 0xffff_ffff;
+
+pub(super) const COMPRESSION_NONE: u16 = 1;
+pub(super) const COMPRESSION_PALMDOC: u16 = 2;
+pub(super) const COMPRESSION_HUFF_CDIC: u16 = 0x4448;
+
+// EXTH record types
+const EXTH_AUTHOR: u32 = 100;
+const EXTH_ISBN: u32 = 104;
+const EXTH_ASIN: u32 = 113;
+pub(super) const EXTH_KF8_BOUNDARY: u32 = 121;
+const EXTH_UPDATED_TITLE: u32 = 503;
+const EXTH_LANGUAGE: u32 = 524;
+
+pub(super) fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+pub(super) fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn truncated(what: &str) -> MobiError {
+    MobiError::Parse(format!("truncated {}", what))
+}
+
+/// Byte ranges of the records listed in the PalmDB header.
+pub(super) fn record_ranges(data: &[u8]) -> Result<Vec<Range<usize>>, MobiError> {
+    let count = usize::from(be_u16(data, 76).ok_or_else(|| truncated("PalmDB header"))?);
+    let mut starts = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = be_u32(data, 78 + i * 8).ok_or_else(|| truncated("record list"))?;
+        starts.push(start as usize);
+    }
+    let mut ranges = Vec::with_capacity(count);
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(data.len());
+        if *start > end || end > data.len() {
+            return Err(MobiError::Parse(format!("record {} out of bounds", i)));
+        }
+        ranges.push(*start..end);
+    }
+    Ok(ranges)
+}
+
+/// The PalmDOC and MOBI headers of a record 0, with its EXTH records.
+/// Record numbers are relative to that record 0.
+pub(super) struct MobiHeader {
+    pub(super) compression: u16,
+    pub(super) text_length: usize,
+    pub(super) text_records: usize,
+    pub(super) encryption: u16,
+    pub(super) utf8: bool,
+    pub(super) version: u32,
+    pub(super) full_name: Option<String>,
+    pub(super) first_image: Option<usize>,
+    pub(super) huff_records: Option<Range<usize>>,
+    pub(super) extra_flags: u16,
+    pub(super) ncx_index: Option<usize>,
+    pub(super) fragment_index: Option<usize>,
+    pub(super) skeleton_index: Option<usize>,
+    pub(super) exth: Vec<(u32, Vec<u8>)>,
+}
+
+impl MobiHeader {
+    pub(super) fn parse(record: &[u8]) -> Result<Self, MobiError> {
+        let compression = be_u16(record, 0).ok_or_else(|| truncated("PalmDOC header"))?;
+        let text_length = be_u32(record, 4).ok_or_else(|| truncated("PalmDOC header"))? as usize;
+        let text_records =
+            usize::from(be_u16(record, 8).ok_or_else(|| truncated("PalmDOC header"))?);
+        let encryption = be_u16(record, 12).ok_or_else(|| truncated("PalmDOC header"))?;
+        let mut header = Self {
+            compression,
+            text_length,
+            text_records,
+            encryption,
+            utf8: false,
+            version: 0,
+            full_name: None,
+            first_image: None,
+            huff_records: None,
+            extra_flags: 0,
+            ncx_index: None,
+            fragment_index: None,
+            skeleton_index: None,
+            exth: Vec::new(),
+        };
+        if record.get(16..20) != Some(b"MOBI") {
+            // A plain PalmDOC file
+            return Ok(header);
+        }
+        let length = be_u32(record, 20).ok_or_else(|| truncated("MOBI header"))? as usize;
+        // Fields past the declared header length are not there
+        let field = |at: usize| be_u32(record, at).filter(|_| at + 4 <= 16 + length);
+        let index = |at: usize| field(at).filter(|v| *v != NULL_INDEX).map(|v| v as usize);
+        header.utf8 = field(28) == Some(65001);
+        header.version = field(36).unwrap_or(0).max(field(104).unwrap_or(0));
+        if let (Some(offset), Some(len)) = (field(84), field(88)) {
+            let (offset, len) = (offset as usize, len as usize);
+            header.full_name = record
+                .get(offset..offset + len)
+                .map(|name| header.decode(name))
+                .filter(|name| !name.trim().is_empty());
+        }
+        header.first_image = index(108);
+        if let (Some(first), Some(count)) = (index(112), field(116)) {
+            if count > 0 {
+                header.huff_records = Some(first..first + count as usize);
+            }
+        }
+        if length >= 0xe4 {
+            header.extra_flags = be_u16(record, 0xf2).unwrap_or(0);
+        }
+        header.ncx_index = index(0xf4);
+        if header.version >= 8 {
+            header.fragment_index = index(0xf8);
+            header.skeleton_index = index(0xfc);
+        }
+        if field(128).is_some_and(|flags| flags & 0x40 != 0) {
+            header.exth = parse_exth(record.get(16 + length..).unwrap_or_default());
+        }
+        Ok(header)
+    }
+
+    pub(super) fn decode(&self, bytes: &[u8]) -> String {
+        if self.utf8 {
+            String::from_utf8_lossy(bytes).into_owned()
+        } else {
+            encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned()
+        }
+    }
+
+    pub(super) fn exth_u32(&self, kind: u32) -> Option<u32> {
+        self.exth
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .and_then(|(_, data)| be_u32(data, 0))
+    }
+
+    fn exth_strings(&self, kind: u32) -> impl Iterator<Item = String> + '_ {
+        self.exth
+            .iter()
+            .filter(move |(k, _)| *k == kind)
+            .map(|(_, data)| self.decode(data).trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+
+    pub(super) fn metadata(&self) -> BookMetadata {
+        let title = self
+            .exth_strings(EXTH_UPDATED_TITLE)
+            .next()
+            .or_else(|| self.full_name.clone());
+        let titles = title
+            .map(|title| vec![TitleEntry::new(title, TitleKind::Main)])
+            .unwrap_or_default();
+        let creators = self
+            .exth_strings(EXTH_AUTHOR)
+            .map(|name| CreatorEntry::new(name, vec!["aut".to_string()]))
+            .collect();
+        let mut metadata = BookMetadata::new(titles, creators, None);
+        metadata.set_language(self.exth_strings(EXTH_LANGUAGE).next());
+        metadata.set_identifier(
+            self.exth_strings(EXTH_ISBN)
+                .next()
+                .or_else(|| self.exth_strings(EXTH_ASIN).next()),
+        );
+        metadata
+    }
+}
+
+fn parse_exth(data: &[u8]) -> Vec<(u32, Vec<u8>)> {
+    let mut records = Vec::new();
+    if !data.starts_with(b"EXTH") {
+        return records;
+    }
+    let count = be_u32(data, 8).unwrap_or(0);
+    let mut at = 12;
+    for _ in 0..count {
+        let (Some(kind), Some(len)) = (be_u32(data, at), be_u32(data, at + 4)) else {
+            break;
+        };
+        let len = len as usize;
+        let Some(value) = data.get(at + 8..at + len.max(8)) else {
+            break;
+        };
+        records.push((kind, value.to_vec()));
+        at += len.max(8);
+    }
+    records
+}
+
+/// Bytes of trailing entries at the end of a text record, which are not
+/// part of the text.
+pub(super) fn trailing_size(record: &[u8], extra_flags: u16) -> usize {
+    let mut size = 0;
+    let mut flags = extra_flags >> 1;
+    while flags != 0 {
+        if flags & 1 != 0 {
+            size += trailing_entry_size(&record[..record.len().saturating_sub(size)]);
+        }
+        flags >>= 1;
+    }
+    if extra_flags & 1 != 0 {
+        if let Some(byte) = record.len().checked_sub(size + 1).map(|at| record[at]) {
+            size += usize::from(byte & 0x3) + 1;
+        }
+    }
+    size.min(record.len())
+}
+
+// A backward-encoded variable width size, counting its own bytes
+fn trailing_entry_size(data: &[u8]) -> usize {
+    let mut result = 0;
+    let mut shift = 0;
+    for &byte in data.iter().rev() {
+        result |= usize::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 != 0 || shift >= 28 {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_trailing_entries_and_multibyte_overlap() {
+        // Text, one overlapping byte and its count, then a three-byte entry
+        let record = b"text\xaa\x01\xbb\xcc\x83";
+        assert_eq!(trailing_size(record, 0b11), 5);
+        assert_eq!(trailing_size(record, 0b10), 3);
+        assert_eq!(trailing_size(b"text", 0), 0);
+    }
+}
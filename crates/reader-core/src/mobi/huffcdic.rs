@@ -0,0 +1,194 @@
+use super::error::MobiError;
+use super::header::{be_u16, be_u32};
+
+#[derive(Clone, Copy)]
+struct CodeInfo {
+    len: u32,
+    terminal: bool,
+    max: u64,
+}
+
+/// A phrase from the CDIC records; phrases that are not yet expanded are
+/// themselves Huffman coded.
+struct Phrase {
+    bytes: Vec<u8>,
+    expanded: bool,
+}
+
+/// Decoder for HUFF/CDIC compressed text records: a Huffman code over a
+/// dictionary of phrases.
+pub(super) struct HuffCdic {
+    codes: Vec<CodeInfo>,
+    min_codes: Vec<u64>,
+    max_codes: Vec<u64>,
+    phrases: Vec<Phrase>,
+}
+
+fn corrupt(what: &str) -> MobiError {
+    MobiError::Parse(format!("bad {} record", what))
+}
+
+impl HuffCdic {
+    /// `records` holds the HUFF record followed by its CDIC records.
+    pub(super) fn new(records: &[&[u8]]) -> Result<Self, MobiError> {
+        let (huff, cdics) = records.split_first().ok_or_else(|| corrupt("HUFF"))?;
+        if !huff.starts_with(b"HUFF") {
+            return Err(corrupt("HUFF"));
+        }
+        let table1 = be_u32(huff, 8).ok_or_else(|| corrupt("HUFF"))? as usize;
+        let table2 = be_u32(huff, 12).ok_or_else(|| corrupt("HUFF"))? as usize;
+        let mut codes = Vec::with_capacity(256);
+        for i in 0..256 {
+            let v = be_u32(huff, table1 + i * 4).ok_or_else(|| corrupt("HUFF"))?;
+            let len = v & 0x1f;
+            if len == 0 {
+                return Err(corrupt("HUFF"));
+            }
+            let max = ((u64::from(v >> 8) + 1) << (32 - len)) - 1;
+            codes.push(CodeInfo {
+                len,
+                terminal: v & 0x80 != 0,
+                max,
+            });
+        }
+        let mut min_codes = vec![0];
+        let mut max_codes = vec![0];
+        for len in 1..=32u32 {
+            let at = table2 + (len as usize - 1) * 8;
+            let min = be_u32(huff, at).ok_or_else(|| corrupt("HUFF"))?;
+            let max = be_u32(huff, at + 4).ok_or_else(|| corrupt("HUFF"))?;
+            min_codes.push(u64::from(min) << (32 - len));
+            max_codes.push(((u64::from(max) + 1) << (32 - len)) - 1);
+        }
+
+        let mut phrases = Vec::new();
+        for cdic in cdics {
+            if !cdic.starts_with(b"CDIC") {
+                return Err(corrupt("CDIC"));
+            }
+            let total = be_u32(cdic, 8).ok_or_else(|| corrupt("CDIC"))? as usize;
+            let bits = be_u32(cdic, 12).ok_or_else(|| corrupt("CDIC"))?;
+            let count = (1usize << bits.min(31)).min(total.saturating_sub(phrases.len()));
+            for i in 0..count {
+                let offset = usize::from(be_u16(cdic, 16 + i * 2).ok_or_else(|| corrupt("CDIC"))?);
+                let info = be_u16(cdic, 16 + offset).ok_or_else(|| corrupt("CDIC"))?;
+                let start = 18 + offset;
+                let end = (start + usize::from(info & 0x7fff)).min(cdic.len());
+                phrases.push(Phrase {
+                    bytes: cdic.get(start..end).unwrap_or_default().to_vec(),
+                    expanded: info & 0x8000 != 0,
+                });
+            }
+        }
+        Ok(Self {
+            codes,
+            min_codes,
+            max_codes,
+            phrases,
+        })
+    }
+
+    pub(super) fn decompress(&mut self, data: &[u8], out: &mut Vec<u8>) -> Result<(), MobiError> {
+        self.unpack(data, out, 0)
+    }
+
+    fn unpack(&mut self, data: &[u8], out: &mut Vec<u8>, depth: usize) -> Result<(), MobiError> {
+        if depth > 32 {
+            return Err(corrupt("CDIC"));
+        }
+        let mut padded = data.to_vec();
+        padded.extend_from_slice(&[0; 8]);
+        let window = |pos: usize| u64::from_be_bytes(padded[pos..pos + 8].try_into().unwrap());
+        let mut bits_left = data.len() as i64 * 8;
+        let mut pos = 0;
+        let mut x = window(pos);
+        let mut n: i64 = 32;
+        loop {
+            if n <= 0 {
+                pos += 4;
+                x = window(pos);
+                n += 32;
+            }
+            let code = (x >> n as u32) & 0xffff_ffff;
+            let info = self.codes[(code >> 24) as usize];
+            let mut len = info.len;
+            let mut max = info.max;
+            if !info.terminal {
+                while len < 32 && code < self.min_codes[len as usize] {
+                    len += 1;
+                }
+                max = self.max_codes[len as usize];
+            }
+            n -= i64::from(len);
+            bits_left -= i64::from(len);
+            if bits_left < 0 {
+                break;
+            }
+            let index = (max.wrapping_sub(code) >> (32 - len)) as usize;
+            let phrase = self.phrases.get_mut(index).ok_or_else(|| corrupt("CDIC"))?;
+            if !phrase.expanded {
+                let packed = std::mem::take(&mut phrase.bytes);
+                let mut expanded = Vec::new();
+                self.unpack(&packed, &mut expanded, depth + 1)?;
+                let phrase = &mut self.phrases[index];
+                phrase.bytes = expanded;
+                phrase.expanded = true;
+            }
+            out.extend_from_slice(&self.phrases[index].bytes);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every byte is an 8-bit terminal code for phrase 255 - byte
+    fn huff_record() -> Vec<u8> {
+        let mut huff = b"HUFF\x00\x00\x00\x18".to_vec();
+        huff.extend_from_slice(&24u32.to_be_bytes());
+        huff.extend_from_slice(&(24u32 + 1024).to_be_bytes());
+        huff.extend_from_slice(&[0; 8]);
+        for _ in 0..256 {
+            huff.extend_from_slice(&(255u32 << 8 | 0x80 | 8).to_be_bytes());
+        }
+        huff.extend_from_slice(&[0; 256]);
+        huff
+    }
+
+    fn cdic_record(phrases: &[(&[u8], bool)]) -> Vec<u8> {
+        let mut cdic = b"CDIC\x00\x00\x00\x10".to_vec();
+        cdic.extend_from_slice(&(phrases.len() as u32).to_be_bytes());
+        cdic.extend_from_slice(&8u32.to_be_bytes());
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        let table = phrases.len() * 2;
+        for (bytes, literal) in phrases {
+            offsets.push((table + body.len()) as u16);
+            let flag = if *literal { 0x8000 } else { 0 };
+            body.extend_from_slice(&(bytes.len() as u16 | flag).to_be_bytes());
+            body.extend_from_slice(bytes);
+        }
+        for offset in offsets {
+            cdic.extend_from_slice(&offset.to_be_bytes());
+        }
+        cdic.extend_from_slice(&body);
+        cdic
+    }
+
+    #[test]
+    fn decodes_phrases_and_nested_phrases() {
+        let mut phrases: Vec<(&[u8], bool)> = vec![(b"?", true); 256];
+        phrases[0] = (b"Call me ", true);
+        phrases[1] = (b"Ishmael", true);
+        // Phrase 2 is itself coded: phrases 0 then 1
+        phrases[2] = (&[255, 254], false);
+        let huff = huff_record();
+        let cdic = cdic_record(&phrases);
+        let mut decoder = HuffCdic::new(&[&huff, &cdic]).unwrap();
+        let mut out = Vec::new();
+        decoder.decompress(&[253, 255], &mut out).unwrap();
+        assert_eq!(out, b"Call me IshmaelCall me ");
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use super::error::MobiError;
+use super::header::be_u32;
+
+/// One row of an INDX table: its identifier and tag values.
+pub(super) struct IndexEntry {
+    pub(super) ident: String,
+    tags: HashMap<u8, Vec<u32>>,
+}
+
+impl IndexEntry {
+    pub(super) fn tag(&self, tag: u8, at: usize) -> Option<u32> {
+        self.tags.get(&tag)?.get(at).copied()
+    }
+}
+
+/// Strings an index refers to by offset, from its CNCX records.
+#[derive(Default)]
+pub(super) struct Cncx(HashMap<u32, Vec<u8>>);
+
+impl Cncx {
+    pub(super) fn get(&self, offset: u32) -> Option<&[u8]> {
+        self.0.get(&offset).map(Vec::as_slice)
+    }
+}
+
+struct TagDef {
+    tag: u8,
+    values: usize,
+    mask: u8,
+    end: bool,
+}
+
+fn bad_index() -> MobiError {
+    MobiError::Parse("bad INDX record".into())
+}
+
+/// Read the index whose header is record `first`; `record` looks records
+/// up by number.
+pub(super) fn read_index<'a>(
+    record: impl Fn(usize) -> Option<&'a [u8]>,
+    first: usize,
+) -> Result<(Vec<IndexEntry>, Cncx), MobiError> {
+    let header = record(first).ok_or_else(bad_index)?;
+    if !header.starts_with(b"INDX") {
+        return Err(bad_index());
+    }
+    let header_len = be_u32(header, 4).ok_or_else(bad_index)? as usize;
+    let records = be_u32(header, 24).ok_or_else(bad_index)? as usize;
+    let cncx_records = be_u32(header, 52).ok_or_else(bad_index)? as usize;
+    let (control_bytes, tags) = parse_tagx(header.get(header_len..).ok_or_else(bad_index)?)?;
+
+    let mut cncx = Cncx::default();
+    for i in 0..cncx_records {
+        let data = record(first + records + 1 + i).ok_or_else(bad_index)?;
+        let base = (i as u32) << 16;
+        let mut pos = 0;
+        while pos < data.len() {
+            let (len, used) = decint(&data[pos..]);
+            let start = pos + used;
+            let end = (start + len as usize).min(data.len());
+            if len > 0 {
+                cncx.0.insert(base + pos as u32, data[start..end].to_vec());
+            }
+            pos = end.max(pos + 1);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for i in 1..=records {
+        let data = record(first + i).ok_or_else(bad_index)?;
+        let idxt = be_u32(data, 20).ok_or_else(bad_index)? as usize;
+        let count = be_u32(data, 24).ok_or_else(bad_index)? as usize;
+        // Each entry has a two byte offset after the IDXT tag
+        if count > data.len().saturating_sub(idxt + 4) / 2 {
+            return Err(bad_index());
+        }
+        let mut starts = Vec::with_capacity(count + 1);
+        for j in 0..count {
+            let at = idxt + 4 + 2 * j;
+            let pos = data.get(at..at + 2).ok_or_else(bad_index)?;
+            starts.push(usize::from(u16::from_be_bytes([pos[0], pos[1]])));
+        }
+        starts.push(idxt);
+        for pair in starts.windows(2) {
+            let entry = data.get(pair[0]..pair[1]).ok_or_else(bad_index)?;
+            let (&len, rest) = entry.split_first().ok_or_else(bad_index)?;
+            let len = usize::from(len).min(rest.len());
+            let ident = String::from_utf8_lossy(&rest[..len]).into_owned();
+            let tags = tag_values(control_bytes, &tags, &rest[len..]);
+            entries.push(IndexEntry { ident, tags });
+        }
+    }
+    Ok((entries, cncx))
+}
+
+fn parse_tagx(data: &[u8]) -> Result<(usize, Vec<TagDef>), MobiError> {
+    if !data.starts_with(b"TAGX") {
+        return Err(bad_index());
+    }
+    let len = be_u32(data, 4).ok_or_else(bad_index)? as usize;
+    let control_bytes = be_u32(data, 8).ok_or_else(bad_index)? as usize;
+    let defs = data
+        .get(12..len.min(data.len()))
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|def| TagDef {
+            tag: def[0],
+            values: usize::from(def[1]),
+            mask: def[2],
+            end: def[3] & 1 != 0,
+        })
+        .collect();
+    Ok((control_bytes, defs))
+}
+
+// Control bytes say how many values each tag has; the values follow as
+// variable width integers
+fn tag_values(control_bytes: usize, defs: &[TagDef], data: &[u8]) -> HashMap<u8, Vec<u32>> {
+    enum Count {
+        Values(usize),
+        Bytes(usize),
+    }
+    let control = data.get(..control_bytes).unwrap_or_default();
+    let mut data = data.get(control_bytes..).unwrap_or_default();
+    let mut byte = 0;
+    let mut counts = Vec::new();
+    for def in defs {
+        if def.end {
+            byte += 1;
+            continue;
+        }
+        let Some(&control) = control.get(byte) else {
+            break;
+        };
+        let value = control & def.mask;
+        if value == 0 {
+            continue;
+        }
+        let count = if value == def.mask && def.mask.count_ones() > 1 {
+            let (bytes, used) = decint(data);
+            data = &data[used..];
+            Count::Bytes(bytes as usize)
+        } else {
+            Count::Values(usize::from(value >> def.mask.trailing_zeros()) * def.values)
+        };
+        counts.push((def.tag, count));
+    }
+    let mut tags = HashMap::new();
+    for (tag, count) in counts {
+        let mut values = Vec::new();
+        match count {
+            Count::Values(n) => {
+                for _ in 0..n {
+                    let (value, used) = decint(data);
+                    data = &data[used..];
+                    values.push(value);
+                }
+            }
+            Count::Bytes(n) => {
+                let mut consumed = 0;
+                while consumed < n && !data.is_empty() {
+                    let (value, used) = decint(data);
+                    data = &data[used..];
+                    consumed += used;
+                    values.push(value);
+                }
+            }
+        }
+        tags.insert(tag, values);
+    }
+    tags
+}
+
+/// A forward variable width integer: seven bits a byte, most significant
+/// first, with the high bit set on the last byte.
+pub(super) fn decint(data: &[u8]) -> (u32, usize) {
+    let mut value = 0u32;
+    for (i, &byte) in data.iter().take(5).enumerate() {
+        value = value << 7 | u32::from(byte & 0x7f);
+        if byte & 0x80 != 0 {
+            return (value, i + 1);
+        }
+    }
+    (value, data.len().min(5))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// An index entry: its identifier and (tag, values) pairs.
+    pub(in crate::mobi) type Entry<'a> = (&'a str, Vec<(u8, Vec<u32>)>);
+
+    pub(in crate::mobi) fn encint(mut value: u32) -> Vec<u8> {
+        let mut out = vec![(value & 0x7f) as u8 | 0x80];
+        value >>= 7;
+        while value > 0 {
+            out.insert(0, (value & 0x7f) as u8);
+            value >>= 7;
+        }
+        out
+    }
+
+    /// An index header record, one entry record and CNCX records, for
+    /// tags with one control byte. `tags` lists (tag, values per entry,
+    /// mask) and each entry its identifier and (tag, values) pairs.
+    pub(in crate::mobi) fn build_index(
+        tags: &[(u8, u8, u8)],
+        entries: &[Entry],
+        cncx: &[u8],
+    ) -> Vec<Vec<u8>> {
+        let mut tagx = b"TAGX".to_vec();
+        tagx.extend_from_slice(&(12 + 4 * (tags.len() as u32 + 1)).to_be_bytes());
+        tagx.extend_from_slice(&1u32.to_be_bytes());
+        for (tag, values, mask) in tags {
+            tagx.extend_from_slice(&[*tag, *values, *mask, 0]);
+        }
+        tagx.extend_from_slice(&[0, 0, 0, 1]);
+        let mut header = indx_header(0, 1, u32::from(!cncx.is_empty()));
+        header.extend_from_slice(&tagx);
+
+        let mut body = Vec::new();
+        let mut offsets = Vec::new();
+        for (ident, values) in entries {
+            offsets.push(192 + body.len());
+            body.push(ident.len() as u8);
+            body.extend_from_slice(ident.as_bytes());
+            let mut control = 0u8;
+            let mut data = Vec::new();
+            for (tag, values_per_entry, mask) in tags {
+                let Some((_, found)) = values.iter().find(|(t, _)| t == tag) else {
+                    continue;
+                };
+                let count = (found.len() / usize::from(*values_per_entry)) as u8;
+                control |= count << mask.trailing_zeros();
+                for value in found {
+                    data.extend(encint(*value));
+                }
+            }
+            body.push(control);
+            body.extend_from_slice(&data);
+        }
+        let idxt = 192 + body.len();
+        let mut record = indx_header(idxt as u32, entries.len() as u32, 0);
+        record.extend_from_slice(&body);
+        record.extend_from_slice(b"IDXT");
+        for offset in offsets {
+            record.extend_from_slice(&(offset as u16).to_be_bytes());
+        }
+        let mut records = vec![header, record];
+        if !cncx.is_empty() {
+            records.push(cncx.to_vec());
+        }
+        records
+    }
+
+    fn indx_header(idxt: u32, count: u32, cncx: u32) -> Vec<u8> {
+        let mut header = vec![0u8; 192];
+        header[..4].copy_from_slice(b"INDX");
+        header[4..8].copy_from_slice(&192u32.to_be_bytes());
+        header[20..24].copy_from_slice(&idxt.to_be_bytes());
+        header[24..28].copy_from_slice(&count.to_be_bytes());
+        header[52..56].copy_from_slice(&cncx.to_be_bytes());
+        header
+    }
+
+    #[test]
+    fn reads_entries_tags_and_cncx_strings() {
+        let mut cncx = encint(5);
+        cncx.extend_from_slice(b"Intro");
+        let records = build_index(
+            &[(1, 1, 0x01), (3, 1, 0x02), (6, 2, 0x0c)],
+            &[
+                ("0", vec![(1, vec![300]), (3, vec![0]), (6, vec![4, 1234])]),
+                ("1", vec![(1, vec![7])]),
+            ],
+            &cncx,
+        );
+        let (entries, strings) = read_index(|i| records.get(i).map(Vec::as_slice), 0).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ident, "0");
+        assert_eq!(entries[0].tag(1, 0), Some(300));
+        assert_eq!(entries[0].tag(6, 1), Some(1234));
+        assert_eq!(entries[1].tag(3, 0), None);
+        let label = entries[0].tag(3, 0).and_then(|offset| strings.get(offset));
+        assert_eq!(label, Some(&b"Intro"[..]));
+        assert_eq!(decint(&encint(1_000_000)), (1_000_000, 3));
+    }
+
+    #[test]
+    fn rejects_entry_counts_past_the_record() {
+        let mut records = build_index(&[(1, 1, 0x01)], &[("0", vec![(1, vec![7])])], &[]);
+        records[1][24..28].copy_from_slice(&u32::MAX.to_be_bytes());
+        let result = read_index(|i| records.get(i).map(Vec::as_slice), 0);
+        assert!(matches!(result, Err(MobiError::Parse(_))));
+    }
+}
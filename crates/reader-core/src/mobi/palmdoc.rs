@@ -0,0 +1,51 @@
+/// Expand one PalmDOC (LZ77) compressed text record onto `out`.
+pub(super) fn decompress(input: &[u8], out: &mut Vec<u8>) {
+    let start = out.len();
+    let mut i = 0;
+    while i < input.len() {
+        let c = input[i];
+        i += 1;
+        match c {
+            0x01..=0x08 => {
+                let end = (i + c as usize).min(input.len());
+                out.extend_from_slice(&input[i..end]);
+                i = end;
+            }
+            0x80..=0xbf => {
+                let Some(&next) = input.get(i) else {
+                    break;
+                };
+                i += 1;
+                let pair = (u16::from(c) << 8 | u16::from(next)) & 0x3fff;
+                let distance = usize::from(pair >> 3);
+                let length = usize::from(pair & 7) + 3;
+                // Copies may overlap what they write, so go byte by byte
+                if distance == 0 || distance > out.len() - start {
+                    continue;
+                }
+                for _ in 0..length {
+                    out.push(out[out.len() - distance]);
+                }
+            }
+            0xc0..=0xff => {
+                out.push(b' ');
+                out.push(c ^ 0x80);
+            }
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_literals_copies_and_space_pairs() {
+        // "abc", a three-byte run copied from distance 3, " d", raw bytes
+        let input = [b'a', b'b', b'c', 0x80, 0x18, 0xe4, 0x02, 0xc5, 0x00];
+        let mut out = Vec::new();
+        decompress(&input, &mut out);
+        assert_eq!(out, b"abcabc d\xc5\x00");
+    }
+}
@@ -0,0 +1,265 @@
+use std::path::Path;
+
+use super::header::{COMPRESSION_NONE, COMPRESSION_PALMDOC, NULL_INDEX};
+use super::index::tests::{build_index, encint};
+use super::*;
+use crate::types::{Block, Document};
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\nimage";
+
+struct Header {
+    compression: u16,
+    text_length: usize,
+    version: u32,
+    extra_flags: u16,
+    encryption: u16,
+    first_image: u32,
+    ncx: u32,
+    fragments: u32,
+    skeletons: u32,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            compression: COMPRESSION_NONE,
+            text_length: 0,
+            version: 6,
+            extra_flags: 0,
+            encryption: 0,
+            first_image: NULL_INDEX,
+            ncx: NULL_INDEX,
+            fragments: NULL_INDEX,
+            skeletons: NULL_INDEX,
+        }
+    }
+}
+
+fn record0(header: &Header, exth: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut record = vec![0u8; 16 + 264];
+    let mut put = |at: usize, value: u32| record[at..at + 4].copy_from_slice(&value.to_be_bytes());
+    put(4, header.text_length as u32);
+    put(20, 264);
+    put(24, 2);
+    put(28, 65001);
+    put(36, header.version);
+    put(104, header.version);
+    put(108, header.first_image);
+    put(112, NULL_INDEX);
+    put(128, 0x40);
+    put(0xf0, u32::from(header.extra_flags));
+    put(0xf4, header.ncx);
+    put(0xf8, header.fragments);
+    put(0xfc, header.skeletons);
+    record[..2].copy_from_slice(&header.compression.to_be_bytes());
+    record[16..20].copy_from_slice(b"MOBI");
+    record[8..10].copy_from_slice(&1u16.to_be_bytes());
+    record[12..14].copy_from_slice(&header.encryption.to_be_bytes());
+
+    let mut entries = Vec::new();
+    for (kind, value) in exth {
+        entries.extend_from_slice(&kind.to_be_bytes());
+        entries.extend_from_slice(&(8 + value.len() as u32).to_be_bytes());
+        entries.extend_from_slice(value);
+    }
+    record.extend_from_slice(b"EXTH");
+    record.extend_from_slice(&(12 + entries.len() as u32).to_be_bytes());
+    record.extend_from_slice(&(exth.len() as u32).to_be_bytes());
+    record.extend_from_slice(&entries);
+    record
+}
+
+fn palmdb(records: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = vec![0u8; 76];
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    let mut offset = data.len() + records.len() * 8 + 2;
+    for record in records {
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+        offset += record.len();
+    }
+    data.extend_from_slice(&[0; 2]);
+    for record in records {
+        data.extend_from_slice(record);
+    }
+    data
+}
+
+fn load(records: &[Vec<u8>]) -> Result<MobiBook, MobiError> {
+    MobiBook::from_bytes(Path::new("book.mobi"), palmdb(records))
+}
+
+fn paragraph<'a>(document: &'a Document, needle: &str) -> &'a str {
+    document
+        .blocks()
+        .iter()
+        .find_map(|block| match block {
+            Block::Paragraph(text) if text.contains(needle) => Some(text.as_str()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn has_image(document: &Document) -> bool {
+    document
+        .blocks()
+        .iter()
+        .any(|block| matches!(block, Block::Image(image) if image.data() == Some(PNG)))
+}
+
+#[test]
+fn mobi6_splits_on_page_breaks_and_resolves_filepos_links() {
+    let template = "<html><body><h1>One</h1><p>See <a filepos=0000000000>two</a>.</p>\
+                    <p><img recindex=\"00001\"></p><mbp:pagebreak/>\
+                    <h1>Two</h1><p>Second.</p></body></html>";
+    let target = template.find("<h1>Two").unwrap();
+    let text = template.replace("0000000000", &format!("{:010}", target));
+    // PalmDOC passes plain ASCII through; one trailing entry follows
+    let mut text_record = text.as_bytes().to_vec();
+    text_record.push(0x81);
+    let header = Header {
+        compression: COMPRESSION_PALMDOC,
+        text_length: text.len(),
+        extra_flags: 0b10,
+        first_image: 2,
+        ..Header::default()
+    };
+    let exth: &[(u32, &[u8])] = &[(100, b"Ann Author"), (503, b"Two Parts"), (524, b"en")];
+    let book = load(&[record0(&header, exth), text_record, PNG.to_vec()]).unwrap();
+
+    assert_eq!(book.metadata.main_title(), Some("Two Parts"));
+    assert_eq!(book.metadata.author_string().as_deref(), Some("Ann Author"));
+    assert_eq!(book.metadata.language(), Some("en"));
+    assert_eq!(book.chapter_count(), 2);
+    let document = book.to_document();
+    assert_eq!(document.chapter_titles(), &["One", "Two"]);
+    assert_eq!(document.chapter_hrefs(), &["part0000", "part0001"]);
+    let link = format!("\x1Cpart0001#filepos{}\x1D", target);
+    assert!(paragraph(&document, "See").contains(&link));
+    assert!(has_image(&document));
+}
+
+#[test]
+fn kf8_rebuilds_files_from_skeletons_and_fragments() {
+    let skeleton = "<html><body></body></html>";
+    let body_at = skeleton.find("</body>").unwrap();
+    let first = "<h1 id=\"one\">One</h1>\
+                 <p>Go <a href=\"kindle:pos:fid:0001:off:0000000000\">on</a>.</p>\
+                 <img src=\"kindle:embed:0001?mime=image/png\"/>";
+    let second = "<h1 id=\"two\">Two</h1><p>Second.</p>";
+    let second_start = skeleton.len() + first.len();
+    let text = [skeleton, first, skeleton, second].concat();
+
+    let skeletons = build_index(
+        &[(1, 1, 0x01), (6, 2, 0x0c)],
+        &[
+            (
+                "SKEL0000000",
+                vec![(1, vec![1]), (6, vec![0, skeleton.len() as u32])],
+            ),
+            (
+                "SKEL0000001",
+                vec![
+                    (1, vec![1]),
+                    (6, vec![second_start as u32, skeleton.len() as u32]),
+                ],
+            ),
+        ],
+        &[],
+    );
+    let first_insert = body_at.to_string();
+    let second_insert = (second_start + body_at).to_string();
+    let fragments = build_index(
+        &[(6, 2, 0x03)],
+        &[
+            (
+                first_insert.as_str(),
+                vec![(6, vec![0, first.len() as u32])],
+            ),
+            (
+                second_insert.as_str(),
+                vec![(6, vec![0, second.len() as u32])],
+            ),
+        ],
+        &[],
+    );
+    let mut cncx = encint(9);
+    cncx.extend_from_slice(b"Chapter 1");
+    let second_label = cncx.len() as u32;
+    cncx.extend(encint(9));
+    cncx.extend_from_slice(b"Chapter 2");
+    let ncx = build_index(
+        &[(3, 1, 0x01), (4, 1, 0x02), (6, 2, 0x0c)],
+        &[
+            ("0", vec![(3, vec![0]), (4, vec![0]), (6, vec![0, 0])]),
+            (
+                "1",
+                vec![(3, vec![second_label]), (4, vec![0]), (6, vec![1, 0])],
+            ),
+        ],
+        &cncx,
+    );
+
+    let header = Header {
+        text_length: text.len(),
+        version: 8,
+        first_image: 2,
+        skeletons: 3,
+        fragments: 3 + skeletons.len() as u32,
+        ncx: 3 + (skeletons.len() + fragments.len()) as u32,
+        ..Header::default()
+    };
+    let mut records = vec![record0(&header, &[]), text.into_bytes(), PNG.to_vec()];
+    records.extend(skeletons);
+    records.extend(fragments);
+    records.extend(ncx);
+    let book = load(&records).unwrap();
+
+    let document = book.to_document();
+    assert_eq!(document.chapter_titles(), &["Chapter 1", "Chapter 2"]);
+    let toc: Vec<(&str, &str)> = document
+        .toc_entries()
+        .iter()
+        .map(|entry| (entry.href(), entry.label()))
+        .collect();
+    assert_eq!(
+        toc,
+        vec![("part0000#one", "Chapter 1"), ("part0001#two", "Chapter 2")]
+    );
+    assert!(paragraph(&document, "Go").contains("\x1Cpart0001#two\x1D"));
+    assert!(paragraph(&document, "Second.").contains("Second."));
+    assert!(has_image(&document));
+}
+
+#[test]
+fn refuses_encrypted_files() {
+    let header = Header {
+        encryption: 2,
+        ..Header::default()
+    };
+    let result = load(&[record0(&header, &[]), Vec::new()]);
+    assert!(matches!(result, Err(MobiError::Encrypted)));
+    assert!(is_mobi_path(Path::new("/books/Dune.AZW3")));
+    assert!(!is_mobi_path(Path::new("dune.epub")));
+}
+
+#[test]
+fn keys_books_by_their_identifier() {
+    let text = "<html><body><p>Text.</p></body></html>";
+    let header = Header {
+        text_length: text.len(),
+        ..Header::default()
+    };
+    let exth: &[(u32, &[u8])] = &[(104, b"9780000000001")];
+    let book = load(&[record0(&header, exth), text.as_bytes().to_vec()]).unwrap();
+    assert_eq!(book.to_document().info().id(), "dc:9780000000001");
+
+    // A text length far past what the records hold is cut to what they do
+    let header = Header {
+        text_length: u32::MAX as usize,
+        ..Header::default()
+    };
+    let book = load(&[record0(&header, &[]), text.as_bytes().to_vec()]).unwrap();
+    assert!(book.to_document().info().id().starts_with("sha256:"));
+    assert!(paragraph(&book.to_document(), "Text.").contains("Text."));
+}
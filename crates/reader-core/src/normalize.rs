@@ -2,10 +2,12 @@ mod html;
 mod images;
 mod inline;
 mod postprocess;
+mod readability;
 mod table;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 pub use html::{html_to_blocks, html_to_blocks_with_assets, html_to_blocks_with_images};
 pub use postprocess::postprocess_blocks;
+pub use readability::{extract_article, Article};
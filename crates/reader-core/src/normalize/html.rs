@@ -59,6 +59,9 @@ where
         }
         match tag.as_str() {
             "p" => {
+                if let Some(img) = lone_image(node) {
+                    return image_block(&img, resolve_image);
+                }
                 let text = inline_text(node, ctx);
                 if text.is_empty() {
                     None
@@ -221,6 +224,29 @@ where
     blocks
 }
 
+// A paragraph holding just an image, and no anchor a link could target,
+// is a block image rather than a line of text. This applies to every
+// format, EPUB included, not only the MOBI `<p><img recindex>` pattern.
+fn lone_image(node: &NodeRef) -> Option<NodeRef> {
+    if !node.text_contents().trim().is_empty() {
+        return None;
+    }
+    let mut image = None;
+    for child in node.inclusive_descendants() {
+        let Some(el) = child.as_element() else {
+            continue;
+        };
+        let attrs = el.attributes.borrow();
+        if attrs.contains("id") || attrs.contains("name") {
+            return None;
+        }
+        if el.name.local.as_ref() == "img" && image.replace(child.clone()).is_some() {
+            return None;
+        }
+    }
+    image
+}
+
 fn image_block<F>(node: &NodeRef, resolve: &mut F) -> Option<Block>
 where
     F: FnMut(&str) -> Option<(String, Vec<u8>)>,
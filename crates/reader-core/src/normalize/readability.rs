@@ -0,0 +1,439 @@
+use kuchiki::{traits::*, NodeRef};
+
+/// The main article of a web page: its markup, ready for block conversion,
+/// and the title and byline found around it.
+pub struct Article {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub html: String,
+}
+
+// Pages whose best candidate holds less text than this are left whole
+const MIN_ARTICLE_CHARS: usize = 250;
+
+// Words in class and id attributes, after Mozilla Readability
+const UNLIKELY: &[&str] = &[
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumb",
+    "combx",
+    "comment",
+    "community",
+    "cookie",
+    "disqus",
+    "extra",
+    "footer",
+    "gdpr",
+    "header",
+    "legends",
+    "menu",
+    "modal",
+    "newsletter",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "subscribe",
+    "supplemental",
+];
+const MAYBE: &[&str] = &[
+    "and", "article", "body", "column", "content", "main", "shadow",
+];
+const POSITIVE: &[&str] = &[
+    "article", "body", "content", "entry", "hentry", "h-entry", "main", "page", "post", "story",
+    "text", "blog",
+];
+const NEGATIVE: &[&str] = &[
+    "hidden",
+    "banner",
+    "combx",
+    "comment",
+    "contact",
+    "foot",
+    "footer",
+    "footnote",
+    "masthead",
+    "media",
+    "meta",
+    "outbrain",
+    "promo",
+    "related",
+    "scroll",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "sponsor",
+    "shopping",
+    "tags",
+    "tool",
+    "widget",
+];
+const BYLINE: &[&str] = &["byline", "author", "dateline", "writtenby"];
+
+const DROPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "form", "button", "input", "select",
+    "textarea", "nav", "footer", "aside", "header", "dialog",
+];
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dl",
+    "div",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Find the main article of a page the way Mozilla Readability does: drop
+/// chrome such as navigation and footers, score paragraph containers by
+/// how much plain text they hold, discount link-heavy ones, and keep the
+/// best container with its strongest siblings. `None` when no candidate
+/// holds enough text to be an article.
+pub fn extract_article(html: &str) -> Option<Article> {
+    let document = kuchiki::parse_html().one(html);
+    let title = article_title(&document);
+    let bylines = byline_candidates(&document);
+    remove_unlikely(&document);
+    let body = document.select_first("body").ok()?.as_node().clone();
+
+    let mut scores: Vec<(NodeRef, f32)> = Vec::new();
+    for node in body.descendants() {
+        if !is_scored(&node) {
+            continue;
+        }
+        let text = inner_text(&node);
+        let length = text.chars().count();
+        if length < 25 {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f32 + (length as f32 / 100.0).min(3.0);
+        for (level, ancestor) in node.ancestors().take(3).enumerate() {
+            if tag(&ancestor).is_none_or(|tag| tag == "html") {
+                break;
+            }
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f32 * 3.0,
+            };
+            match scores
+                .iter_mut()
+                .find(|(candidate, _)| *candidate == ancestor)
+            {
+                Some((_, total)) => *total += score / divider,
+                None => {
+                    let initial = initial_score(&ancestor);
+                    scores.push((ancestor, initial + score / divider));
+                }
+            }
+        }
+    }
+    let final_score = |node: &NodeRef| {
+        scores
+            .iter()
+            .find(|(candidate, _)| candidate == node)
+            .map(|(_, score)| score * (1.0 - link_density(node)))
+    };
+    let (best, best_score) = scores
+        .iter()
+        .filter_map(|(node, _)| Some((node.clone(), final_score(node)?)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    // Siblings that score well, or read like paragraphs, belong to the
+    // article too
+    let threshold = (best_score * 0.2).max(10.0);
+    let content: Vec<NodeRef> = if best == body {
+        body.children().collect()
+    } else {
+        best.parent()
+            .map(|parent| {
+                parent
+                    .children()
+                    .filter(|sibling| {
+                        *sibling == best
+                            || final_score(sibling).is_some_and(|score| score >= threshold)
+                            || reads_like_paragraph(sibling)
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![best])
+    };
+    let byline = take_byline(&document, bylines, &content);
+    for node in &content {
+        clean(node);
+    }
+    let length: usize = content
+        .iter()
+        .map(|node| inner_text(node).chars().count())
+        .sum();
+    if length < MIN_ARTICLE_CHARS {
+        return None;
+    }
+    let mut html = String::from("<html><body>");
+    for node in &content {
+        html.push_str(&node.to_string());
+    }
+    html.push_str("</body></html>");
+    Some(Article {
+        title,
+        byline,
+        html,
+    })
+}
+
+fn tag(node: &NodeRef) -> Option<String> {
+    node.as_element().map(|el| el.name.local.to_lowercase())
+}
+
+fn class_and_id(node: &NodeRef) -> String {
+    let Some(el) = node.as_element() else {
+        return String::new();
+    };
+    let attrs = el.attributes.borrow();
+    let class = attrs.get("class").unwrap_or_default();
+    let id = attrs.get("id").unwrap_or_default();
+    format!("{} {}", class, id).to_lowercase()
+}
+
+fn matches_any(haystack: &str, words: &[&str]) -> bool {
+    words.iter().any(|word| haystack.contains(word))
+}
+
+fn inner_text(node: &NodeRef) -> String {
+    node.text_contents()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Paragraph-like nodes: text blocks, and divs used as paragraphs
+fn is_scored(node: &NodeRef) -> bool {
+    match tag(node).as_deref() {
+        Some("p" | "pre" | "td" | "section" | "h2" | "h3" | "h4" | "h5" | "h6") => true,
+        Some("div") => !node
+            .children()
+            .any(|child| tag(&child).is_some_and(|tag| BLOCK_TAGS.contains(&tag.as_str()))),
+        _ => false,
+    }
+}
+
+fn initial_score(node: &NodeRef) -> f32 {
+    let base = match tag(node).as_deref() {
+        Some("div" | "article") => 5.0,
+        Some("pre" | "td" | "blockquote") => 3.0,
+        Some("address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form") => -3.0,
+        Some("h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th") => -5.0,
+        _ => 0.0,
+    };
+    base + class_weight(node)
+}
+
+fn class_weight(node: &NodeRef) -> f32 {
+    let names = class_and_id(node);
+    let mut weight = 0.0;
+    if matches_any(&names, NEGATIVE) {
+        weight -= 25.0;
+    }
+    if matches_any(&names, POSITIVE) {
+        weight += 25.0;
+    }
+    weight
+}
+
+/// Share of a node's text that sits inside links.
+fn link_density(node: &NodeRef) -> f32 {
+    let length = inner_text(node).chars().count();
+    if length == 0 {
+        return 0.0;
+    }
+    let linked: usize = node
+        .select("a")
+        .map(|links| {
+            links
+                .map(|link| inner_text(link.as_node()).chars().count())
+                .sum()
+        })
+        .unwrap_or(0);
+    linked as f32 / length as f32
+}
+
+fn reads_like_paragraph(node: &NodeRef) -> bool {
+    if tag(node).as_deref() != Some("p") {
+        return false;
+    }
+    let text = inner_text(node);
+    let length = text.chars().count();
+    let density = link_density(node);
+    (length > 80 && density < 0.25)
+        || (length > 0 && density == 0.0 && (text.contains(". ") || text.ends_with('.')))
+}
+
+// Chrome by tag, and elements whose class or id says they are not content
+fn remove_unlikely(document: &NodeRef) {
+    let doomed: Vec<NodeRef> = document
+        .descendants()
+        .filter(|node| {
+            let Some(tag) = tag(node) else {
+                return false;
+            };
+            if DROPPED_TAGS.contains(&tag.as_str()) {
+                return true;
+            }
+            if matches!(tag.as_str(), "html" | "body" | "article" | "main" | "a") {
+                return false;
+            }
+            let names = class_and_id(node);
+            let role = node
+                .as_element()
+                .and_then(|el| el.attributes.borrow().get("role").map(str::to_string))
+                .unwrap_or_default();
+            matches!(
+                role.as_str(),
+                "navigation" | "banner" | "complementary" | "contentinfo" | "menu" | "dialog"
+            ) || (matches_any(&names, UNLIKELY) && !matches_any(&names, MAYBE))
+        })
+        .collect();
+    for node in doomed {
+        node.detach();
+    }
+}
+
+// Lists, tables and divs inside the article that are mostly links are
+// leftover navigation
+fn clean(root: &NodeRef) {
+    let doomed: Vec<NodeRef> = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                tag(node).as_deref(),
+                Some("div" | "section" | "ul" | "ol" | "table")
+            ) && {
+                let length = inner_text(node).chars().count();
+                // Containers without text may still hold pictures
+                let empty = length == 0
+                    && node.select_first("img").is_err()
+                    && node.select_first("figure").is_err();
+                class_weight(node) < 0.0 || (link_density(node) > 0.5 && length < 1000) || empty
+            }
+        })
+        .collect();
+    for node in doomed {
+        node.detach();
+    }
+}
+
+/// The page title without a trailing or leading site name, as in
+/// `Article title | Site`; `og:title` when there is no `<title>`.
+fn article_title(document: &NodeRef) -> Option<String> {
+    let full = document
+        .select_first("title")
+        .ok()
+        .map(|node| inner_text(node.as_node()))
+        .filter(|title| !title.is_empty())
+        .or_else(|| meta_content(document, &["og:title", "twitter:title"]))?;
+    for separator in [" | ", " - ", " – ", " — ", " » ", " :: "] {
+        let Some((head, tail)) = full.split_once(separator) else {
+            continue;
+        };
+        // The longer side is the article; a short one is the site
+        let (article, _) = if head.len() >= tail.len() {
+            (head, tail)
+        } else {
+            (tail, head)
+        };
+        if article.split_whitespace().count() >= 3 {
+            return Some(article.trim().to_string());
+        }
+    }
+    Some(full)
+}
+
+/// Short elements marked as bylines, each with whether it sits next to the
+/// page's `h1`; taken before the chrome around the article is dropped.
+fn byline_candidates(document: &NodeRef) -> Vec<(NodeRef, bool)> {
+    document
+        .descendants()
+        .filter(|node| {
+            let Some(el) = node.as_element() else {
+                return false;
+            };
+            let rel_author = el.attributes.borrow().get("rel") == Some("author");
+            let length = inner_text(node).chars().count();
+            (rel_author || matches_any(&class_and_id(node), BYLINE)) && length > 0 && length < 100
+        })
+        .map(|node| {
+            let near_title = node
+                .ancestors()
+                .take(2)
+                .take_while(|ancestor| tag(ancestor).is_some_and(|tag| tag != "body"))
+                .any(|ancestor| ancestor.select_first("h1").is_ok());
+            (node, near_title)
+        })
+        .collect()
+}
+
+/// The author from the page's metadata, or else the text of the first
+/// byline inside the article or next to its title. That element is removed
+/// so it does not repeat in the article; bylines of other stories on the
+/// page, such as teasers in a sidebar, are left alone.
+fn take_byline(
+    document: &NodeRef,
+    candidates: Vec<(NodeRef, bool)>,
+    content: &[NodeRef],
+) -> Option<String> {
+    let meta = meta_content(document, &["author", "article:author", "dc.creator"])
+        .filter(|author| !author.contains("://"));
+    let in_article = |node: &NodeRef| {
+        node.inclusive_ancestors()
+            .any(|ancestor| content.contains(&ancestor))
+    };
+    let node = candidates
+        .iter()
+        .find(|(node, _)| in_article(node))
+        .or_else(|| candidates.iter().find(|(_, near_title)| *near_title))
+        .map(|(node, _)| node.clone());
+    let text = node.as_ref().map(inner_text);
+    if let Some(node) = node {
+        node.detach();
+    }
+    meta.or(text)
+}
+
+fn meta_content(document: &NodeRef, names: &[&str]) -> Option<String> {
+    document.select("meta").ok()?.find_map(|node| {
+        let attrs = node.attributes.borrow();
+        let key = attrs.get("name").or_else(|| attrs.get("property"))?;
+        if !names.iter().any(|name| key.eq_ignore_ascii_case(name)) {
+            return None;
+        }
+        let content = attrs.get("content")?.trim();
+        (!content.is_empty()).then(|| content.to_string())
+    })
+}
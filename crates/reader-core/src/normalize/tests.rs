@@ -3,7 +3,7 @@ use crate::types::Block;
 use super::inline::{
     normalize_line, ANCHOR_END, ANCHOR_START, LINK_END, LINK_START, STYLE_END, STYLE_START,
 };
-use super::{html_to_blocks, postprocess_blocks};
+use super::{extract_article, html_to_blocks, postprocess_blocks};
 
 fn strip_inline_markers(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
@@ -130,3 +130,80 @@ fn captures_code_with_language() {
             if lang.as_deref() == Some("rust") && text.contains("fn main")
     ));
 }
+
+#[test]
+fn paragraph_with_only_an_image_is_an_image_block() {
+    let blocks = html_to_blocks(
+        "<p><span><img src=\"a.png\" alt=\"Map\"/></span></p>\
+         <p>Text <img src=\"b.png\" alt=\"icon\"/></p>\
+         <p id=\"fig\"><img src=\"c.png\"/></p>",
+    );
+    assert!(matches!(&blocks[0], Block::Image(image) if image.id() == "a.png"));
+    assert!(matches!(&blocks[1], Block::Paragraph(text) if text.starts_with("Text")));
+    assert!(matches!(&blocks[2], Block::Paragraph(_)));
+}
+
+pub(crate) const SAVED_PAGE: &str = r#"<html><head>
+  <title>How Rivers Carve Canyons Over Time | The Daily Site</title>
+</head><body>
+  <div class="site-header"><a href="/">Home</a> <a href="/news">News</a></div>
+  <nav><ul><li><a href="/a">Politics</a></li><li><a href="/b">Science</a></li></ul></nav>
+  <div id="main-article" class="post">
+    <h1>How Rivers Carve Canyons Over Time</h1>
+    <p class="byline">By Rita River</p>
+    <p>Water is patient. Over millions of years, a river cuts through layer after layer
+       of rock, carrying sand, gravel and boulders downstream as it goes.</p>
+    <p>The speed of the cut depends on the slope, the volume of water, and the hardness
+       of the rock, which is why some canyons are narrow and others wide.</p>
+    <div class="share-links"><a href="/fb">Share</a> <a href="/tw">Tweet</a></div>
+    <p>Floods matter most: a single storm can move more rock than a decade of calm flow,
+       scouring the bed and undercutting the walls.</p>
+  </div>
+  <div class="sidebar"><h3>Popular</h3><ul><li><a href="/x">Ten cats, ranked</a></li></ul></div>
+  <footer>Copyright, all rights reserved.</footer>
+</body></html>"#;
+
+#[test]
+fn article_extraction_keeps_the_story_and_drops_the_chrome() {
+    let article = extract_article(SAVED_PAGE).expect("article");
+    assert_eq!(
+        article.title.as_deref(),
+        Some("How Rivers Carve Canyons Over Time")
+    );
+    assert_eq!(article.byline.as_deref(), Some("By Rita River"));
+    let text: Vec<String> = html_to_blocks(&article.html)
+        .iter()
+        .filter_map(|block| match block {
+            Block::Paragraph(text) | Block::Heading(text, _) => Some(strip_inline_markers(text)),
+            _ => None,
+        })
+        .collect();
+    assert!(text[0].starts_with("How Rivers Carve"));
+    assert_eq!(text.iter().filter(|t| t.starts_with("Water is")).count(), 1);
+    assert!(text.iter().any(|t| t.starts_with("Floods matter")));
+    let joined = text.join("\n");
+    for chrome in ["Home", "Politics", "Tweet", "Ten cats", "Copyright", "Rita"] {
+        assert!(!joined.contains(chrome), "{chrome} left in the article");
+    }
+}
+
+#[test]
+fn article_extraction_gives_up_on_short_pages() {
+    let html = "<html><head><title>Hi</title></head><body><p>Just a note.</p></body></html>";
+    assert!(extract_article(html).is_none());
+}
+
+#[test]
+fn article_extraction_ignores_bylines_outside_the_article() {
+    let page = SAVED_PAGE
+        .replace(
+            r#"<p class="byline">By Rita River</p>"#,
+            "<p>Reporting from the gorge.</p>",
+        )
+        .replace(
+            "<h3>Popular</h3>",
+            r#"<h3>Popular</h3><span class="byline">By Sam Sidebar</span>"#,
+        );
+    let article = extract_article(&page).expect("article");
+    assert_eq!(article.byline, None);
+}
@@ -95,7 +95,7 @@ impl PdfRsBackend {
     fn walk_outline_item(
         &self,
         item_ref: &pdf::object::Ref<pdf::object::OutlineItem>,
-        _depth: usize,
+        depth: usize,
         resolver: &impl Resolve,
         out: &mut Vec<OutlineEntry>,
     ) -> Result<(), PdfError> {
@@ -107,14 +107,15 @@ impl PdfRsBackend {
                 out.push(OutlineEntry {
                     title: title.to_string_lossy(),
                     page_index: dest_page,
+                    level: depth,
                 });
             }
         }
         if let Some(first) = &item.first {
-            self.walk_outline_item(first, _depth + 1, resolver, out)?;
+            self.walk_outline_item(first, depth + 1, resolver, out)?;
         }
         if let Some(next) = &item.next {
-            self.walk_outline_item(next, _depth, resolver, out)?;
+            self.walk_outline_item(next, depth, resolver, out)?;
         }
         Ok(())
     }
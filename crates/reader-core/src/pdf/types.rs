@@ -22,6 +22,7 @@ pub struct PdfSummary {
 pub struct OutlineEntry {
     pub title: String,
     pub page_index: usize,
+    pub level: usize, // depth in the outline, 0 at the top
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,340 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::layout::Page;
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("invalid pattern: {0}")]
+    Pattern(String),
+}
+
+impl From<regex::Error> for SearchError {
+    // Syntax errors span several lines with the pattern and a caret; the
+    // last line holds the reason
+    fn from(err: regex::Error) -> Self {
+        let message = err.to_string();
+        let reason = message.lines().last().unwrap_or_default();
+        SearchError::Pattern(reason.trim_start_matches("error: ").to_string())
+    }
+}
+
+/// How letter case is compared. `Smart` ignores case unless the query
+/// contains an uppercase letter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Insensitive,
+    Smart,
+    Sensitive,
+}
+
+impl CaseMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            CaseMode::Insensitive => "ignore case",
+            CaseMode::Smart => "smart case",
+            CaseMode::Sensitive => "match case",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Insensitive => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Insensitive,
+        }
+    }
+}
+
+/// Search modes; the default is a case-insensitive substring match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub whole_word: bool,
+    pub case: CaseMode,
+    pub ignore_diacritics: bool, // "cafe" finds "café"
+}
+
+impl SearchOptions {
+    /// Short labels of the modes that are on, for prompts and status lines.
+    pub fn labels(&self) -> Vec<&'static str> {
+        let mut labels = Vec::new();
+        if self.regex {
+            labels.push("regex");
+        }
+        if self.whole_word {
+            labels.push("whole word");
+        }
+        labels.push(self.case.name());
+        if self.ignore_diacritics {
+            labels.push("ignore accents");
+        }
+        labels
+    }
+}
+
+/// A compiled query. Matches are byte ranges in the text searched.
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    query: String,
+    options: SearchOptions,
+    regex: Regex,
+}
+
+impl Matcher {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, SearchError> {
+        let query = query.trim().to_string();
+        let pattern = if options.ignore_diacritics {
+            strip_diacritics(&query)
+        } else {
+            query.clone()
+        };
+        let mut pattern = if options.regex {
+            pattern
+        } else {
+            regex::escape(&pattern)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        let ignore_case = match options.case {
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !query.chars().any(char::is_uppercase),
+            CaseMode::Sensitive => false,
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(Self {
+            query,
+            options,
+            regex,
+        })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// Non-empty matches in `text`, in order.
+    pub fn find_iter(&self, text: &str) -> Vec<Range<usize>> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+        if !self.options.ignore_diacritics {
+            return self
+                .regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect();
+        }
+        let folded = Folded::new(text);
+        self.regex
+            .find_iter(&folded.text)
+            .filter(|m| !m.is_empty())
+            .map(|m| folded.original(m.range()))
+            .collect()
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        !self.find_iter(text).is_empty()
+    }
+}
+
+fn strip_diacritics(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+// Text with diacritics removed, and for each byte of it, the original
+// char it came from.
+struct Folded {
+    text: String,
+    source: Vec<Range<usize>>,
+}
+
+impl Folded {
+    fn new(text: &str) -> Self {
+        let mut folded = String::with_capacity(text.len());
+        let mut source = Vec::with_capacity(text.len());
+        for (idx, ch) in text.char_indices() {
+            let span = idx..idx + ch.len_utf8();
+            for c in std::iter::once(ch).nfd().filter(|c| !is_combining_mark(*c)) {
+                folded.push(c);
+                source.extend(std::iter::repeat_n(span.clone(), c.len_utf8()));
+            }
+        }
+        Self {
+            text: folded,
+            source,
+        }
+    }
+
+    fn original(&self, range: Range<usize>) -> Range<usize> {
+        self.source[range.start].start..self.source[range.end - 1].end
+    }
+}
+
+/// The text of a page as one string for matching. Lines are joined with a
+/// space where the layout wrapped at whitespace, directly where it broke a
+/// word, and without the hyphen it added when hyphenating.
+pub struct PageText {
+    pub text: String,
+    lines: Vec<(usize, usize)>, // start in `text` and bytes kept of each line
+}
+
+impl PageText {
+    pub fn new(page: &Page) -> Self {
+        let mut text = String::new();
+        let mut lines = Vec::with_capacity(page.lines.len());
+        for (idx, line) in page.lines.iter().enumerate() {
+            let line_text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+            let here = page.positions.get(idx).copied().flatten();
+            let next = page.positions.get(idx + 1).copied().flatten();
+            let units = line.text_units();
+            let (kept, joiner) = match (here, next) {
+                (Some(here), Some(next)) if next.block == here.block => {
+                    if next.offset == here.offset + units {
+                        (line_text.len(), "")
+                    } else if line_text.ends_with('-') && next.offset + 1 == here.offset + units {
+                        (line_text.len() - 1, "")
+                    } else {
+                        (line_text.len(), " ")
+                    }
+                }
+                _ => (line_text.len(), " "),
+            };
+            lines.push((text.len(), kept));
+            text.push_str(&line_text[..kept]);
+            if idx + 1 < page.lines.len() {
+                text.push_str(joiner);
+            }
+        }
+        Self { text, lines }
+    }
+
+    /// Line holding byte `at` of the text.
+    pub fn line_at(&self, at: usize) -> usize {
+        self.lines
+            .partition_point(|(start, _)| *start <= at)
+            .saturating_sub(1)
+    }
+
+    /// The parts of `range` on each line it covers, as byte ranges into
+    /// that line's text.
+    pub fn line_ranges(&self, range: Range<usize>) -> Vec<(usize, Range<usize>)> {
+        let first = self.line_at(range.start);
+        self.lines[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, (start, _))| *start < range.end)
+            .filter_map(|(offset, (start, kept))| {
+                let from = range.start.max(*start) - start;
+                let to = range.end.min(start + kept) - start;
+                (from < to).then_some((first + offset, from..to))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{paginate_with_options, Hyphenator, LayoutOptions, Size};
+    use crate::types::Block;
+
+    fn find(query: &str, options: SearchOptions, text: &str) -> Vec<String> {
+        let matcher = Matcher::new(query, options).unwrap();
+        matcher
+            .find_iter(text)
+            .into_iter()
+            .map(|range| text[range].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn modes_change_what_matches() {
+        let text = "Café society met at the cafe. Cafés closed; CAFE opened.";
+        let plain = SearchOptions::default();
+        assert_eq!(find("cafe", plain, text), ["cafe", "CAFE"]);
+
+        let accents = SearchOptions {
+            ignore_diacritics: true,
+            ..plain
+        };
+        assert_eq!(
+            find("cafe", accents, text),
+            ["Café", "cafe", "Café", "CAFE"]
+        );
+
+        let words = SearchOptions {
+            whole_word: true,
+            ..accents
+        };
+        assert_eq!(find("cafe", words, text), ["Café", "cafe", "CAFE"]);
+
+        let smart = SearchOptions {
+            case: CaseMode::Smart,
+            ..accents
+        };
+        assert_eq!(find("cafe", smart, text).len(), 4);
+        assert_eq!(find("Cafe", smart, text), ["Café", "Café"]);
+
+        let regex = SearchOptions {
+            regex: true,
+            ..plain
+        };
+        assert_eq!(
+            find(r"caf\w+ \w+", regex, text),
+            ["Café society", "Cafés closed", "CAFE opened"]
+        );
+        let err = Matcher::new("caf(", regex).unwrap_err();
+        assert_eq!(err.to_string(), "invalid pattern: unclosed group");
+        assert_eq!(find("caf(", plain, text).len(), 0);
+    }
+
+    #[test]
+    fn page_text_rejoins_wrapped_and_hyphenated_words() {
+        let text = "Extraordinary representations of incomprehensible circumstances.";
+        let hyphenator = Hyphenator::for_language(Some("en")).unwrap();
+        let pagination = paginate_with_options(
+            &[Block::Paragraph(text.to_string())],
+            Size {
+                width: 16,
+                height: 20,
+            },
+            LayoutOptions {
+                hyphenator: Some(&hyphenator),
+                ..LayoutOptions::default()
+            },
+        );
+        let page = PageText::new(&pagination.pages[0]);
+        assert!(page.text.starts_with(text));
+
+        let matcher = Matcher::new("incomprehensible", SearchOptions::default()).unwrap();
+        let found = matcher.find_iter(&page.text);
+        assert_eq!(found.len(), 1);
+        let parts = page.line_ranges(found[0].clone());
+        assert_eq!(parts.len(), 2);
+        let line_text = |idx: usize| -> String {
+            pagination.pages[0].lines[idx]
+                .segments
+                .iter()
+                .map(|s| s.text.as_str())
+                .collect()
+        };
+        let pieces: Vec<String> = parts
+            .iter()
+            .map(|(line, range)| line_text(*line)[range.clone()].to_string())
+            .collect();
+        assert_eq!(pieces.concat(), "incomprehensible");
+    }
+}
@@ -1,9 +1,15 @@
 use crate::{
     config,
+    identity::is_path_key,
     types::{AppStateRecord, BookId, SpritzSession},
 };
 use serde_json;
-use std::{fs, io::Write, path::PathBuf};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 pub fn config_dir() -> Option<PathBuf> {
     config::config_root()
@@ -20,52 +26,167 @@ fn state_paths() -> Vec<PathBuf> {
     paths
 }
 
+fn spritz_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(primary) = config::config_root() {
+        paths.push(primary.join("spritz_sessions.json"));
+    }
+    for legacy in config::legacy_config_roots() {
+        paths.push(legacy.join("spritz_sessions.json"));
+    }
+    paths
+}
+
+// Records match by id; the path is only a fallback for records saved
+// before the book had a stable id.
+fn find_record(records: &[AppStateRecord], book: &BookId) -> Option<usize> {
+    records
+        .iter()
+        .position(|r| r.book().id() == book.id())
+        .or_else(|| {
+            records
+                .iter()
+                .position(|r| is_path_key(r.book().id()) && r.book().path() == book.path())
+        })
+}
+
+/// Saved state of every book, primary config first; a book recorded in
+/// several state files is only returned once.
+pub fn load_all_states() -> Vec<AppStateRecord> {
+    let mut all: Vec<AppStateRecord> = Vec::new();
+    for path in state_paths() {
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(records) = serde_json::from_slice::<Vec<AppStateRecord>>(&data) else {
+            continue;
+        };
+        for rec in records {
+            if !all.iter().any(|r| r.book().id() == rec.book().id()) {
+                all.push(rec);
+            }
+        }
+    }
+    all
+}
+
+/// The record for `book` among `records`, matched like `load_state`.
+pub fn find_state<'a>(records: &'a [AppStateRecord], book: &BookId) -> Option<&'a AppStateRecord> {
+    find_record(records, book).map(|idx| &records[idx])
+}
+
 pub fn load_state(book: &BookId) -> Option<AppStateRecord> {
     for path in state_paths() {
-        let data = fs::read(&path).ok()?;
-        let records: Vec<AppStateRecord> = serde_json::from_slice(&data).ok()?;
-        if let Some(rec) = records
-            .into_iter()
-            .find(|r| r.book().id() == book.id() || r.book().path() == book.path())
-        {
+        if let Some(rec) = load_state_from(&path, book) {
             return Some(rec);
         }
     }
     None
 }
 
+pub(crate) fn load_state_from(path: &Path, book: &BookId) -> Option<AppStateRecord> {
+    let data = fs::read(path).ok()?;
+    let mut records = serde_json::from_slice::<Vec<AppStateRecord>>(&data).ok()?;
+    let idx = find_record(&records, book)?;
+    Some(records.swap_remove(idx))
+}
+
 pub fn save_state(record: &AppStateRecord) -> std::io::Result<()> {
     let dir = config_dir()
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config dir"))?;
     fs::create_dir_all(&dir)?;
-    let path = dir.join("state.json");
-    let mut records: Vec<AppStateRecord> = fs::read(&path)
+    save_state_to(&dir.join("state.json"), record)
+}
+
+pub(crate) fn save_state_to(path: &Path, record: &AppStateRecord) -> std::io::Result<()> {
+    let mut records: Vec<AppStateRecord> = fs::read(path)
         .ok()
         .and_then(|d| serde_json::from_slice(&d).ok())
         .unwrap_or_default();
-    if let Some(existing) = records
-        .iter_mut()
-        .find(|r| r.book().id() == record.book().id() || r.book().path() == record.book().path())
-    {
-        *existing = record.clone();
-    } else {
-        records.push(record.clone());
+    let mut record = record.clone();
+    record.set_last_read(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs()),
+    );
+    match find_record(&records, record.book()) {
+        Some(idx) => records[idx] = record,
+        None => records.push(record),
     }
+    write_json(path, &records)
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
     let mut f = fs::File::create(path)?;
-    let s = serde_json::to_string_pretty(&records).unwrap_or_else(|_| "[]".into());
+    let s = serde_json::to_string_pretty(value).unwrap_or_else(|_| "[]".into());
     f.write_all(s.as_bytes())
 }
 
-pub fn load_spritz_session(book_id: &str) -> Option<SpritzSession> {
-    let mut paths = Vec::new();
-    if let Some(primary) = config::config_root() {
-        paths.push(primary.join("spritz_sessions.json"));
+/// Give records a new identity where `resolve` returns one, in every state
+/// file, and carry spritz sessions over to the new ids. Returns the old id
+/// and new identity of each migrated book.
+pub(crate) fn migrate_book_ids(
+    resolve: &dyn Fn(&BookId) -> Option<BookId>,
+) -> std::io::Result<Vec<(String, BookId)>> {
+    let mut migrated: Vec<(String, BookId)> = Vec::new();
+    for path in state_paths() {
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(mut records) = serde_json::from_slice::<Vec<AppStateRecord>>(&data) else {
+            continue;
+        };
+        let mut changed = false;
+        for rec in &mut records {
+            let old = rec.book().id().to_string();
+            let known = migrated
+                .iter()
+                .find(|(id, _)| *id == old)
+                .map(|(_, book)| book.clone());
+            let book = match known {
+                Some(book) => book,
+                None => {
+                    let Some(book) = resolve(rec.book()) else {
+                        continue;
+                    };
+                    migrated.push((old, book.clone()));
+                    book
+                }
+            };
+            rec.set_book(book);
+            changed = true;
+        }
+        if changed {
+            write_json(&path, &records)?;
+        }
     }
-    for legacy in config::legacy_config_roots() {
-        paths.push(legacy.join("spritz_sessions.json"));
+    if migrated.is_empty() {
+        return Ok(migrated);
     }
+    for path in spritz_paths() {
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(mut sessions) = serde_json::from_slice::<Vec<SpritzSession>>(&data) else {
+            continue;
+        };
+        let mut changed = false;
+        for session in &mut sessions {
+            if let Some((_, book)) = migrated.iter().find(|(id, _)| id == session.book_id()) {
+                session.set_book_id(book.id());
+                changed = true;
+            }
+        }
+        if changed {
+            write_json(&path, &sessions)?;
+        }
+    }
+    Ok(migrated)
+}
 
-    for path in paths {
+pub fn load_spritz_session(book_id: &str) -> Option<SpritzSession> {
+    for path in spritz_paths() {
         if let Ok(data) = fs::read(&path) {
             if let Ok(sessions) = serde_json::from_slice::<Vec<SpritzSession>>(&data) {
                 if let Some(session) = sessions.into_iter().find(|s| s.book_id() == book_id) {
@@ -100,3 +221,45 @@ pub fn save_spritz_session(session: &SpritzSession) -> std::io::Result<()> {
     let s = serde_json::to_string_pretty(&sessions).unwrap_or_else(|_| "[]".into());
     f.write_all(s.as_bytes())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DocumentFormat, Location};
+
+    fn record(id: &str, path: &str, page: usize) -> AppStateRecord {
+        let book = BookId::new(id, path, None, DocumentFormat::Epub3);
+        AppStateRecord::new(book, Location::new(0, page), Vec::new())
+    }
+
+    #[test]
+    fn records_match_by_id_then_legacy_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        save_state_to(&path, &record("path:/books/a.epub", "/books/a.epub", 3)).unwrap();
+        save_state_to(&path, &record("dc:urn:uuid:b", "/books/b.epub", 5)).unwrap();
+
+        // A legacy record is found through its path until it has an id
+        let a = BookId::new(
+            "dc:urn:uuid:a",
+            "/books/a.epub",
+            None,
+            DocumentFormat::Epub3,
+        );
+        let loaded = load_state_from(&path, &a).unwrap();
+        assert_eq!(loaded.last_location().offset(), 3);
+        assert!(loaded.last_read().is_some());
+
+        // A moved book is found by id; another book now at its old path is not
+        let moved = BookId::new("dc:urn:uuid:b", "/new/b.epub", None, DocumentFormat::Epub3);
+        assert_eq!(
+            load_state_from(&path, &moved)
+                .unwrap()
+                .last_location()
+                .offset(),
+            5
+        );
+        let other = BookId::new("sha256:00", "/books/b.epub", None, DocumentFormat::Epub3);
+        assert!(load_state_from(&path, &other).is_none());
+    }
+}
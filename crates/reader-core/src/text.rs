@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
+use crate::identity::bytes_key;
 use crate::types::{Block, Document, DocumentFormat, DocumentInfo};
 
 #[derive(Debug, Error)]
@@ -32,7 +33,7 @@ impl TextFile {
             .unwrap_or_else(|| "Untitled".to_string());
         let path_str = self.path.to_string_lossy().into_owned();
         let info = DocumentInfo::new(
-            format!("path:{}", path_str),
+            bytes_key(self.content.as_bytes()),
             path_str,
             Some(title.clone()),
             None,
@@ -62,7 +63,7 @@ fn detect_format(path: &Path) -> DocumentFormat {
     }
 }
 
-fn title_from_path(path: &Path) -> Option<String> {
+pub(crate) fn title_from_path(path: &Path) -> Option<String> {
     let stem = path.file_stem().and_then(|s| s.to_str())?;
     let title = prettify_title(stem);
     if title.is_empty() {
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::jumps::JumpList;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum DocumentFormat {
@@ -9,6 +11,10 @@ pub enum DocumentFormat {
     Text,
     Markdown,
     Pdf,
+    Fb2,
+    Mobi,
+    Cbz,
+    Html,
     #[serde(other)]
     Other,
 }
@@ -105,6 +111,9 @@ pub struct BookMetadata {
     titles: Vec<TitleEntry>,
     creators: Vec<CreatorEntry>,
     series: Option<SeriesInfo>,
+    language: Option<String>,
+    identifier: Option<String>,
+    right_to_left: bool,
 }
 
 impl BookMetadata {
@@ -117,6 +126,9 @@ impl BookMetadata {
             titles,
             creators,
             series,
+            language: None,
+            identifier: None,
+            right_to_left: false,
         }
     }
 
@@ -132,6 +144,33 @@ impl BookMetadata {
         self.series.as_ref()
     }
 
+    /// BCP 47 tag from `dc:language`, as written in the package.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    /// The package's unique `dc:identifier` (ISBN, UUID, ...).
+    pub fn identifier(&self) -> Option<&str> {
+        self.identifier.as_deref()
+    }
+
+    pub fn set_identifier(&mut self, identifier: Option<String>) {
+        self.identifier = identifier;
+    }
+
+    /// Pages run right to left, as in manga.
+    pub fn right_to_left(&self) -> bool {
+        self.right_to_left
+    }
+
+    pub fn set_right_to_left(&mut self, right_to_left: bool) {
+        self.right_to_left = right_to_left;
+    }
+
     pub fn main_title(&self) -> Option<&str> {
         self.titles
             .iter()
@@ -231,6 +270,14 @@ impl DocumentInfo {
         self.metadata.as_ref()
     }
 
+    pub fn language(&self) -> Option<&str> {
+        self.metadata.as_ref().and_then(|m| m.language())
+    }
+
+    pub fn right_to_left(&self) -> bool {
+        self.metadata.as_ref().is_some_and(|m| m.right_to_left())
+    }
+
     pub fn format(&self) -> DocumentFormat {
         self.format
     }
@@ -352,6 +399,7 @@ pub struct ImageBlock {
     caption: Option<String>,
     width: Option<u32>,
     height: Option<u32>,
+    page: bool,
 }
 
 impl ImageBlock {
@@ -370,6 +418,20 @@ impl ImageBlock {
             caption,
             width,
             height,
+            page: false,
+        }
+    }
+
+    /// An image that fills a page of its own, like a comic page.
+    pub fn full_page(
+        id: impl Into<String>,
+        data: Vec<u8>,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Self {
+        Self {
+            page: true,
+            ..Self::new(id, Some(data), None, None, width, height)
         }
     }
 
@@ -397,6 +459,10 @@ impl ImageBlock {
         self.height
     }
 
+    pub fn is_page(&self) -> bool {
+        self.page
+    }
+
     pub(crate) fn alt_mut(&mut self) -> Option<&mut String> {
         self.alt.as_mut()
     }
@@ -508,8 +574,8 @@ impl RgbColor {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BookId {
-    id: String,   // sha256 or dc:identifier
-    path: String, // absolute path
+    id: String,   // see `identity::book_key`; legacy records use `path:<path>`
+    path: String, // path the book was opened from
     title: Option<String>,
     #[serde(default = "default_format")]
     format: DocumentFormat,
@@ -547,10 +613,16 @@ impl BookId {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Location {
     spine_index: usize,
-    offset: usize,
+    offset: usize, // page index at save time; fallback for records without an anchor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chapter_href: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block_index: Option<usize>, // relative to the chapter start
+    #[serde(default)]
+    char_offset: usize,
 }
 
 impl Location {
@@ -558,9 +630,45 @@ impl Location {
         Self {
             spine_index,
             offset,
+            chapter_href: None,
+            block_index: None,
+            char_offset: 0,
         }
     }
 
+    /// A location anchored to content so it survives reflow at a different width.
+    pub fn anchored(
+        spine_index: usize,
+        chapter_href: Option<String>,
+        block_index: usize,
+        char_offset: usize,
+        page: usize,
+    ) -> Self {
+        Self {
+            spine_index,
+            offset: page,
+            chapter_href,
+            block_index: Some(block_index),
+            char_offset,
+        }
+    }
+
+    pub fn is_anchored(&self) -> bool {
+        self.block_index.is_some()
+    }
+
+    pub fn chapter_href(&self) -> Option<&str> {
+        self.chapter_href.as_deref()
+    }
+
+    pub fn block_index(&self) -> Option<usize> {
+        self.block_index
+    }
+
+    pub fn char_offset(&self) -> usize {
+        self.char_offset
+    }
+
     pub fn spine_index(&self) -> usize {
         self.spine_index
     }
@@ -583,6 +691,11 @@ pub struct AppStateRecord {
     book: BookId,
     last_location: Location,
     bookmarks: Vec<Location>,
+    #[serde(default, skip_serializing_if = "JumpList::is_empty")]
+    jumps: JumpList,
+    // Seconds since the Unix epoch when the record was last saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_read: Option<u64>,
 }
 
 impl AppStateRecord {
@@ -591,13 +704,27 @@ impl AppStateRecord {
             book,
             last_location,
             bookmarks,
+            jumps: JumpList::default(),
+            last_read: None,
         }
     }
 
+    pub fn last_read(&self) -> Option<u64> {
+        self.last_read
+    }
+
+    pub fn set_last_read(&mut self, last_read: Option<u64>) {
+        self.last_read = last_read;
+    }
+
     pub fn book(&self) -> &BookId {
         &self.book
     }
 
+    pub fn set_book(&mut self, book: BookId) {
+        self.book = book;
+    }
+
     pub fn last_location(&self) -> &Location {
         &self.last_location
     }
@@ -609,6 +736,18 @@ impl AppStateRecord {
     pub fn bookmarks(&self) -> &[Location] {
         &self.bookmarks
     }
+
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<Location>) {
+        self.bookmarks = bookmarks;
+    }
+
+    pub fn jumps(&self) -> &JumpList {
+        &self.jumps
+    }
+
+    pub fn set_jumps(&mut self, jumps: JumpList) {
+        self.jumps = jumps;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -638,6 +777,10 @@ impl SpritzSession {
         &self.book_id
     }
 
+    pub fn set_book_id(&mut self, book_id: impl Into<String>) {
+        self.book_id = book_id.into();
+    }
+
     pub fn word_index(&self) -> usize {
         self.word_index
     }
@@ -1,5 +1,16 @@
+mod annotations;
+mod bookmarks;
+mod chapters;
 mod command;
+mod command_line;
+mod ex;
 mod footnotes;
+mod goto;
+mod hints;
+mod jumps;
+mod keymap;
+mod library;
+mod location;
 mod prefetch;
 mod run;
 mod search;
@@ -10,7 +21,9 @@ mod state;
 mod toc;
 mod types;
 
+pub use keymap::{KeyChord, Keymap, KeymapError};
 pub use state::App;
 pub use types::{
-    ChapterPrefetchRequest, IncomingChapter, IncomingPage, Mode, PrefetchRequest, SpritzSettings,
+    ChapterPrefetchRequest, GoTo, IncomingChapter, IncomingPage, Mode, OpenRequest, PendingSearch,
+    PrefetchRequest, RunExit, SpritzSettings,
 };
@@ -0,0 +1,165 @@
+use chrono::Utc;
+use reader_core::annotations::{save_annotations, Annotation, HighlightColor};
+use reader_core::layout::TextPosition;
+
+use crate::reader_view::{HighlightSpan, ReaderView, SelectionRange};
+use crate::views::{FootnoteView, NoteView};
+
+use super::selection::selection_text;
+use super::types::SearchCommand;
+use super::App;
+
+impl App {
+    /// Resolve stored annotations against the loaded blocks for rendering.
+    pub(super) fn refresh_highlights(&self, view: &mut ReaderView) {
+        view.highlights = self
+            .annotations
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, ann)| {
+                let start = self.position_for_location(ann.start())?;
+                let end = self.position_for_location(ann.end())?;
+                Some(HighlightSpan {
+                    start,
+                    end,
+                    color: ann.color().rgb(),
+                    has_note: ann.note().is_some(),
+                    annotation: idx,
+                })
+            })
+            .collect();
+    }
+
+    /// Highlight the selection, or cycle the color of the highlight under it;
+    /// cycling past the last color removes the highlight.
+    pub(super) fn toggle_highlight(&mut self, view: &mut ReaderView) {
+        let Some(selection) = view.selection else {
+            return;
+        };
+        match self.annotation_for_selection(view, selection) {
+            Some(idx) => match self.annotations[idx].color().next() {
+                Some(color) => self.annotations[idx].set_color(color),
+                None => {
+                    self.annotations.remove(idx);
+                }
+            },
+            None => {
+                let Some(ann) = self.annotation_from_selection(view, selection) else {
+                    return;
+                };
+                self.annotations.push(ann);
+            }
+        }
+        view.selection = None;
+        self.save_annotations();
+        self.refresh_highlights(view);
+    }
+
+    /// Open the note editor for the selected highlight, creating it if needed.
+    pub(super) fn start_note(&mut self, view: &mut ReaderView) {
+        let Some(selection) = view.selection else {
+            return;
+        };
+        let idx = match self.annotation_for_selection(view, selection) {
+            Some(idx) => idx,
+            None => {
+                let Some(ann) = self.annotation_from_selection(view, selection) else {
+                    return;
+                };
+                self.annotations.push(ann);
+                self.save_annotations();
+                self.refresh_highlights(view);
+                self.annotations.len() - 1
+            }
+        };
+        let note = self.annotations[idx].note().unwrap_or_default();
+        self.note_input = Some(NoteView::new(idx, note));
+        view.selection = None;
+    }
+
+    // Notes are edited with the same keys as the search prompt
+    pub(super) fn apply_note_command(&mut self, view: &mut ReaderView, command: SearchCommand) {
+        match command {
+            SearchCommand::Cancel => {
+                self.note_input = None;
+            }
+            SearchCommand::Backspace => {
+                if let Some(note) = &mut self.note_input {
+                    note.backspace();
+                }
+            }
+            SearchCommand::Insert(c) => {
+                if let Some(note) = &mut self.note_input {
+                    note.push_char(c);
+                }
+            }
+            SearchCommand::Submit => {
+                let Some(note) = self.note_input.take() else {
+                    return;
+                };
+                if let Some(ann) = self.annotations.get_mut(note.annotation) {
+                    ann.set_note(Some(note.text.trim().to_string()));
+                    self.save_annotations();
+                    self.refresh_highlights(view);
+                }
+            }
+        }
+    }
+
+    /// Show the note attached to a clicked highlight.
+    pub(super) fn maybe_open_note(&mut self, idx: usize) -> bool {
+        let Some(note) = self.annotations.get(idx).and_then(|ann| ann.note()) else {
+            return false;
+        };
+        self.footnote = Some(FootnoteView::note(note.to_string()));
+        true
+    }
+
+    fn annotation_for_selection(
+        &self,
+        view: &ReaderView,
+        selection: SelectionRange,
+    ) -> Option<usize> {
+        let (start, end) = selection_positions(view, selection)?;
+        view.highlights
+            .iter()
+            .find(|span| span.start < end && start < span.end)
+            .map(|span| span.annotation)
+    }
+
+    fn annotation_from_selection(
+        &self,
+        view: &ReaderView,
+        selection: SelectionRange,
+    ) -> Option<Annotation> {
+        let (start, end) = selection_positions(view, selection)?;
+        let text = selection_text(view, selection);
+        if text.trim().is_empty() {
+            return None;
+        }
+        let (first, last) = selection.normalized();
+        Some(Annotation::new(
+            self.location_for_position(start, first.page),
+            self.location_for_position(end, last.page),
+            HighlightColor::default(),
+            text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Utc::now().to_rfc3339(),
+        ))
+    }
+
+    fn save_annotations(&self) {
+        if let Some(book) = &self.book {
+            let _ = save_annotations(book, &self.annotations);
+        }
+    }
+}
+
+fn selection_positions(
+    view: &ReaderView,
+    selection: SelectionRange,
+) -> Option<(TextPosition, TextPosition)> {
+    let (start, end) = selection.normalized();
+    let start = view.position_at_point(start, true)?;
+    let end = view.position_at_point(end, false)?;
+    (start < end).then_some((start, end))
+}
@@ -0,0 +1,206 @@
+use reader_core::state::{load_state, save_state};
+use reader_core::types::{AppStateRecord, Location};
+
+use crate::reader_view::ReaderView;
+use crate::views::{BookmarkItem, BookmarkView};
+
+use super::types::Mode;
+use super::App;
+
+const SNIPPET_LEN: usize = 80;
+
+impl App {
+    pub(super) fn add_bookmark(&mut self, view: &ReaderView) {
+        if view.pages.is_empty() || !self.bookmarks_on_current_page(view).is_empty() {
+            return;
+        }
+        let location = self.location_for_view(view);
+        self.bookmarks.push(location);
+        self.bookmarks.sort_by_key(bookmark_order);
+        self.save_bookmarks(view);
+    }
+
+    pub(super) fn remove_bookmark(&mut self, view: &mut ReaderView) {
+        match self.mode {
+            Mode::Bookmarks => {
+                let Some(index) = self
+                    .bookmark_view
+                    .as_ref()
+                    .and_then(|list| list.current_item())
+                    .map(|item| item.index)
+                else {
+                    return;
+                };
+                self.bookmarks.remove(index);
+                self.save_bookmarks(view);
+                let selected = self.bookmark_view.as_ref().map_or(0, |list| list.selected);
+                let mut list = BookmarkView::new(self.build_bookmark_items(view));
+                list.selected = selected.min(list.items.len().saturating_sub(1));
+                self.bookmark_view = Some(list);
+            }
+            _ => {
+                let on_page = self.bookmarks_on_current_page(view);
+                if on_page.is_empty() {
+                    return;
+                }
+                for index in on_page.into_iter().rev() {
+                    self.bookmarks.remove(index);
+                }
+                self.save_bookmarks(view);
+            }
+        }
+    }
+
+    pub(super) fn open_bookmarks(&mut self, view: &ReaderView) {
+        let items = self.build_bookmark_items(view);
+        let mut list = BookmarkView::new(items);
+        if let Some(idx) = list
+            .items
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, item)| item.page.filter(|p| *p <= view.current).map(|_| i))
+        {
+            list.selected = idx;
+        }
+        self.bookmark_view = Some(list);
+        self.mode = Mode::Bookmarks;
+    }
+
+    pub(super) fn submit_bookmarks(&mut self, view: &mut ReaderView) {
+        let location = self
+            .bookmark_view
+            .as_ref()
+            .and_then(|list| list.current_item())
+            .and_then(|item| self.bookmarks.get(item.index))
+            .cloned();
+        if let Some(location) = location {
+            let from = self.location_for_view(view);
+            self.push_jump(view, from);
+            self.go_to_location(view, location);
+        }
+        self.mode = Mode::Reader;
+        self.bookmark_view = None;
+    }
+
+    fn build_bookmark_items(&self, view: &ReaderView) -> Vec<BookmarkItem> {
+        self.bookmarks
+            .iter()
+            .enumerate()
+            .map(|(index, location)| {
+                let page = self.bookmark_page(view, location);
+                let chapter_idx = self
+                    .loaded_chapter_for_location(location)
+                    .unwrap_or(location.spine_index());
+                let snippet = match page {
+                    Some(_) => self.bookmark_snippet(view, location).unwrap_or_default(),
+                    None => "(chapter not loaded yet)".to_string(),
+                };
+                BookmarkItem {
+                    index,
+                    chapter: view.chapter_label(chapter_idx),
+                    snippet,
+                    percent: self.bookmark_percent(view, location, page),
+                    page,
+                }
+            })
+            .collect()
+    }
+
+    fn bookmark_page(&self, view: &ReaderView, location: &Location) -> Option<usize> {
+        if location.is_anchored() {
+            self.page_for_location(view, location)
+        } else {
+            (location.offset() < view.pages.len()).then_some(location.offset())
+        }
+    }
+
+    pub(super) fn bookmarks_on_current_page(&self, view: &ReaderView) -> Vec<usize> {
+        let last_visible = if view.two_pane {
+            view.current + 1
+        } else {
+            view.current
+        };
+        self.bookmarks
+            .iter()
+            .enumerate()
+            .filter(|(_, location)| {
+                self.bookmark_page(view, location)
+                    .is_some_and(|page| page >= view.current && page <= last_visible)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn bookmark_snippet(&self, view: &ReaderView, location: &Location) -> Option<String> {
+        let pos = self.position_for_location(location)?;
+        let page = view.pages.get(self.page_for_location(view, location)?)?;
+        let first = page
+            .positions
+            .iter()
+            .rposition(|p| p.is_some_and(|p| p <= pos))
+            .unwrap_or(0);
+        let mut text = String::new();
+        for line in page.lines.iter().skip(first) {
+            let line_text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+            let line_text = line_text.trim();
+            if line_text.is_empty() {
+                if text.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(line_text);
+            if text.chars().count() >= SNIPPET_LEN {
+                break;
+            }
+        }
+        Some(text.chars().take(SNIPPET_LEN).collect())
+    }
+
+    fn bookmark_percent(
+        &self,
+        view: &ReaderView,
+        location: &Location,
+        page: Option<usize>,
+    ) -> Option<usize> {
+        if let Some(page) = page {
+            if self.all_chapters_loaded() && !view.pages.is_empty() {
+                return Some((page + 1) * 100 / view.pages.len());
+            }
+        }
+        if let Some(page_no) = location
+            .chapter_href()
+            .and_then(|href| href.strip_prefix("page:"))
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            let total = self.total_pages?;
+            return Some((page_no * 100 / total.max(1)).min(100));
+        }
+        let total = self.total_chapters.filter(|t| *t > 0)?;
+        Some((location.spine_index() * 100 / total).min(100))
+    }
+
+    fn save_bookmarks(&self, view: &ReaderView) {
+        let Some(book) = &self.book else {
+            return;
+        };
+        let mut record = load_state(book).unwrap_or_else(|| {
+            AppStateRecord::new(book.clone(), self.location_for_view(view), Vec::new())
+        });
+        record.set_bookmarks(self.bookmarks.clone());
+        let _ = save_state(&record);
+    }
+}
+
+fn bookmark_order(location: &Location) -> (usize, usize, usize, usize) {
+    (
+        location.spine_index(),
+        location.block_index().unwrap_or(0),
+        location.char_offset(),
+        location.offset(),
+    )
+}
@@ -0,0 +1,112 @@
+use crate::reader_view::ReaderView;
+
+use super::types::GoTo;
+use super::App;
+
+impl App {
+    /// Show the start of the `count`th next or previous chapter, or the
+    /// last or first one; going back from inside a chapter counts its own
+    /// start first. A chapter that has not streamed in yet is requested and
+    /// shown once it arrives.
+    pub(super) fn step_chapter(&mut self, view: &mut ReaderView, count: usize, forward: bool) {
+        let Some(idx) = view.current_chapter_index() else {
+            return;
+        };
+        if let Some(page) = self.streamed_pdf_page(idx) {
+            let target = if forward {
+                let last = self.total_pages.unwrap_or(usize::MAX);
+                (page < last).then(|| page.saturating_add(count).min(last))
+            } else {
+                (page > 1).then(|| page.saturating_sub(count).max(1))
+            };
+            match target {
+                Some(target) => self.go_to(view, GoTo::Page(target)),
+                None => self.status = Some(no_chapter(forward)),
+            }
+            return;
+        }
+        let target = if forward {
+            // Chapters starting on a page already on screen are skipped
+            let last_shown = view.current + usize::from(view.two_pane);
+            let mut next = idx + 1;
+            while view
+                .chapter_starts
+                .get(next)
+                .is_some_and(|start| *start <= last_shown)
+            {
+                next += 1;
+            }
+            let chapters = if self.all_chapters_loaded() {
+                view.chapter_starts.len()
+            } else {
+                self.total_chapters.unwrap_or(usize::MAX)
+            };
+            let last = chapters.saturating_sub(1);
+            (next <= last).then(|| (next + count.saturating_sub(1)).min(last))
+        } else {
+            let inside = view
+                .chapter_starts
+                .get(idx)
+                .is_some_and(|start| *start < view.current);
+            let from = idx + usize::from(inside);
+            (from > 0).then(|| from.saturating_sub(count))
+        };
+        match target {
+            Some(target) => self.go_to(view, GoTo::Chapter(target + 1)),
+            None => self.status = Some(no_chapter(forward)),
+        }
+    }
+
+    /// Show the page of the `count`th next or previous heading. Past the
+    /// last heading loaded, a book still streaming in goes on to its next
+    /// chapter.
+    pub(super) fn step_heading(&mut self, view: &mut ReaderView, count: usize, forward: bool) {
+        let skip = count.saturating_sub(1);
+        let target = if forward {
+            let last_shown = view.current + usize::from(view.two_pane);
+            view.heading_starts
+                .iter()
+                .filter(|page| **page > last_shown)
+                .nth(skip)
+        } else {
+            view.heading_starts
+                .iter()
+                .rev()
+                .filter(|page| **page < view.current)
+                .nth(skip)
+        };
+        match target.copied() {
+            Some(page) => {
+                let from = self.location_for_view(view);
+                self.show_page(view, page);
+                self.push_jump(view, from);
+            }
+            None if forward && !self.all_chapters_loaded() => self.step_chapter(view, 1, true),
+            None => {
+                self.status = Some(if forward {
+                    "no next heading".into()
+                } else {
+                    "no previous heading".into()
+                })
+            }
+        }
+    }
+
+    // Streamed PDFs have a chapter per page, in the order the pages arrived
+    fn streamed_pdf_page(&self, chapter: usize) -> Option<usize> {
+        self.incoming_pages.as_ref()?;
+        self.chapter_hrefs
+            .get(chapter)?
+            .strip_prefix("page:")?
+            .parse()
+            .ok()
+    }
+}
+
+fn no_chapter(forward: bool) -> String {
+    if forward {
+        "no next chapter".into()
+    } else {
+        "no previous chapter".into()
+    }
+}
@@ -1,16 +1,40 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::{Backend, Terminal};
+use reader_core::layout::LineBreaking;
 
 use crate::reader_view::ReaderView;
 use crate::search_view::SearchView;
 
-use super::settings::save_settings;
-use super::types::{Command, CommandOutcome, Mode, SearchCommand, SpritzSettings};
+use super::keymap::{KeyChord, Lookup};
+use super::types::{Command, CommandOutcome, Fold, GoTo, Mode, SearchCommand, SearchToggle};
 use super::App;
 
+const MAX_COUNT: usize = 99_999;
+
 impl Command {
-    pub(super) fn from_key(app: &App, key: KeyEvent) -> Option<Self> {
-        if app.search.is_some() {
+    /// Text fields take keys as typed; everything else goes through the
+    /// keymap.
+    pub(super) fn from_key(app: &mut App, key: KeyEvent) -> Option<Self> {
+        if let Some(search) = &app.search {
+            if search.results.is_some() {
+                return match key.code {
+                    KeyCode::Esc => Some(Command::Search(SearchCommand::Cancel)),
+                    KeyCode::Enter => Some(Command::Search(SearchCommand::Submit)),
+                    KeyCode::Char('j') | KeyCode::Down => Some(Command::NavigateDown(1)),
+                    KeyCode::Char('k') | KeyCode::Up => Some(Command::NavigateUp(1)),
+                    _ => None,
+                };
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) {
+                let toggle = match key.code {
+                    KeyCode::Char('r') => SearchToggle::Regex,
+                    KeyCode::Char('w') => SearchToggle::WholeWord,
+                    KeyCode::Char('a') => SearchToggle::Case,
+                    KeyCode::Char('d') => SearchToggle::Diacritics,
+                    _ => return None,
+                };
+                return Some(Command::ToggleSearchOption(toggle));
+            }
             return match key.code {
                 KeyCode::Esc => Some(Command::Search(SearchCommand::Cancel)),
                 KeyCode::Enter => Some(Command::Search(SearchCommand::Submit)),
@@ -19,57 +43,146 @@ impl Command {
                 _ => None,
             };
         }
+        if app.command_line.is_some() {
+            return match key.code {
+                KeyCode::Esc => Some(Command::Ex(SearchCommand::Cancel)),
+                KeyCode::Enter => Some(Command::Ex(SearchCommand::Submit)),
+                KeyCode::Backspace => Some(Command::Ex(SearchCommand::Backspace)),
+                KeyCode::Tab => Some(Command::ExComplete { back: false }),
+                KeyCode::BackTab => Some(Command::ExComplete { back: true }),
+                KeyCode::Up => Some(Command::ExHistory { older: true }),
+                KeyCode::Down => Some(Command::ExHistory { older: false }),
+                KeyCode::Char(c) => Some(Command::Ex(SearchCommand::Insert(c))),
+                _ => None,
+            };
+        }
+        if app.link_hints.is_some() {
+            return match key.code {
+                KeyCode::Esc => Some(Command::LinkHint(SearchCommand::Cancel)),
+                KeyCode::Enter => Some(Command::LinkHint(SearchCommand::Submit)),
+                KeyCode::Backspace => Some(Command::LinkHint(SearchCommand::Backspace)),
+                KeyCode::Char(c) => Some(Command::LinkHint(SearchCommand::Insert(c))),
+                _ => None,
+            };
+        }
+        if app.note_input.is_some() {
+            return match key.code {
+                KeyCode::Esc => Some(Command::Note(SearchCommand::Cancel)),
+                KeyCode::Enter => Some(Command::Note(SearchCommand::Submit)),
+                KeyCode::Backspace => Some(Command::Note(SearchCommand::Backspace)),
+                KeyCode::Char(c) => Some(Command::Note(SearchCommand::Insert(c))),
+                _ => None,
+            };
+        }
         if app.footnote.is_some() {
             return matches!(key.code, KeyCode::Esc).then_some(Command::CloseFootnote);
         }
         if app.show_help {
-            return matches!(key.code, KeyCode::Esc | KeyCode::Char('?'))
+            let command = app
+                .keymap
+                .resolve(app.mode, &mut app.pending_keys, KeyChord::from(key));
+            return (key.code == KeyCode::Esc || command == Some(Command::ToggleHelp))
                 .then_some(Command::CloseHelp);
         }
-
-        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-        match key.code {
-            KeyCode::Char('q') => Some(Command::Exit),
-            KeyCode::Char('c') if ctrl => Some(Command::Exit),
-            KeyCode::Esc => Some(Command::Cancel),
-            KeyCode::Enter => Some(Command::Submit),
-            KeyCode::Char('/') => Some(Command::StartSearch),
-            KeyCode::Char('t') => Some(Command::ToggleToc),
-            KeyCode::Char('s') => Some(Command::ToggleSpritz),
-            KeyCode::Char('?') => Some(Command::ToggleHelp),
-            KeyCode::Char('j') | KeyCode::Down => {
-                if ctrl && matches!(app.mode, Mode::Spritz) {
-                    Some(Command::SpritzAdvance(10))
-                } else if ctrl {
-                    None
-                } else {
-                    Some(Command::NavigateDown(1))
-                }
+        if app.mode == Mode::Toc && app.toc.as_ref().is_some_and(|toc| toc.filtering) {
+            return match key.code {
+                KeyCode::Esc => Some(Command::TocFilter(SearchCommand::Cancel)),
+                KeyCode::Enter => Some(Command::TocFilter(SearchCommand::Submit)),
+                KeyCode::Backspace => Some(Command::TocFilter(SearchCommand::Backspace)),
+                KeyCode::Down => Some(Command::NavigateDown(1)),
+                KeyCode::Up => Some(Command::NavigateUp(1)),
+                KeyCode::Char(c) => Some(Command::TocFilter(SearchCommand::Insert(c))),
+                _ => None,
+            };
+        }
+        if let Mode::Library = app.mode {
+            if app
+                .library
+                .as_ref()
+                .is_some_and(|library| library.filtering)
+            {
+                return match key.code {
+                    KeyCode::Esc => Some(Command::LibraryFilter(SearchCommand::Cancel)),
+                    KeyCode::Enter => Some(Command::LibraryFilter(SearchCommand::Submit)),
+                    KeyCode::Backspace => Some(Command::LibraryFilter(SearchCommand::Backspace)),
+                    KeyCode::Char(c) => Some(Command::LibraryFilter(SearchCommand::Insert(c))),
+                    _ => None,
+                };
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                if ctrl && matches!(app.mode, Mode::Spritz) {
-                    Some(Command::SpritzRewind(10))
-                } else if ctrl {
-                    None
-                } else {
-                    Some(Command::NavigateUp(1))
-                }
-            }
-            KeyCode::Char('h') | KeyCode::Left => Some(Command::AdjustWidth(-2)),
-            KeyCode::Char('l') | KeyCode::Right => Some(Command::AdjustWidth(2)),
-            KeyCode::PageDown => Some(Command::PageDown),
-            KeyCode::PageUp => Some(Command::PageUp),
-            KeyCode::Char('J') => Some(Command::ToggleJustify),
-            KeyCode::Char('b') => Some(Command::ToggleTwoPane),
-            KeyCode::Char(' ') => Some(Command::SpritzTogglePlay),
-            KeyCode::Char('r') => Some(Command::SpritzJumpToChapterStart),
-            KeyCode::Char('f') => Some(Command::SpritzJumpToChapterEnd),
-            KeyCode::Char('+') | KeyCode::Char('=') => Some(Command::SpritzAdjustWpm(10)),
-            KeyCode::Char('-') | KeyCode::Char('_') => Some(Command::SpritzAdjustWpm(-10)),
-            KeyCode::Char(']') => Some(Command::SpritzAdjustWpm(50)),
-            KeyCode::Char('[') => Some(Command::SpritzAdjustWpm(-50)),
-            _ => None,
+            if app
+                .library
+                .as_ref()
+                .is_some_and(|library| library.query.is_some())
+            {
+                return match key.code {
+                    KeyCode::Esc => Some(Command::LibraryQuery(SearchCommand::Cancel)),
+                    KeyCode::Enter => Some(Command::LibraryQuery(SearchCommand::Submit)),
+                    KeyCode::Backspace => Some(Command::LibraryQuery(SearchCommand::Backspace)),
+                    KeyCode::Char(c) => Some(Command::LibraryQuery(SearchCommand::Insert(c))),
+                    _ => None,
+                };
+            }
+        }
+        let chord = KeyChord::from(key);
+        if let Some(digit) = app.count_digit(chord) {
+            let count = app.count.unwrap_or(0).saturating_mul(10) + digit;
+            app.count = Some(count.min(MAX_COUNT));
+            return None;
+        }
+        let command = app.keymap.resolve(app.mode, &mut app.pending_keys, chord);
+        if command.is_none() && app.pending_keys.is_empty() {
+            app.count = None;
+        }
+        command
+    }
+
+    /// Apply a count typed before the command: motions move that much
+    /// further, `gg` and `G` go to that page, `zM` shows that many levels
+    /// of the table of contents.
+    pub(super) fn with_count(self, count: Option<usize>) -> Self {
+        let Some(count) = count else {
+            return self;
+        };
+        let scale = |delta: i16| delta.saturating_mul(count.min(i16::MAX as usize) as i16);
+        match self {
+            Command::NavigateDown(n) => Command::NavigateDown(n.saturating_mul(count)),
+            Command::NavigateUp(n) => Command::NavigateUp(n.saturating_mul(count)),
+            Command::PageDown(n) => Command::PageDown(n.saturating_mul(count)),
+            Command::PageUp(n) => Command::PageUp(n.saturating_mul(count)),
+            Command::SearchNext(n) => Command::SearchNext(n.saturating_mul(count)),
+            Command::SearchPrevious(n) => Command::SearchPrevious(n.saturating_mul(count)),
+            Command::JumpBack(n) => Command::JumpBack(n.saturating_mul(count)),
+            Command::JumpForward(n) => Command::JumpForward(n.saturating_mul(count)),
+            Command::SpritzAdvance(n) => Command::SpritzAdvance(n.saturating_mul(count)),
+            Command::SpritzRewind(n) => Command::SpritzRewind(n.saturating_mul(count)),
+            Command::NextChapter(n) => Command::NextChapter(n.saturating_mul(count)),
+            Command::PreviousChapter(n) => Command::PreviousChapter(n.saturating_mul(count)),
+            Command::NextHeading(n) => Command::NextHeading(n.saturating_mul(count)),
+            Command::PreviousHeading(n) => Command::PreviousHeading(n.saturating_mul(count)),
+            Command::AdjustWidth(delta) => Command::AdjustWidth(scale(delta)),
+            Command::SpritzAdjustWpm(delta) => Command::SpritzAdjustWpm(scale(delta)),
+            Command::GoTo(GoTo::Start | GoTo::End) => Command::GoTo(GoTo::Page(count)),
+            Command::Fold(Fold::Level(_)) => Command::Fold(Fold::Level(count)),
+            _ => self,
+        }
+    }
+}
+
+impl App {
+    // A digit not bound to anything starts or extends the count; 0 only
+    // extends one, so it can still be bound on its own.
+    fn count_digit(&self, chord: KeyChord) -> Option<usize> {
+        if !self.pending_keys.is_empty() || !chord.modifiers.is_empty() {
+            return None;
+        }
+        let KeyCode::Char(c) = chord.code else {
+            return None;
+        };
+        let digit = c.to_digit(10)? as usize;
+        if digit == 0 && self.count.is_none() {
+            return None;
         }
+        (self.keymap.lookup(self.mode, &[chord]) == Lookup::Unbound).then_some(digit)
     }
 }
 
@@ -88,6 +201,45 @@ impl App {
             Command::Search(search) => {
                 self.apply_search_command(view, search);
             }
+            Command::ToggleSearchOption(toggle) => {
+                self.toggle_search_option(toggle);
+            }
+            Command::Ex(line) => {
+                if let Some(ex) = self.apply_command_line(line) {
+                    return self.run_ex(ex, view, width, last_inner, terminal);
+                }
+            }
+            Command::ExComplete { back } => {
+                self.complete_command_line(back);
+            }
+            Command::ExHistory { older } => {
+                self.recall_command_line(older);
+            }
+            Command::LinkHint(hint) => {
+                self.apply_link_hint_command(view, hint);
+            }
+            Command::Note(note) => {
+                self.apply_note_command(view, note);
+            }
+            Command::TocFilter(filter) => {
+                self.apply_toc_filter(view, filter);
+            }
+            Command::LibraryFilter(filter) => {
+                self.apply_library_filter(filter);
+            }
+            Command::LibraryQuery(query) => {
+                self.apply_library_query(query);
+            }
+            Command::Highlight => {
+                if let Mode::Reader = self.mode {
+                    self.toggle_highlight(view);
+                }
+            }
+            Command::StartNote => {
+                if let Mode::Reader = self.mode {
+                    self.start_note(view);
+                }
+            }
             Command::CloseFootnote => {
                 self.footnote = None;
             }
@@ -102,15 +254,33 @@ impl App {
                     self.mode = Mode::Reader;
                     self.toc = None;
                 }
+                Mode::Bookmarks => {
+                    self.mode = Mode::Reader;
+                    self.bookmark_view = None;
+                }
                 Mode::Spritz => {
                     self.stop_spritz();
                 }
-                Mode::Reader => {}
+                Mode::Library => {
+                    if let Some(library) = self.library.as_mut().filter(|l| l.hits.is_some()) {
+                        library.hits = None;
+                    } else if self.library.as_ref().is_some_and(|l| !l.filter.is_empty()) {
+                        self.apply_library_filter(SearchCommand::Cancel);
+                    } else {
+                        self.close_library();
+                    }
+                }
+                Mode::Reader => {
+                    view.selection = None;
+                }
             },
             Command::Submit => match self.mode {
                 Mode::Toc => {
                     self.submit_toc(view);
                 }
+                Mode::Bookmarks => {
+                    self.submit_bookmarks(view);
+                }
                 Mode::Spritz => {
                     if let Some(spritz) = &mut self.spritz {
                         if !spritz.is_playing {
@@ -118,18 +288,119 @@ impl App {
                         }
                     }
                 }
+                Mode::Library => return Ok(self.submit_library(view)),
                 Mode::Reader => {}
             },
+            Command::StartSearch if matches!(self.mode, Mode::Toc) => {
+                if let Some(toc) = &mut self.toc {
+                    toc.filtering = true;
+                }
+            }
+            Command::StartSearch if matches!(self.mode, Mode::Library) => {
+                if let Some(library) = &mut self.library {
+                    library.filtering = true;
+                }
+            }
             Command::StartSearch => {
-                let search = if let Some(prev) = &self.last_search {
-                    SearchView::with_query(prev)
+                let mut search = if let Some(prev) = &self.last_search {
+                    SearchView::with_query(prev.query())
                 } else {
                     SearchView::new()
                 };
+                search.options = self.search_options;
                 self.search = Some(search);
             }
-            Command::ToggleToc => {
-                self.open_toc(view);
+            Command::SearchNext(n) | Command::SearchPrevious(n) => {
+                if let Mode::Reader = self.mode {
+                    self.step_search(view, matches!(command, Command::SearchNext(_)), n);
+                    view.last_key = Some("n/N search".into());
+                }
+            }
+            Command::JumpBack(n) | Command::JumpForward(n) => {
+                if let Mode::Reader = self.mode {
+                    if matches!(command, Command::JumpBack(_)) {
+                        self.jump_back(view, n);
+                    } else {
+                        self.jump_forward(view, n);
+                    }
+                    view.last_key = Some("^O/^I jump".into());
+                }
+            }
+            Command::StartCommandLine => {
+                if let Mode::Reader = self.mode {
+                    self.start_command_line();
+                }
+            }
+            Command::GoTo(target) => {
+                if let Mode::Reader = self.mode {
+                    self.go_to(view, target);
+                }
+            }
+            Command::NextChapter(count) | Command::PreviousChapter(count) => {
+                if let Mode::Reader = self.mode {
+                    self.step_chapter(view, count, matches!(command, Command::NextChapter(_)));
+                }
+            }
+            Command::NextHeading(count) | Command::PreviousHeading(count) => {
+                if let Mode::Reader = self.mode {
+                    self.step_heading(view, count, matches!(command, Command::NextHeading(_)));
+                }
+            }
+            Command::StartLinkHints => {
+                if let Mode::Reader = self.mode {
+                    self.start_link_hints(view);
+                }
+            }
+            Command::ToggleToc => match self.mode {
+                Mode::Reader => {
+                    self.open_toc(view);
+                }
+                Mode::Toc => {
+                    self.mode = Mode::Reader;
+                    self.toc = None;
+                }
+                Mode::Bookmarks | Mode::Spritz | Mode::Library => {}
+            },
+            Command::Fold(fold) => {
+                if let Mode::Toc = self.mode {
+                    self.fold_toc(fold);
+                }
+            }
+            Command::ToggleBookmarks => match self.mode {
+                Mode::Reader => {
+                    self.open_bookmarks(view);
+                }
+                Mode::Bookmarks => {
+                    self.mode = Mode::Reader;
+                    self.bookmark_view = None;
+                }
+                Mode::Toc | Mode::Spritz | Mode::Library => {}
+            },
+            Command::OpenLibrary => {
+                if let Mode::Reader = self.mode {
+                    self.open_library();
+                }
+            }
+            Command::CycleLibrarySort => {
+                if let Some(library) = self.library.as_mut().filter(|l| l.hits.is_none()) {
+                    library.cycle_sort();
+                }
+            }
+            Command::StartLibrarySearch => {
+                if let Some(library) = &mut self.library {
+                    library.query = Some(String::new());
+                }
+            }
+            Command::AddBookmark => {
+                if let Mode::Reader = self.mode {
+                    self.add_bookmark(view);
+                    view.last_key = Some("m bookmark".into());
+                }
+            }
+            Command::RemoveBookmark => {
+                if let Mode::Reader | Mode::Bookmarks = self.mode {
+                    self.remove_bookmark(view);
+                }
             }
             Command::ToggleSpritz => match self.mode {
                 Mode::Reader => {
@@ -138,8 +409,18 @@ impl App {
                 Mode::Spritz => {
                     self.stop_spritz();
                 }
-                Mode::Toc => {}
+                Mode::Toc | Mode::Bookmarks | Mode::Library => {}
             },
+            Command::NavigateDown(_) if self.search.is_some() => {
+                if let Some(search) = &mut self.search {
+                    search.down();
+                }
+            }
+            Command::NavigateUp(_) if self.search.is_some() => {
+                if let Some(search) = &mut self.search {
+                    search.up();
+                }
+            }
             Command::NavigateDown(lines) => match self.mode {
                 Mode::Reader => {
                     view.down(lines);
@@ -150,6 +431,16 @@ impl App {
                         toc.down();
                     }
                 }
+                Mode::Bookmarks => {
+                    if let Some(list) = &mut self.bookmark_view {
+                        list.down();
+                    }
+                }
+                Mode::Library => {
+                    if let Some(library) = &mut self.library {
+                        library.down();
+                    }
+                }
                 Mode::Spritz => {
                     if let Some(spritz) = &mut self.spritz {
                         spritz.fast_forward(lines);
@@ -166,51 +457,89 @@ impl App {
                         toc.up();
                     }
                 }
+                Mode::Bookmarks => {
+                    if let Some(list) = &mut self.bookmark_view {
+                        list.up();
+                    }
+                }
+                Mode::Library => {
+                    if let Some(library) = &mut self.library {
+                        library.up();
+                    }
+                }
                 Mode::Spritz => {
                     if let Some(spritz) = &mut self.spritz {
                         spritz.rewind(lines);
                     }
                 }
             },
-            Command::PageDown => {
+            Command::PageDown(n) => {
                 if let Mode::Reader = self.mode {
-                    view.down((height / 2) as usize);
+                    view.down(((height / 2) as usize).saturating_mul(n));
                     view.last_key = Some("PgDn".into());
                 }
             }
-            Command::PageUp => {
+            Command::PageUp(n) => {
                 if let Mode::Reader = self.mode {
-                    view.up((height / 2) as usize);
+                    view.up(((height / 2) as usize).saturating_mul(n));
                     view.last_key = Some("PgUp".into());
                 }
             }
             Command::AdjustWidth(delta) => match self.mode {
                 Mode::Reader => {
                     Self::apply_width_delta(width, delta);
+                    self.save_settings(view, *width);
                     self.reflow_view(view, terminal, *width, last_inner)?;
                     view.last_key = Some(if delta < 0 { "h/left" } else { "l/right" }.into());
                 }
                 Mode::Spritz => {
                     Self::apply_width_delta(width, delta);
+                    self.save_settings(view, *width);
                     *last_inner = (*width, last_inner.1);
                 }
-                Mode::Toc => {}
+                Mode::Toc | Mode::Bookmarks | Mode::Library => {}
             },
             Command::ToggleJustify => {
                 if let Mode::Reader = self.mode {
                     view.justify = !view.justify;
-                    save_settings(view.justify, view.two_pane, &SpritzSettings::default());
+                    self.save_settings(view, *width);
                     view.last_key = Some("J toggle".into());
                     self.reflow_view(view, terminal, *width, last_inner)?;
                 }
             }
+            Command::ToggleHyphenate => {
+                if let Mode::Reader = self.mode {
+                    view.hyphenate = !view.hyphenate;
+                    self.save_settings(view, *width);
+                    view.last_key = Some(
+                        if view.hyphenate {
+                            "Y hyphenate on"
+                        } else {
+                            "Y hyphenate off"
+                        }
+                        .into(),
+                    );
+                    self.reflow_view(view, terminal, *width, last_inner)?;
+                }
+            }
+            Command::ToggleLineBreaking => {
+                if let Mode::Reader = self.mode {
+                    view.line_breaking = match view.line_breaking {
+                        LineBreaking::Greedy => LineBreaking::Optimal,
+                        LineBreaking::Optimal => LineBreaking::Greedy,
+                    };
+                    self.save_settings(view, *width);
+                    view.last_key = Some(format!("O {} breaks", view.line_breaking.name()));
+                    self.reflow_view(view, terminal, *width, last_inner)?;
+                }
+            }
             Command::ToggleTwoPane => {
                 if let Mode::Reader = self.mode {
                     view.two_pane = !view.two_pane;
                     if view.two_pane {
                         view.current = view.current.saturating_sub(view.current % 2);
                     }
-                    save_settings(view.justify, view.two_pane, &SpritzSettings::default());
+                    self.save_settings(view, *width);
                     self.reflow_view(view, terminal, *width, last_inner)?;
                     view.last_key = Some(
                         if view.two_pane {
@@ -276,7 +605,7 @@ impl App {
         }
     }
 
-    fn reflow_view<B: Backend>(
+    pub(super) fn reflow_view<B: Backend>(
         &self,
         view: &mut ReaderView,
         terminal: &mut Terminal<B>,
@@ -285,10 +614,70 @@ impl App {
     ) -> std::io::Result<()> {
         let size = terminal
             .size()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
         let inner = ReaderView::inner_size(size.into(), width, view.two_pane);
         view.reflow(&self.blocks, inner);
         *last_inner = (inner.width, inner.height);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::Keymap;
+
+    fn key(c: char) -> KeyChord {
+        KeyChord::from(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn counts_scale_motions_instead_of_repeating_them() {
+        assert_eq!(Command::PageDown(1).with_count(None), Command::PageDown(1));
+        assert_eq!(
+            Command::PageDown(1).with_count(Some(5)),
+            Command::PageDown(5)
+        );
+        assert_eq!(
+            Command::SearchNext(1).with_count(Some(99_999)),
+            Command::SearchNext(99_999)
+        );
+        assert_eq!(
+            Command::JumpBack(1).with_count(Some(3)),
+            Command::JumpBack(3)
+        );
+        assert_eq!(
+            Command::AdjustWidth(-2).with_count(Some(3)),
+            Command::AdjustWidth(-6)
+        );
+        assert_eq!(
+            Command::GoTo(GoTo::End).with_count(Some(12)),
+            Command::GoTo(GoTo::Page(12))
+        );
+        assert_eq!(Command::Exit.with_count(Some(4)), Command::Exit);
+    }
+
+    #[test]
+    fn unbound_digits_start_a_count() {
+        let mut app = App::new();
+        assert_eq!(app.count_digit(key('5')), Some(5));
+        assert_eq!(app.count_digit(key('0')), None);
+        app.count = Some(5);
+        assert_eq!(app.count_digit(key('0')), Some(0));
+        assert_eq!(app.count_digit(key('j')), None);
+        assert_eq!(
+            app.count_digit(KeyChord::from(KeyEvent::new(
+                KeyCode::Char('5'),
+                KeyModifiers::CONTROL
+            ))),
+            None
+        );
+
+        // Not partway through a sequence, nor for a digit bound to an action
+        app.pending_keys = vec![key('g')];
+        assert_eq!(app.count_digit(key('5')), None);
+        app.pending_keys.clear();
+        app.keymap = Keymap::with_overrides([("reader", "5", "page-down")]).unwrap();
+        assert_eq!(app.count_digit(key('5')), None);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::command_line::CommandLineView;
+
+use super::ex::{completions, ExCommand};
+use super::types::SearchCommand;
+use super::App;
+
+impl App {
+    pub(super) fn start_command_line(&mut self) {
+        self.command_line = Some(CommandLineView::new());
+    }
+
+    // The prompt is edited with the same keys as the search prompt; a
+    // submitted command is returned for `run_ex`
+    pub(super) fn apply_command_line(&mut self, command: SearchCommand) -> Option<ExCommand> {
+        let line = self.command_line.as_mut()?;
+        match command {
+            SearchCommand::Cancel => self.command_line = None,
+            // Like vim, deleting past the colon closes the prompt
+            SearchCommand::Backspace if line.input.is_empty() => self.command_line = None,
+            SearchCommand::Backspace => line.backspace(),
+            SearchCommand::Insert(c) => line.push_char(c),
+            SearchCommand::Submit => {
+                let input = line.input.trim().to_string();
+                if input.is_empty() {
+                    self.command_line = None;
+                    return None;
+                }
+                match ExCommand::parse(&input) {
+                    Ok(command) => {
+                        self.command_line = None;
+                        self.push_history(&input);
+                        return Some(command);
+                    }
+                    Err(e) => line.error = Some(e),
+                }
+            }
+        }
+        None
+    }
+
+    pub(super) fn complete_command_line(&mut self, back: bool) {
+        if let Some(line) = &mut self.command_line {
+            line.complete(completions, back);
+        }
+    }
+
+    pub(super) fn recall_command_line(&mut self, older: bool) {
+        if let Some(line) = &mut self.command_line {
+            line.recall(&self.ex_history, older);
+        }
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ratatui::prelude::{Backend, Color, Terminal};
+use reader_core::export::{BookExport, ExportFormat};
+use reader_core::layout::LineBreaking;
+use reader_core::types::Location;
+
+use crate::reader_view::{color_name, color_names, parse_color, ReaderView, Theme};
+
+use super::types::{CommandOutcome, GoTo, OpenRequest};
+use super::App;
+
+const COMMANDS: &[&str] = &[
+    "bookmark",
+    "bookmarks",
+    "export",
+    "help",
+    "open",
+    "quit",
+    "set",
+    "theme",
+];
+const BOOKMARK_ACTIONS: &[&str] = &["add", "remove", "list"];
+const EXPORT_WORDS: &[&str] = &["notes", "md", "json"];
+const MAX_HISTORY: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingKind {
+    Flag,
+    Number { min: u16, max: u16 },
+    LineBreaking,
+    Color,
+}
+
+// The keys of settings.toml, the book's page order (not saved), then the
+// colors of `Theme`, which are saved once changed here
+const SETTINGS: &[(&str, SettingKind)] = &[
+    ("width", SettingKind::Number { min: 20, max: 400 }),
+    ("justify", SettingKind::Flag),
+    ("two_pane", SettingKind::Flag),
+    ("fit_screen", SettingKind::Flag),
+    ("hyphenate", SettingKind::Flag),
+    ("line_breaking", SettingKind::LineBreaking),
+    (
+        "spritz_wpm",
+        SettingKind::Number {
+            min: 100,
+            max: 1000,
+        },
+    ),
+    ("spritz_pause_on_punct", SettingKind::Flag),
+    (
+        "spritz_punct_pause_ms",
+        SettingKind::Number { min: 0, max: 2000 },
+    ),
+    ("right_to_left", SettingKind::Flag),
+    ("header_bg", SettingKind::Color),
+    ("header_fg", SettingKind::Color),
+    ("header_pad_bg", SettingKind::Color),
+    ("footer_bg", SettingKind::Color),
+    ("footer_fg", SettingKind::Color),
+    ("footer_pad_bg", SettingKind::Color),
+];
+
+/// What one `:set` argument does to its setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SetValue {
+    Show,
+    Toggle,
+    Flag(bool),
+    Number(u16),
+    LineBreaking(LineBreaking),
+    Color(Color),
+}
+
+/// A parsed `:` command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum ExCommand {
+    GoTo(GoTo),
+    Set(Vec<(&'static str, SetValue)>),
+    Theme(Theme),
+    AddBookmark,
+    RemoveBookmark,
+    ListBookmarks,
+    Export {
+        format: ExportFormat,
+        path: Option<PathBuf>,
+    },
+    Open(PathBuf),
+    Help,
+    Quit,
+}
+
+impl ExCommand {
+    /// Parse a command line without its colon. Commands can be shortened
+    /// to any unambiguous prefix, as in `:se` or `:q`.
+    pub(super) fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if let Some(target) = GoTo::parse(input) {
+            return Ok(ExCommand::GoTo(target));
+        }
+        let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let args = args.trim();
+        let command = expand(name, COMMANDS).ok_or_else(|| {
+            if name.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("not a page, percentage or chapter: {}", input)
+            } else {
+                format!("unknown command: {}", name)
+            }
+        })?;
+        match command {
+            "set" => parse_set(args),
+            "theme" => Theme::preset(args).map(ExCommand::Theme).ok_or_else(|| {
+                format!(
+                    "unknown theme `{}` (try {})",
+                    args,
+                    Theme::PRESETS.join(", ")
+                )
+            }),
+            "bookmarks" => Ok(ExCommand::ListBookmarks),
+            "bookmark" => {
+                match expand(if args.is_empty() { "add" } else { args }, BOOKMARK_ACTIONS) {
+                    Some("add") => Ok(ExCommand::AddBookmark),
+                    Some("remove") => Ok(ExCommand::RemoveBookmark),
+                    Some(_) => Ok(ExCommand::ListBookmarks),
+                    None => Err(format!(
+                        "bookmark takes add, remove or list, not `{}`",
+                        args
+                    )),
+                }
+            }
+            "export" => parse_export(args),
+            "open" if args.is_empty() => Err("open needs a path".into()),
+            "open" => Ok(ExCommand::Open(expand_home(args))),
+            "help" => Ok(ExCommand::Help),
+            "quit" => Ok(ExCommand::Quit),
+            other => Err(format!("unknown command: {}", other)),
+        }
+    }
+}
+
+/// `word` itself or the one entry of `names` it is a prefix of.
+fn expand(word: &str, names: &[&'static str]) -> Option<&'static str> {
+    if word.is_empty() {
+        return None;
+    }
+    if let Some(exact) = names.iter().find(|name| **name == word) {
+        return Some(exact);
+    }
+    let mut matches = names.iter().filter(|name| name.starts_with(word));
+    let first = matches.next()?;
+    matches.next().is_none().then_some(*first)
+}
+
+// `name`, `noname`, `name!`, `name?` and `name=value`, as in vim
+fn parse_set(args: &str) -> Result<ExCommand, String> {
+    if args.is_empty() {
+        return Err(format!(
+            "set takes one of {}",
+            SETTINGS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let mut changes = Vec::new();
+    for arg in args.split_whitespace() {
+        let (word, value) = match arg.split_once('=') {
+            Some((word, value)) => (word, Some(value)),
+            None => (arg, None),
+        };
+        let (word, suffix) = match word.strip_suffix(['!', '?']) {
+            Some(stripped) => (stripped, word.chars().last()),
+            None => (word, None),
+        };
+        let (name, kind, negated) = match setting(word) {
+            Some((name, kind)) => (name, kind, false),
+            None => match word.strip_prefix("no").and_then(setting) {
+                Some((name, SettingKind::Flag)) => (name, SettingKind::Flag, true),
+                _ => return Err(format!("unknown setting: {}", word)),
+            },
+        };
+        let value = match (kind, suffix, value) {
+            (_, Some('?'), None) => SetValue::Show,
+            (SettingKind::Flag, Some('!'), None) => SetValue::Toggle,
+            (SettingKind::Flag, None, None) => SetValue::Flag(!negated),
+            (_, None, None) => SetValue::Show,
+            (_, None, Some(value)) if !negated => parse_value(name, kind, value)?,
+            _ => return Err(format!("invalid argument: {}", arg)),
+        };
+        changes.push((name, value));
+    }
+    Ok(ExCommand::Set(changes))
+}
+
+fn setting(word: &str) -> Option<(&'static str, SettingKind)> {
+    let names: Vec<&'static str> = SETTINGS.iter().map(|(name, _)| *name).collect();
+    let name = expand(word, &names)?;
+    SETTINGS.iter().find(|(known, _)| *known == name).copied()
+}
+
+fn parse_value(name: &str, kind: SettingKind, value: &str) -> Result<SetValue, String> {
+    let invalid = |expected: &str| format!("{} takes {}, not `{}`", name, expected, value);
+    match kind {
+        SettingKind::Flag => match value.to_ascii_lowercase().as_str() {
+            "true" | "on" | "yes" => Ok(SetValue::Flag(true)),
+            "false" | "off" | "no" => Ok(SetValue::Flag(false)),
+            _ => Err(invalid("true or false")),
+        },
+        SettingKind::Number { min, max } => value
+            .parse()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .map(SetValue::Number)
+            .ok_or_else(|| invalid(&format!("a number from {} to {}", min, max))),
+        SettingKind::LineBreaking => LineBreaking::from_name(value)
+            .map(SetValue::LineBreaking)
+            .ok_or_else(|| invalid("greedy or optimal")),
+        SettingKind::Color => parse_color(value)
+            .map(SetValue::Color)
+            .ok_or_else(|| invalid("a color name or #rrggbb")),
+    }
+}
+
+// `export [notes] [md|json] [FILE]`; the format defaults to the file's
+// extension, then Markdown
+fn parse_export(args: &str) -> Result<ExCommand, String> {
+    let mut format = None;
+    let mut path = None;
+    for word in args.split_whitespace() {
+        if word == "notes" && format.is_none() && path.is_none() {
+            continue;
+        }
+        match ExportFormat::from_name(word) {
+            Some(named) if format.is_none() && path.is_none() => format = Some(named),
+            _ if path.is_none() => path = Some(expand_home(word)),
+            _ => return Err(format!("unexpected argument: {}", word)),
+        }
+    }
+    let format = format
+        .or_else(|| {
+            path.as_deref()
+                .and_then(Path::extension)
+                .and_then(|ext| ext.to_str())
+                .and_then(ExportFormat::from_name)
+        })
+        .unwrap_or(ExportFormat::Markdown);
+    Ok(ExCommand::Export { format, path })
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Completions for the last word of `input`, each the whole input with
+/// that word completed.
+pub(super) fn completions(input: &str) -> Vec<String> {
+    let (head, word) = match input.rfind(char::is_whitespace) {
+        Some(idx) => input.split_at(idx + 1),
+        None => ("", input),
+    };
+    let command = head
+        .split_whitespace()
+        .next()
+        .and_then(|name| expand(name, COMMANDS));
+    let candidates: Vec<String> = match command {
+        None if head.trim().is_empty() => words(COMMANDS),
+        Some("set") => match word.split_once('=') {
+            Some((name, _)) => match setting(name) {
+                Some((name, SettingKind::LineBreaking)) => prefixed(name, ["greedy", "optimal"]),
+                Some((name, SettingKind::Color)) => prefixed(name, color_names()),
+                Some((name, SettingKind::Flag)) => prefixed(name, ["true", "false"]),
+                _ => Vec::new(),
+            },
+            None => SETTINGS.iter().map(|(name, _)| name.to_string()).collect(),
+        },
+        Some("theme") => words(Theme::PRESETS),
+        Some("bookmark") => words(BOOKMARK_ACTIONS),
+        Some("export") if !word.contains('/') && !word.starts_with('~') => words(EXPORT_WORDS),
+        Some("export") | Some("open") => return path_completions(head, word),
+        _ => Vec::new(),
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word) && candidate != word)
+        .map(|candidate| format!("{}{}", head, candidate))
+        .collect()
+}
+
+fn words(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn prefixed<'a>(name: &str, values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|value| format!("{}={}", name, value))
+        .collect()
+}
+
+// Entries of the directory typed so far; directories end in `/` so the
+// next Tab descends into them
+fn path_completions(head: &str, word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(idx) => word.split_at(idx + 1),
+        None => ("", word),
+    };
+    let listed = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+    let Ok(entries) = fs::read_dir(listed) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            Some(if is_dir { name + "/" } else { name })
+        })
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("{}{}{}", head, dir, name))
+        .collect()
+}
+
+fn history_path() -> Option<PathBuf> {
+    reader_core::config::config_root().map(|dir| dir.join("command_history"))
+}
+
+/// Commands entered at the `:` prompt in earlier sessions, oldest first.
+pub(super) fn load_history() -> Vec<String> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn save_history(history: &[String]) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, history.join("\n") + "\n");
+}
+
+impl App {
+    /// Remember `input` for Up/Down at the prompt; a repeated command
+    /// moves to the end.
+    pub(super) fn push_history(&mut self, input: &str) {
+        self.ex_history.retain(|entry| entry != input);
+        self.ex_history.push(input.to_string());
+        let excess = self.ex_history.len().saturating_sub(MAX_HISTORY);
+        self.ex_history.drain(..excess);
+        save_history(&self.ex_history);
+    }
+
+    pub(super) fn run_ex<B: Backend>(
+        &mut self,
+        command: ExCommand,
+        view: &mut ReaderView,
+        width: &mut u16,
+        last_inner: &mut (u16, u16),
+        terminal: &mut Terminal<B>,
+    ) -> std::io::Result<CommandOutcome> {
+        match command {
+            ExCommand::GoTo(target) => self.go_to(view, target),
+            ExCommand::Set(changes) => {
+                let mut shown = Vec::new();
+                for (name, value) in changes {
+                    if value == SetValue::Show {
+                        shown.push(self.setting_value(view, *width, name));
+                    } else {
+                        self.apply_setting(view, width, name, value);
+                    }
+                }
+                if !shown.is_empty() {
+                    self.status = Some(shown.join("  "));
+                }
+                self.save_settings(view, *width);
+                if view.two_pane {
+                    view.current = view.current.saturating_sub(view.current % 2);
+                }
+                self.reflow_view(view, terminal, *width, last_inner)?;
+            }
+            ExCommand::Theme(theme) => {
+                self.theme = theme.clone();
+                view.theme = theme;
+                self.theme_changed = true;
+                self.save_settings(view, *width);
+            }
+            ExCommand::AddBookmark => {
+                if self.bookmarks_on_current_page(view).is_empty() {
+                    self.add_bookmark(view);
+                    self.status = Some("bookmarked this page".into());
+                } else {
+                    self.status = Some("this page is already bookmarked".into());
+                }
+            }
+            ExCommand::RemoveBookmark => {
+                if self.bookmarks_on_current_page(view).is_empty() {
+                    self.status = Some("no bookmark on this page".into());
+                } else {
+                    self.remove_bookmark(view);
+                    self.status = Some("removed the bookmark on this page".into());
+                }
+            }
+            ExCommand::ListBookmarks => self.open_bookmarks(view),
+            ExCommand::Export { format, path } => {
+                self.status = Some(match self.export_notes(view, format, path) {
+                    Ok(path) => format!("wrote {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+            ExCommand::Open(path) => {
+                if !path.is_file() {
+                    self.status = Some(format!("no such file: {}", path.display()));
+                } else {
+                    self.open_request = Some(OpenRequest {
+                        path: path.display().to_string(),
+                        location: None,
+                    });
+                    return Ok(CommandOutcome::Exit);
+                }
+            }
+            ExCommand::Help => self.show_help = true,
+            ExCommand::Quit => return Ok(CommandOutcome::Exit),
+        }
+        Ok(CommandOutcome::Continue)
+    }
+
+    fn apply_setting(
+        &mut self,
+        view: &mut ReaderView,
+        width: &mut u16,
+        name: &str,
+        value: SetValue,
+    ) {
+        let flag = |current: bool| match value {
+            SetValue::Toggle => !current,
+            SetValue::Flag(on) => on,
+            _ => current,
+        };
+        let spritz = &mut self.spritz_settings;
+        match (name, value) {
+            ("width", SetValue::Number(n)) => *width = n,
+            ("justify", _) => view.justify = flag(view.justify),
+            ("two_pane", _) => view.two_pane = flag(view.two_pane),
+            ("fit_screen", _) => view.fit_screen = flag(view.fit_screen),
+            ("right_to_left", _) => {
+                self.right_to_left = flag(self.right_to_left);
+                view.right_to_left = self.right_to_left;
+            }
+            ("hyphenate", _) => view.hyphenate = flag(view.hyphenate),
+            ("line_breaking", SetValue::LineBreaking(mode)) => view.line_breaking = mode,
+            ("spritz_wpm", SetValue::Number(n)) => spritz.wpm = n,
+            ("spritz_pause_on_punct", _) => spritz.pause_on_punct = flag(spritz.pause_on_punct),
+            ("spritz_punct_pause_ms", SetValue::Number(n)) => spritz.punct_pause_ms = n,
+            (field, SetValue::Color(color)) => {
+                self.theme.set_color(field, color);
+                view.theme.set_color(field, color);
+                self.theme_changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn setting_value(&self, view: &ReaderView, width: u16, name: &str) -> String {
+        let spritz = &self.spritz_settings;
+        let value = match name {
+            "width" => width.to_string(),
+            "justify" => view.justify.to_string(),
+            "two_pane" => view.two_pane.to_string(),
+            "fit_screen" => view.fit_screen.to_string(),
+            "right_to_left" => view.right_to_left.to_string(),
+            "hyphenate" => view.hyphenate.to_string(),
+            "line_breaking" => view.line_breaking.name().to_string(),
+            "spritz_wpm" => spritz.wpm.to_string(),
+            "spritz_pause_on_punct" => spritz.pause_on_punct.to_string(),
+            "spritz_punct_pause_ms" => spritz.punct_pause_ms.to_string(),
+            field => self.theme.color(field).map(color_name).unwrap_or_default(),
+        };
+        format!("{}={}", name, value)
+    }
+
+    /// Write highlights, notes and bookmarks like `librarian export`, by
+    /// default to `<book>-notes.<ext>` in the working directory.
+    fn export_notes(
+        &self,
+        view: &ReaderView,
+        format: ExportFormat,
+        path: Option<PathBuf>,
+    ) -> Result<PathBuf, String> {
+        let book = self.book.as_ref().ok_or("no book to export notes from")?;
+        let path = path.unwrap_or_else(|| {
+            let stem = Path::new(book.path())
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("book");
+            PathBuf::from(format!("{}-notes.{}", stem, format.extension()))
+        });
+        let export = BookExport::new(
+            book,
+            &self.annotations,
+            &self.bookmarks,
+            |loc: &Location| match self.loaded_chapter_for_location(loc) {
+                Some(idx) => view.chapter_label(idx),
+                None => format!("Chapter {}", loc.spine_index() + 1),
+            },
+        );
+        if export.is_empty() {
+            return Err("no highlights or bookmarks to export".into());
+        }
+        fs::write(&path, export.render(format))
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_settings() {
+        assert_eq!(ExCommand::parse("42"), Ok(ExCommand::GoTo(GoTo::Page(42))));
+        assert_eq!(ExCommand::parse("q"), Ok(ExCommand::Quit));
+        assert_eq!(
+            ExCommand::parse("se width=72 nojustify hyphenate! line_breaking=optimal two_pane?"),
+            Ok(ExCommand::Set(vec![
+                ("width", SetValue::Number(72)),
+                ("justify", SetValue::Flag(false)),
+                ("hyphenate", SetValue::Toggle),
+                (
+                    "line_breaking",
+                    SetValue::LineBreaking(LineBreaking::Optimal)
+                ),
+                ("two_pane", SetValue::Show),
+            ]))
+        );
+        assert_eq!(
+            ExCommand::parse("set nofit_screen right_to_left"),
+            Ok(ExCommand::Set(vec![
+                ("fit_screen", SetValue::Flag(false)),
+                ("right_to_left", SetValue::Flag(true)),
+            ]))
+        );
+        assert_eq!(
+            ExCommand::parse("set header_bg=#ff8000"),
+            Ok(ExCommand::Set(vec![(
+                "header_bg",
+                SetValue::Color(Color::Rgb(255, 128, 0))
+            )]))
+        );
+        assert_eq!(
+            ExCommand::parse("export notes json"),
+            Ok(ExCommand::Export {
+                format: ExportFormat::Json,
+                path: None
+            })
+        );
+        assert_eq!(
+            ExCommand::parse("export notes out.json"),
+            Ok(ExCommand::Export {
+                format: ExportFormat::Json,
+                path: Some(PathBuf::from("out.json"))
+            })
+        );
+        assert_eq!(ExCommand::parse("bookmark"), Ok(ExCommand::AddBookmark));
+        assert_eq!(
+            ExCommand::parse("bookmark rem"),
+            Ok(ExCommand::RemoveBookmark)
+        );
+        assert!(matches!(
+            ExCommand::parse("theme dracula"),
+            Ok(ExCommand::Theme(_))
+        ));
+
+        let err = |input: &str| ExCommand::parse(input).unwrap_err();
+        assert_eq!(err("fly"), "unknown command: fly");
+        // `b` could be bookmark or bookmarks
+        assert_eq!(err("b"), "unknown command: b");
+        assert_eq!(
+            err("set width=5"),
+            "width takes a number from 20 to 400, not `5`"
+        );
+        assert_eq!(err("set nowidth"), "unknown setting: nowidth");
+        assert!(err("theme neon").starts_with("unknown theme `neon`"));
+    }
+
+    #[test]
+    fn completes_the_last_word() {
+        assert_eq!(completions("th"), ["theme"]);
+        assert_eq!(completions("set hy"), ["set hyphenate"]);
+        assert_eq!(
+            completions("set line_breaking="),
+            ["set line_breaking=greedy", "set line_breaking=optimal"]
+        );
+        assert_eq!(
+            completions("theme "),
+            [
+                "theme default",
+                "theme gruvbox",
+                "theme dracula",
+                "theme tokyonight"
+            ]
+        );
+        assert_eq!(completions("export notes j"), ["export notes json"]);
+        assert!(completions("quit ").is_empty());
+    }
+}
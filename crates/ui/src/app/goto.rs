@@ -0,0 +1,137 @@
+use crate::reader_view::ReaderView;
+
+use super::types::{ChapterPrefetchRequest, GoTo, PrefetchRequest};
+use super::App;
+
+impl GoTo {
+    /// Parse the argument of `:`: `42` (page), `50%` or `ch 7`.
+    pub(super) fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(percent) = text.strip_suffix('%') {
+            let percent: u8 = percent.trim().parse().ok()?;
+            return (percent <= 100).then_some(GoTo::Percent(percent));
+        }
+        if let Some(chapter) = text
+            .strip_prefix("chapter")
+            .or_else(|| text.strip_prefix("ch"))
+        {
+            return positive(chapter).map(GoTo::Chapter);
+        }
+        positive(text).map(GoTo::Page)
+    }
+}
+
+fn positive(text: &str) -> Option<usize> {
+    text.trim().parse().ok().filter(|n| *n > 0)
+}
+
+impl App {
+    /// Show `target`, or stream in the pages or chapters it needs and show
+    /// it once they arrive.
+    pub(super) fn go_to(&mut self, view: &mut ReaderView, target: GoTo) {
+        let from = self.location_for_view(view);
+        match self.page_for_goto(view, target) {
+            Some(page) => {
+                self.pending_goto = None;
+                self.show_page(view, page);
+                self.push_jump(view, from);
+            }
+            None => {
+                self.request_goto(target);
+                self.pending_goto = Some((target, from));
+            }
+        }
+    }
+
+    pub(super) fn resolve_pending_goto(&mut self, view: &mut ReaderView) {
+        let Some((target, from)) = self.pending_goto.clone() else {
+            return;
+        };
+        if let Some(page) = self.page_for_goto(view, target) {
+            self.pending_goto = None;
+            self.show_page(view, page);
+            self.push_jump(view, from);
+        } else if self.all_chapters_loaded() {
+            self.pending_goto = None;
+        }
+    }
+
+    // Streamed PDFs go to pages of the document rather than of the screen
+    fn pdf_page(&self, target: GoTo) -> Option<usize> {
+        let total = self.total_pages.filter(|_| self.incoming_pages.is_some())?;
+        let page = match target {
+            GoTo::Start => 1,
+            GoTo::End => total,
+            GoTo::Page(n) | GoTo::Chapter(n) => n,
+            GoTo::Percent(percent) => (total * percent as usize).div_ceil(100),
+        };
+        Some(page.clamp(1, total.max(1)))
+    }
+
+    /// Page showing `target`, or None until what it needs is loaded.
+    fn page_for_goto(&self, view: &ReaderView, target: GoTo) -> Option<usize> {
+        let last = view.pages.len().checked_sub(1)?;
+        if let Some(page) = self.pdf_page(target) {
+            let href = format!("page:{}", page);
+            let chapter = self.chapter_hrefs.iter().position(|h| *h == href)?;
+            let start = *view.chapter_starts.get(chapter)?;
+            if target != GoTo::End {
+                return Some(start);
+            }
+            let next = view.chapter_starts.get(chapter + 1);
+            return Some(next.map_or(last, |next| next.saturating_sub(1)));
+        }
+        let loaded = self.all_chapters_loaded();
+        match target {
+            GoTo::Start => Some(0),
+            GoTo::End => loaded.then_some(last),
+            GoTo::Page(n) => (n <= view.pages.len() || loaded).then(|| (n - 1).min(last)),
+            GoTo::Percent(percent) => {
+                loaded.then(|| (view.pages.len() * percent as usize / 100).min(last))
+            }
+            GoTo::Chapter(n) => match view.chapter_starts.get(n - 1) {
+                Some(start) => Some(*start),
+                None => loaded.then(|| view.chapter_starts.last().copied().unwrap_or(0)),
+            },
+        }
+    }
+
+    // Pages and percentages of a book streamed by chapter are only known
+    // once every chapter is in, so those load the rest of the book.
+    fn request_goto(&self, target: GoTo) {
+        if let Some(page) = self.pdf_page(target) {
+            if let Some(tx) = &self.prefetch_tx {
+                let _ = tx.send(PrefetchRequest {
+                    start: page - 1,
+                    window: self.prefetch_window,
+                });
+            }
+        } else if let Some(tx) = &self.prefetch_chapter_tx {
+            let target_loaded = match target {
+                GoTo::Chapter(n) => n,
+                _ => self.total_chapters.unwrap_or(usize::MAX),
+            };
+            let _ = tx.send(ChapterPrefetchRequest {
+                target_loaded,
+                target_href: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_go_to_targets() {
+        assert_eq!(GoTo::parse("42"), Some(GoTo::Page(42)));
+        assert_eq!(GoTo::parse(" 50% "), Some(GoTo::Percent(50)));
+        assert_eq!(GoTo::parse("ch 7"), Some(GoTo::Chapter(7)));
+        assert_eq!(GoTo::parse("chapter7"), Some(GoTo::Chapter(7)));
+        assert_eq!(GoTo::parse("0"), None);
+        assert_eq!(GoTo::parse("101%"), None);
+        assert_eq!(GoTo::parse("ch"), None);
+        assert_eq!(GoTo::parse("set width"), None);
+    }
+}
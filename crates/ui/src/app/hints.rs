@@ -0,0 +1,59 @@
+use crate::reader_view::{LinkHints, ReaderView};
+
+use super::types::SearchCommand;
+use super::App;
+
+impl App {
+    /// `f`: label the links on screen; nothing happens on a page without links.
+    pub(super) fn start_link_hints(&mut self, view: &mut ReaderView) {
+        let hints = view.link_hints();
+        if hints.is_empty() {
+            view.last_key = Some("f: no links on this page".into());
+            return;
+        }
+        self.link_hints = Some(LinkHints::new(hints));
+    }
+
+    // Hint labels are typed with the same keys as the search prompt
+    pub(super) fn apply_link_hint_command(
+        &mut self,
+        view: &mut ReaderView,
+        command: SearchCommand,
+    ) {
+        let Some(hints) = &mut self.link_hints else {
+            return;
+        };
+        match command {
+            SearchCommand::Cancel => self.link_hints = None,
+            SearchCommand::Backspace => {
+                hints.typed.pop();
+            }
+            SearchCommand::Insert(c) => {
+                hints.typed.push(c.to_ascii_lowercase());
+                if let Some(hint) = hints.chosen().cloned() {
+                    self.link_hints = None;
+                    self.follow_link(view, &hint.target, Some(&hint.text));
+                } else if hints.candidates().next().is_none() {
+                    self.link_hints = None;
+                }
+            }
+            SearchCommand::Submit => {
+                if let Some(hint) = hints.single().cloned() {
+                    self.link_hints = None;
+                    self.follow_link(view, &hint.target, Some(&hint.text));
+                }
+            }
+        }
+    }
+
+    /// Open a footnote-like target in the popup, otherwise jump to it.
+    pub(super) fn follow_link(&mut self, view: &mut ReaderView, target: &str, label: Option<&str>) {
+        if self.maybe_open_footnote(view, target, label) {
+            return;
+        }
+        let from = self.location_for_view(view);
+        if self.jump_to_href(view, target) {
+            self.push_jump(view, from);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use reader_core::state::{load_state, save_state};
+use reader_core::types::{AppStateRecord, Location};
+
+use crate::reader_view::ReaderView;
+
+use super::App;
+
+impl App {
+    /// Remember `from` before a TOC, link, search, bookmark or go-to jump moves
+    /// away from it.
+    pub(super) fn push_jump(&mut self, view: &ReaderView, from: Location) {
+        if view.pages.is_empty() {
+            return;
+        }
+        self.jumps.push(from);
+        self.save_jumps(view);
+    }
+
+    /// Ctrl-O: back to where the last jump started, or `count` jumps back.
+    pub(super) fn jump_back(&mut self, view: &mut ReaderView, count: usize) {
+        let current = self.location_for_view(view);
+        let target = (0..count.max(1))
+            .map_while(|_| self.jumps.back(current.clone()))
+            .last();
+        if let Some(location) = target {
+            self.go_to_location(view, location);
+            self.save_jumps(view);
+        }
+    }
+
+    /// Ctrl-I / Tab: forward again after `jump_back`, `count` jumps at most.
+    pub(super) fn jump_forward(&mut self, view: &mut ReaderView, count: usize) {
+        let target = (0..count.max(1)).map_while(|_| self.jumps.forward()).last();
+        if let Some(location) = target {
+            self.go_to_location(view, location);
+            self.save_jumps(view);
+        }
+    }
+
+    /// Show `location`, falling back to its page index for records saved
+    /// before locations were anchored.
+    pub(super) fn go_to_location(&mut self, view: &mut ReaderView, location: Location) {
+        if location.is_anchored() {
+            self.restore_location(view, location);
+        } else {
+            view.current = location.offset().min(view.pages.len().saturating_sub(1));
+        }
+    }
+
+    fn save_jumps(&self, view: &ReaderView) {
+        let Some(book) = &self.book else {
+            return;
+        };
+        let mut record = load_state(book).unwrap_or_else(|| {
+            AppStateRecord::new(book.clone(), self.location_for_view(view), Vec::new())
+        });
+        record.set_jumps(self.jumps.clone());
+        let _ = save_state(&record);
+    }
+}
@@ -0,0 +1,769 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use thiserror::Error;
+
+use super::types::{Command, Fold, GoTo, Mode};
+
+const MODES: [(&str, Mode); 5] = [
+    ("reader", Mode::Reader),
+    ("toc", Mode::Toc),
+    ("bookmarks", Mode::Bookmarks),
+    ("spritz", Mode::Spritz),
+    ("library", Mode::Library),
+];
+
+// Action names used in the `[keymap.<mode>]` tables of config.toml
+const ACTIONS: &[(&str, Command)] = &[
+    ("quit", Command::Exit),
+    ("cancel", Command::Cancel),
+    ("select", Command::Submit),
+    ("help", Command::ToggleHelp),
+    ("down", Command::NavigateDown(1)),
+    ("up", Command::NavigateUp(1)),
+    ("page-down", Command::PageDown(1)),
+    ("page-up", Command::PageUp(1)),
+    ("narrow", Command::AdjustWidth(-2)),
+    ("widen", Command::AdjustWidth(2)),
+    ("search", Command::StartSearch),
+    ("search-next", Command::SearchNext(1)),
+    ("search-previous", Command::SearchPrevious(1)),
+    ("jump-back", Command::JumpBack(1)),
+    ("jump-forward", Command::JumpForward(1)),
+    ("link-hints", Command::StartLinkHints),
+    ("top", Command::GoTo(GoTo::Start)),
+    ("bottom", Command::GoTo(GoTo::End)),
+    ("next-chapter", Command::NextChapter(1)),
+    ("previous-chapter", Command::PreviousChapter(1)),
+    ("next-heading", Command::NextHeading(1)),
+    ("previous-heading", Command::PreviousHeading(1)),
+    ("command-line", Command::StartCommandLine),
+    ("toc", Command::ToggleToc),
+    ("expand", Command::Fold(Fold::Open)),
+    ("collapse", Command::Fold(Fold::Close)),
+    ("toggle-fold", Command::Fold(Fold::Toggle)),
+    ("fold-all", Command::Fold(Fold::Level(1))),
+    ("unfold-all", Command::Fold(Fold::All)),
+    ("bookmarks", Command::ToggleBookmarks),
+    ("add-bookmark", Command::AddBookmark),
+    ("remove-bookmark", Command::RemoveBookmark),
+    ("highlight", Command::Highlight),
+    ("note", Command::StartNote),
+    ("library", Command::OpenLibrary),
+    ("library-sort", Command::CycleLibrarySort),
+    ("library-search", Command::StartLibrarySearch),
+    ("spritz", Command::ToggleSpritz),
+    ("justify", Command::ToggleJustify),
+    ("hyphenate", Command::ToggleHyphenate),
+    ("line-breaking", Command::ToggleLineBreaking),
+    ("two-pane", Command::ToggleTwoPane),
+    ("play", Command::SpritzTogglePlay),
+    ("chapter-start", Command::SpritzJumpToChapterStart),
+    ("chapter-end", Command::SpritzJumpToChapterEnd),
+    ("faster", Command::SpritzAdjustWpm(10)),
+    ("slower", Command::SpritzAdjustWpm(-10)),
+    ("much-faster", Command::SpritzAdjustWpm(50)),
+    ("much-slower", Command::SpritzAdjustWpm(-50)),
+    ("skip-words", Command::SpritzAdvance(10)),
+    ("rewind-words", Command::SpritzRewind(10)),
+];
+
+const READER_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("<C-c>", "quit"),
+    ("<Esc>", "cancel"),
+    ("j", "down"),
+    ("<Down>", "down"),
+    ("k", "up"),
+    ("<Up>", "up"),
+    ("<PageDown>", "page-down"),
+    ("<PageUp>", "page-up"),
+    ("gg", "top"),
+    ("G", "bottom"),
+    ("]", "next-chapter"),
+    ("[", "previous-chapter"),
+    ("}", "next-heading"),
+    ("{", "previous-heading"),
+    (":", "command-line"),
+    ("h", "up"),
+    ("<Left>", "up"),
+    ("l", "down"),
+    ("<Right>", "down"),
+    ("<lt>", "narrow"),
+    (">", "widen"),
+    ("t", "toc"),
+    ("m", "add-bookmark"),
+    ("M", "remove-bookmark"),
+    ("'", "bookmarks"),
+    ("L", "library"),
+    ("H", "highlight"),
+    ("a", "note"),
+    ("/", "search"),
+    ("n", "search-next"),
+    ("N", "search-previous"),
+    ("f", "link-hints"),
+    ("<C-o>", "jump-back"),
+    ("<C-i>", "jump-forward"),
+    ("<Tab>", "jump-forward"),
+    ("s", "spritz"),
+    ("J", "justify"),
+    ("Y", "hyphenate"),
+    ("O", "line-breaking"),
+    ("b", "two-pane"),
+    ("?", "help"),
+];
+
+const TOC_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("<C-c>", "quit"),
+    ("j", "down"),
+    ("<Down>", "down"),
+    ("k", "up"),
+    ("<Up>", "up"),
+    ("<CR>", "select"),
+    ("l", "expand"),
+    ("<Right>", "expand"),
+    ("h", "collapse"),
+    ("<Left>", "collapse"),
+    ("<Space>", "toggle-fold"),
+    ("za", "toggle-fold"),
+    ("zM", "fold-all"),
+    ("zR", "unfold-all"),
+    ("/", "search"),
+    ("<Esc>", "cancel"),
+    ("t", "toc"),
+    ("?", "help"),
+];
+
+const BOOKMARK_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("<C-c>", "quit"),
+    ("j", "down"),
+    ("<Down>", "down"),
+    ("k", "up"),
+    ("<Up>", "up"),
+    ("<CR>", "select"),
+    ("d", "remove-bookmark"),
+    ("M", "remove-bookmark"),
+    ("<Esc>", "cancel"),
+    ("'", "bookmarks"),
+    ("?", "help"),
+];
+
+const SPRITZ_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("<C-c>", "quit"),
+    ("s", "spritz"),
+    ("<Esc>", "cancel"),
+    ("<Space>", "play"),
+    ("<CR>", "select"),
+    ("j", "down"),
+    ("<Down>", "down"),
+    ("k", "up"),
+    ("<Up>", "up"),
+    ("<C-j>", "skip-words"),
+    ("<C-Down>", "skip-words"),
+    ("<C-k>", "rewind-words"),
+    ("<C-Up>", "rewind-words"),
+    ("h", "narrow"),
+    ("<Left>", "narrow"),
+    ("l", "widen"),
+    ("<Right>", "widen"),
+    ("+", "faster"),
+    ("=", "faster"),
+    ("-", "slower"),
+    ("_", "slower"),
+    ("]", "much-faster"),
+    ("[", "much-slower"),
+    ("r", "chapter-start"),
+    ("f", "chapter-end"),
+    ("?", "help"),
+];
+
+const LIBRARY_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("<C-c>", "quit"),
+    ("j", "down"),
+    ("<Down>", "down"),
+    ("k", "up"),
+    ("<Up>", "up"),
+    ("<CR>", "select"),
+    ("/", "search"),
+    ("o", "library-sort"),
+    ("f", "library-search"),
+    ("<Esc>", "cancel"),
+    ("?", "help"),
+];
+
+#[derive(Debug, Error)]
+pub enum KeymapError {
+    #[error("unknown keymap mode `{0}` (expected reader, toc, bookmarks, spritz or library)")]
+    UnknownMode(String),
+    #[error("invalid key `{0}`")]
+    InvalidKey(String),
+    #[error("unknown action `{action}` for `{keys}`")]
+    UnknownAction { keys: String, action: String },
+    #[error("`{action}` is not available in {mode} mode")]
+    Unavailable { mode: String, action: String },
+    #[error("`{first}` and `{second}` conflict in {mode} mode")]
+    Conflict {
+        mode: String,
+        first: String,
+        second: String,
+    },
+}
+
+/// One key press with its modifiers, normalized so a binding and the
+/// event that triggers it compare equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            // Shift is already in the character, and Ctrl-O is Ctrl-o
+            KeyCode::Char(c) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        if code == KeyCode::BackTab {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::CONTROL) => {
+                c.to_ascii_uppercase().to_string()
+            }
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".into(),
+            KeyCode::Esc => "Esc".into(),
+            KeyCode::Tab => "Tab".into(),
+            KeyCode::BackTab => "Shift-Tab".into(),
+            KeyCode::Backspace => "Backspace".into(),
+            KeyCode::PageUp => "PageUp".into(),
+            KeyCode::PageDown => "PageDown".into(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => format!("{:?}", code),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift-");
+        }
+        label + &key
+    }
+
+    fn is_plain_char(&self) -> bool {
+        self.modifiers.is_empty() && matches!(self.code, KeyCode::Char(c) if c != ' ')
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+/// Parse a key sequence in vim notation: plain characters stand for
+/// themselves and `<...>` names a special or modified key, as in `gg`,
+/// `<C-o>`, `<PageDown>` or `<lt>` for `<`.
+pub fn parse_keys(text: &str) -> Result<Vec<KeyChord>, KeymapError> {
+    let invalid = || KeymapError::InvalidKey(text.to_string());
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let special = rest.strip_prefix('<').and_then(|inner| {
+            inner
+                .find('>')
+                .filter(|end| *end > 0)
+                .map(|end| &inner[..end])
+        });
+        match special {
+            Some(name) => {
+                keys.push(parse_special(name).ok_or_else(invalid)?);
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                keys.push(KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if keys.is_empty() {
+        return Err(invalid());
+    }
+    Ok(keys)
+}
+
+fn parse_special(name: &str) -> Option<KeyChord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = name;
+    // `<C-->` is Ctrl and a minus sign
+    while let Some((prefix, rest)) = key.split_once('-').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match prefix.to_ascii_lowercase().as_str() {
+            "c" | "ctrl" => KeyModifiers::CONTROL,
+            "a" | "m" | "alt" => KeyModifiers::ALT,
+            "s" | "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        key = rest;
+    }
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key.to_ascii_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "cr" | "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "bs" | "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "lt" => KeyCode::Char('<'),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "del" | "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            other => KeyCode::F(other.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some(KeyChord::new(code, modifiers))
+}
+
+fn keys_label(keys: &[KeyChord]) -> String {
+    if keys.iter().all(KeyChord::is_plain_char) {
+        keys.iter().map(KeyChord::label).collect()
+    } else {
+        keys.iter()
+            .map(KeyChord::label)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn default_keys(mode: Mode) -> &'static [(&'static str, &'static str)] {
+    match mode {
+        Mode::Reader => READER_KEYS,
+        Mode::Toc => TOC_KEYS,
+        Mode::Bookmarks => BOOKMARK_KEYS,
+        Mode::Spritz => SPRITZ_KEYS,
+        Mode::Library => LIBRARY_KEYS,
+    }
+}
+
+fn action(name: &str) -> Option<Command> {
+    ACTIONS
+        .iter()
+        .find(|(action, _)| *action == name)
+        .map(|(_, command)| *command)
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+    mode: Mode,
+    keys: Vec<KeyChord>,
+    command: Command,
+}
+
+impl Binding {
+    // A sequence that starts another could never run the longer one
+    fn overlaps(&self, other: &Binding) -> bool {
+        self.mode == other.mode
+            && (self.keys.starts_with(&other.keys) || other.keys.starts_with(&self.keys))
+    }
+}
+
+/// What a key sequence does in a mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Lookup {
+    Command(Command),
+    Pending, // the start of a longer sequence
+    Unbound,
+}
+
+/// Key bindings per mode: the defaults, with the `[keymap.<mode>]` tables
+/// of config.toml applied on top.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = MODES
+            .iter()
+            .flat_map(|(_, mode)| {
+                default_keys(*mode).iter().map(|(keys, name)| Binding {
+                    mode: *mode,
+                    keys: parse_keys(keys).expect("default key"),
+                    command: action(name).expect("default action"),
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The defaults with `(mode, keys, action)` overrides applied. An
+    /// override replaces the defaults it overlaps with, and the action
+    /// `none` only removes them; overrides that overlap each other are
+    /// an error.
+    pub fn with_overrides<'a>(
+        overrides: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+    ) -> Result<Self, KeymapError> {
+        let mut keymap = Self::default();
+        let mut added: Vec<(Binding, &str)> = Vec::new();
+        let mut removed: Vec<Binding> = Vec::new();
+        for (mode_key, keys_text, name) in overrides {
+            let mode = MODES
+                .iter()
+                .find(|(name, _)| *name == mode_key)
+                .map(|(_, mode)| *mode)
+                .ok_or_else(|| KeymapError::UnknownMode(mode_key.to_string()))?;
+            let keys = parse_keys(keys_text)?;
+            if name == "none" {
+                removed.push(Binding {
+                    mode,
+                    keys,
+                    command: Command::Exit,
+                });
+                continue;
+            }
+            let command = action(name).ok_or_else(|| KeymapError::UnknownAction {
+                keys: keys_text.to_string(),
+                action: name.to_string(),
+            })?;
+            let available = default_keys(mode)
+                .iter()
+                .any(|(_, default)| action(default) == Some(command));
+            if !available {
+                return Err(KeymapError::Unavailable {
+                    mode: mode_key.to_string(),
+                    action: name.to_string(),
+                });
+            }
+            let binding = Binding {
+                mode,
+                keys,
+                command,
+            };
+            if let Some((_, first)) = added.iter().find(|(other, _)| other.overlaps(&binding)) {
+                return Err(KeymapError::Conflict {
+                    mode: mode_key.to_string(),
+                    first: first.to_string(),
+                    second: keys_text.to_string(),
+                });
+            }
+            added.push((binding, keys_text));
+        }
+        keymap.bindings.retain(|binding| {
+            !removed
+                .iter()
+                .any(|gone| gone.mode == binding.mode && gone.keys == binding.keys)
+                && !added.iter().any(|(new, _)| new.overlaps(binding))
+        });
+        keymap
+            .bindings
+            .extend(added.into_iter().map(|(binding, _)| binding));
+        Ok(keymap)
+    }
+
+    pub(super) fn lookup(&self, mode: Mode, keys: &[KeyChord]) -> Lookup {
+        let mut pending = false;
+        for binding in self.bindings.iter().filter(|b| b.mode == mode) {
+            if binding.keys == keys {
+                return Lookup::Command(binding.command);
+            }
+            pending |= binding.keys.starts_with(keys);
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Add `key` to the keys typed so far and return the command they
+    /// complete. A key that ends no sequence starts over on its own.
+    pub(super) fn resolve(
+        &self,
+        mode: Mode,
+        pending: &mut Vec<KeyChord>,
+        key: KeyChord,
+    ) -> Option<Command> {
+        pending.push(key);
+        let mut lookup = self.lookup(mode, pending);
+        if lookup == Lookup::Unbound && pending.len() > 1 {
+            pending.clear();
+            pending.push(key);
+            lookup = self.lookup(mode, pending);
+        }
+        match lookup {
+            Lookup::Command(command) => {
+                pending.clear();
+                Some(command)
+            }
+            Lookup::Pending => None,
+            Lookup::Unbound => {
+                pending.clear();
+                None
+            }
+        }
+    }
+
+    /// Help lines for `mode`, one per command, listing every key bound to
+    /// it in the order they were bound.
+    pub fn help_lines(&self, mode: Mode) -> Vec<String> {
+        let mut lines: Vec<(Command, Vec<String>)> = Vec::new();
+        for binding in self.bindings.iter().filter(|b| b.mode == mode) {
+            let label = keys_label(&binding.keys);
+            match lines
+                .iter_mut()
+                .find(|(command, _)| *command == binding.command)
+            {
+                Some((_, keys)) => keys.push(label),
+                None => lines.push((binding.command, vec![label])),
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(command, keys)| format!("{}: {}", keys.join(" / "), describe(mode, command)))
+            .collect()
+    }
+}
+
+fn describe(mode: Mode, command: Command) -> &'static str {
+    match (mode, command) {
+        (_, Command::Exit) => "quit",
+        (Mode::Reader, Command::Cancel) => "clear the selection",
+        (Mode::Spritz, Command::Cancel | Command::ToggleSpritz) => "leave spritz mode",
+        (Mode::Library, Command::Cancel) => {
+            "close results, clear the filter, or return to the open book"
+        }
+        (_, Command::Cancel) => "close",
+        (Mode::Spritz, Command::Submit) => "resume playing if paused",
+        (Mode::Library, Command::Submit) => "open the selected book or result",
+        (_, Command::Submit) => "jump to the selected entry",
+        (_, Command::ToggleHelp) => "toggle this help",
+        (Mode::Reader, Command::NavigateDown(_)) => "scroll down a line",
+        (Mode::Reader, Command::NavigateUp(_)) => "scroll up a line",
+        (Mode::Spritz, Command::NavigateDown(_)) => "next word",
+        (Mode::Spritz, Command::NavigateUp(_)) => "previous word",
+        (_, Command::NavigateDown(_)) => "next entry",
+        (_, Command::NavigateUp(_)) => "previous entry",
+        (_, Command::PageDown(_)) => "scroll down half a page",
+        (_, Command::PageUp(_)) => "scroll up half a page",
+        (_, Command::AdjustWidth(delta)) if delta < 0 => "narrow the column",
+        (_, Command::AdjustWidth(_)) => "widen the column",
+        (Mode::Library, Command::StartSearch) => {
+            "filter by title, author or series; Enter to keep, Esc to clear"
+        }
+        (Mode::Toc, Command::StartSearch) => {
+            "filter entries by typing; Up/Down to pick, Enter to jump, Esc to clear"
+        }
+        (_, Command::StartSearch) => "search; Enter lists every match, j/k and Enter to jump",
+        (_, Command::SearchNext(_)) => "next match",
+        (_, Command::SearchPrevious(_)) => "previous match",
+        (_, Command::JumpBack(_)) => {
+            "back to where the last TOC, link, search, bookmark or go-to jump started"
+        }
+        (_, Command::JumpForward(_)) => "forward again through jumps",
+        (_, Command::StartLinkHints) => {
+            "label the links on screen; type a label to follow it, Esc to cancel"
+        }
+        (_, Command::GoTo(GoTo::Start)) => {
+            "go to the start of the book, or with a count to that page"
+        }
+        (_, Command::GoTo(GoTo::End)) => "go to the end of the book, or with a count to that page",
+        (_, Command::NextChapter(_)) => "next chapter",
+        (_, Command::PreviousChapter(_)) => "start of this chapter, then the previous one",
+        (_, Command::NextHeading(_)) => "next heading",
+        (_, Command::PreviousHeading(_)) => "previous heading",
+        (_, Command::StartCommandLine) => {
+            "command line: :42, :50%, :ch 7, :set, :theme, :bookmark, :export notes, :open"
+        }
+        (Mode::Toc, Command::ToggleToc) => "close the table of contents",
+        (_, Command::Fold(Fold::Open)) => "expand the entry, or step into it",
+        (_, Command::Fold(Fold::Close)) => "collapse the entry, or step out to its parent",
+        (_, Command::Fold(Fold::Toggle)) => "expand or collapse the entry",
+        (_, Command::Fold(Fold::Level(_))) => {
+            "show only the top level, or with a count that many levels"
+        }
+        (_, Command::Fold(Fold::All)) => "expand every entry",
+        (_, Command::ToggleToc) => "table of contents; Enter to jump, Esc to close",
+        (Mode::Bookmarks, Command::ToggleBookmarks) => "close the bookmark list",
+        (_, Command::ToggleBookmarks) => "list bookmarks; Enter to jump, d to delete",
+        (_, Command::AddBookmark) => "bookmark this page",
+        (Mode::Bookmarks, Command::RemoveBookmark) => "delete the selected bookmark",
+        (_, Command::RemoveBookmark) => "remove the bookmark on this page",
+        (_, Command::Highlight) => "highlight the selection (again to change color, then remove)",
+        (_, Command::StartNote) => "add or edit a note on the selection",
+        (_, Command::OpenLibrary) => "open the library to switch books",
+        (_, Command::CycleLibrarySort) => "sort by recent, title, author or series order",
+        (_, Command::StartLibrarySearch) => "search the text of every book; Enter opens a result",
+        (_, Command::ToggleSpritz) => "spritz speed reading mode",
+        (_, Command::ToggleJustify) => "toggle justification (persists)",
+        (_, Command::ToggleHyphenate) => "toggle hyphenation (persists)",
+        (_, Command::ToggleLineBreaking) => "toggle optimal line breaking (persists)",
+        (_, Command::ToggleTwoPane) => "toggle two-page spread (persists)",
+        (_, Command::SpritzTogglePlay) => "play / pause",
+        (_, Command::SpritzJumpToChapterStart) => "rewind to chapter start",
+        (_, Command::SpritzJumpToChapterEnd) => "fast forward to chapter end",
+        (_, Command::SpritzAdjustWpm(10)) => "speed up by 10 WPM",
+        (_, Command::SpritzAdjustWpm(-10)) => "slow down by 10 WPM",
+        (_, Command::SpritzAdjustWpm(delta)) if delta > 0 => "speed up by 50 WPM",
+        (_, Command::SpritzAdjustWpm(_)) => "slow down by 50 WPM",
+        (_, Command::SpritzAdvance(_)) => "skip 10 words",
+        (_, Command::SpritzRewind(_)) => "go back 10 words",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::from(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn parses_vim_key_notation() {
+        let keys = parse_keys("g<C-O><lt><S-Tab><PageDown><C-->").unwrap();
+        assert_eq!(
+            keys,
+            [
+                chord(KeyCode::Char('g'), KeyModifiers::NONE),
+                chord(KeyCode::Char('o'), KeyModifiers::CONTROL),
+                chord(KeyCode::Char('<'), KeyModifiers::NONE),
+                chord(KeyCode::BackTab, KeyModifiers::SHIFT),
+                chord(KeyCode::PageDown, KeyModifiers::NONE),
+                chord(KeyCode::Char('-'), KeyModifiers::CONTROL),
+            ]
+        );
+        // Shift comes with the character in key events
+        assert_eq!(
+            parse_keys("N").unwrap(),
+            [chord(KeyCode::Char('N'), KeyModifiers::SHIFT)]
+        );
+        assert_eq!(parse_keys("<").unwrap().len(), 1);
+        assert!(parse_keys("<Nope>").is_err());
+        assert!(parse_keys("").is_err());
+        assert_eq!(keys_label(&parse_keys("gg").unwrap()), "gg");
+        assert_eq!(keys_label(&parse_keys("<C-c>").unwrap()), "Ctrl-C");
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let keymap = Keymap::default();
+        for (idx, binding) in keymap.bindings.iter().enumerate() {
+            for other in &keymap.bindings[idx + 1..] {
+                assert!(
+                    !binding.overlaps(other),
+                    "{:?} overlaps {:?}",
+                    binding.keys,
+                    other.keys
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_resolve_sequences() {
+        let keymap = Keymap::with_overrides([
+            ("reader", "h", "page-up"),
+            ("reader", "gh", "narrow"),
+            ("reader", "<C-i>", "none"),
+        ])
+        .unwrap();
+        let key = |c| chord(KeyCode::Char(c), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.lookup(Mode::Reader, &[key('h')]),
+            Lookup::Command(Command::PageUp(1))
+        );
+        assert_eq!(
+            keymap.lookup(
+                Mode::Reader,
+                &[chord(KeyCode::Char('i'), KeyModifiers::CONTROL)]
+            ),
+            Lookup::Unbound
+        );
+        assert_eq!(
+            Keymap::default().lookup(Mode::Reader, &[key('l')]),
+            Lookup::Command(Command::NavigateDown(1))
+        );
+        // Other modes keep their own bindings
+        assert_eq!(
+            keymap.lookup(Mode::Spritz, &[key('h')]),
+            Lookup::Command(Command::AdjustWidth(-2))
+        );
+
+        let mut pending = Vec::new();
+        assert_eq!(keymap.resolve(Mode::Reader, &mut pending, key('g')), None);
+        assert_eq!(
+            keymap.resolve(Mode::Reader, &mut pending, key('h')),
+            Some(Command::AdjustWidth(-2))
+        );
+        // A key that breaks a sequence counts on its own
+        keymap.resolve(Mode::Reader, &mut pending, key('g'));
+        assert_eq!(
+            keymap.resolve(Mode::Reader, &mut pending, key('j')),
+            Some(Command::NavigateDown(1))
+        );
+        assert!(pending.is_empty());
+
+        let help = keymap.help_lines(Mode::Reader);
+        assert!(help.contains(&"PageUp / h: scroll up half a page".to_string()));
+        assert!(help.contains(&"< / gh: narrow the column".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_overrides() {
+        let err = |overrides: &[(&str, &str, &str)]| {
+            Keymap::with_overrides(overrides.iter().copied())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(&[("reader", "g", "toc"), ("reader", "gg", "help")]),
+            "`g` and `gg` conflict in reader mode"
+        );
+        assert_eq!(
+            err(&[("reader", "<C-x>", "quit"), ("reader", "<C-X>", "help")]),
+            "`<C-x>` and `<C-X>` conflict in reader mode"
+        );
+        assert_eq!(
+            err(&[("reader", "x", "fly")]),
+            "unknown action `fly` for `x`"
+        );
+        assert_eq!(
+            err(&[("library", "x", "play")]),
+            "`play` is not available in library mode"
+        );
+        assert!(err(&[("editor", "x", "quit")]).starts_with("unknown keymap mode"));
+    }
+}
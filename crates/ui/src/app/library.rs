@@ -0,0 +1,131 @@
+use reader_core::index::SearchIndex;
+use reader_core::library::Catalog;
+use reader_core::state::{find_state, load_all_states};
+
+use crate::library_view::{LibraryItem, LibrarySort, LibraryView};
+use crate::reader_view::ReaderView;
+
+use super::types::{CommandOutcome, Mode, OpenRequest, SearchCommand};
+use super::App;
+
+const SEARCH_LIMIT: usize = 200;
+
+impl App {
+    /// An app with no book loaded that starts in the library.
+    pub fn new_library() -> Self {
+        let mut app = Self::new();
+        app.open_library();
+        app
+    }
+
+    /// Show the catalog with reading progress from saved state.
+    pub(super) fn open_library(&mut self) {
+        let catalog = Catalog::load();
+        let states = load_all_states();
+        let items = catalog
+            .books()
+            .iter()
+            .map(|book| {
+                let record = find_state(&states, &book.book_id());
+                LibraryItem::new(book.clone(), record)
+            })
+            .collect();
+        let mut library = LibraryView::new(items, LibrarySort::default());
+        if let Some(current) = &self.book {
+            if let Some(idx) = library.visible.iter().position(|idx| {
+                let book = &library.items[*idx].book;
+                book.id() == current.id() || book.path() == current.path()
+            }) {
+                library.selected = idx;
+            }
+        }
+        self.library = Some(library);
+        self.mode = Mode::Library;
+    }
+
+    /// Leave the library; only possible when a book is open behind it.
+    pub(super) fn close_library(&mut self) {
+        if self.book.is_some() {
+            self.library = None;
+            self.mode = Mode::Reader;
+        }
+    }
+
+    /// Open the selected book, or the book of the selected search hit at
+    /// its position. The current book is simply shown again; any other one
+    /// ends the run so the caller can load it.
+    pub(super) fn submit_library(&mut self, view: &mut ReaderView) -> CommandOutcome {
+        let Some(library) = &self.library else {
+            return CommandOutcome::Continue;
+        };
+        let request = if library.hits.is_some() {
+            library.current_hit().map(|hit| OpenRequest {
+                path: hit.book_path.clone(),
+                location: Some(hit.location()),
+            })
+        } else {
+            library.current_item().map(|item| OpenRequest {
+                path: item.book.path().to_string(),
+                location: None,
+            })
+        };
+        let Some(request) = request else {
+            return CommandOutcome::Continue;
+        };
+        if self
+            .book
+            .as_ref()
+            .is_some_and(|book| book.path() == request.path)
+        {
+            self.close_library();
+            if let Some(location) = request.location {
+                let from = self.location_for_view(view);
+                self.push_jump(view, from);
+                self.restore_location(view, location);
+            }
+            return CommandOutcome::Continue;
+        }
+        self.open_request = Some(request);
+        CommandOutcome::Exit
+    }
+
+    // The filter is edited with the same keys as the search prompt
+    pub(super) fn apply_library_filter(&mut self, command: SearchCommand) {
+        let Some(library) = &mut self.library else {
+            return;
+        };
+        match command {
+            SearchCommand::Cancel => library.clear_filter(),
+            SearchCommand::Submit => library.filtering = false,
+            SearchCommand::Backspace => library.backspace(),
+            SearchCommand::Insert(c) => library.push_char(c),
+        }
+    }
+
+    /// Edit the full-text query; submitting searches the library index.
+    pub(super) fn apply_library_query(&mut self, command: SearchCommand) {
+        let Some(library) = &mut self.library else {
+            return;
+        };
+        let Some(query) = &mut library.query else {
+            return;
+        };
+        match command {
+            SearchCommand::Cancel => library.query = None,
+            SearchCommand::Backspace => {
+                query.pop();
+            }
+            SearchCommand::Insert(c) => query.push(c),
+            SearchCommand::Submit => {
+                let query = library.query.take().unwrap_or_default();
+                if query.trim().is_empty() {
+                    return;
+                }
+                let hits = SearchIndex::open()
+                    .map(|mut index| index.search(&query, SEARCH_LIMIT))
+                    .unwrap_or_default();
+                library.show_hits(hits);
+            }
+        }
+    }
+}
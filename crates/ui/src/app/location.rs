@@ -0,0 +1,136 @@
+use reader_core::layout::{chapter_block_starts, page_for_position, TextPosition};
+use reader_core::types::Location;
+
+use crate::reader_view::ReaderView;
+
+use super::types::{ChapterPrefetchRequest, PrefetchRequest};
+use super::App;
+
+impl App {
+    /// Location of the page on screen, anchored to its first line of content.
+    pub(super) fn location_for_view(&self, view: &ReaderView) -> Location {
+        self.location_for_page(view, view.current)
+    }
+
+    /// Location to persist on exit; an unresolved restore is kept as-is.
+    pub(super) fn exit_location(&self, view: &ReaderView) -> Location {
+        self.pending_location
+            .clone()
+            .unwrap_or_else(|| self.location_for_view(view))
+    }
+
+    pub(super) fn location_for_page(&self, view: &ReaderView, page: usize) -> Location {
+        match view.pages.get(page).and_then(|p| p.first_position()) {
+            Some(pos) => self.location_for_position(pos, page),
+            None => Location::new(0, page),
+        }
+    }
+
+    pub(super) fn location_for_position(&self, pos: TextPosition, page: usize) -> Location {
+        let starts = chapter_block_starts(&self.blocks);
+        let chapter = starts
+            .iter()
+            .rposition(|start| *start <= pos.block)
+            .unwrap_or(0);
+        let block_index = pos.block.saturating_sub(starts[chapter]);
+        Location::anchored(
+            chapter,
+            self.chapter_hrefs.get(chapter).cloned(),
+            block_index,
+            pos.offset,
+            page,
+        )
+    }
+
+    /// Position of `location` in the loaded blocks, or None while its chapter is not loaded.
+    pub(super) fn position_for_location(&self, location: &Location) -> Option<TextPosition> {
+        let block_index = location.block_index()?;
+        let chapter = self.loaded_chapter_for_location(location)?;
+        let start = *chapter_block_starts(&self.blocks).get(chapter)?;
+        Some(TextPosition {
+            block: start + block_index,
+            offset: location.char_offset(),
+        })
+    }
+
+    /// Page showing `location`, or None while its chapter is not loaded yet.
+    pub(super) fn page_for_location(
+        &self,
+        view: &ReaderView,
+        location: &Location,
+    ) -> Option<usize> {
+        page_for_position(&view.pages, self.position_for_location(location)?)
+    }
+
+    /// Move to a saved location, streaming in its chapter first when needed.
+    pub(super) fn restore_location(&mut self, view: &mut ReaderView, location: Location) {
+        if !location.is_anchored() {
+            // Older records only know the page index, which initial_page already applied
+            return;
+        }
+        if let Some(page) = self.page_for_location(view, &location) {
+            self.show_page(view, page);
+            return;
+        }
+        if let Some(href) = location.chapter_href() {
+            if let Some(page_no) = href.strip_prefix("page:") {
+                if let (Some(tx), Ok(page_no)) = (&self.prefetch_tx, page_no.parse::<usize>()) {
+                    let _ = tx.send(PrefetchRequest {
+                        start: page_no.saturating_sub(1),
+                        window: self.prefetch_window,
+                    });
+                }
+            } else if let Some(tx) = &self.prefetch_chapter_tx {
+                let target_loaded = self
+                    .chapter_index_for_href(href)
+                    .unwrap_or(location.spine_index())
+                    .saturating_add(1);
+                let _ = tx.send(ChapterPrefetchRequest {
+                    target_loaded,
+                    target_href: Some(href.to_string()),
+                });
+            }
+        }
+        self.pending_location = Some(location);
+    }
+
+    pub(super) fn resolve_pending_location(&mut self, view: &mut ReaderView) {
+        let Some(location) = self.pending_location.clone() else {
+            return;
+        };
+        if let Some(page) = self.page_for_location(view, &location) {
+            self.show_page(view, page);
+            self.pending_location = None;
+        } else if self.all_chapters_loaded() {
+            self.pending_location = None;
+        }
+    }
+
+    pub(super) fn loaded_chapter_for_location(&self, location: &Location) -> Option<usize> {
+        match location.chapter_href() {
+            Some(href) => self.chapter_hrefs.iter().position(|h| h == href),
+            None if location.spine_index() < self.chapter_hrefs.len().max(1) => {
+                Some(location.spine_index())
+            }
+            None => None,
+        }
+    }
+
+    pub(super) fn all_chapters_loaded(&self) -> bool {
+        let loaded = self.chapter_titles.len();
+        if self.incoming_pages.is_some() {
+            return self.total_pages.is_some_and(|total| loaded >= total);
+        }
+        if self.incoming_chapters.is_some() {
+            return self.total_chapters.is_some_and(|total| loaded >= total);
+        }
+        true
+    }
+
+    pub(super) fn show_page(&self, view: &mut ReaderView, page: usize) {
+        view.current = page.min(view.pages.len().saturating_sub(1));
+        if view.two_pane {
+            view.current = view.current.saturating_sub(view.current % 2);
+        }
+    }
+}
@@ -32,6 +32,8 @@ impl App {
             view.total_pages = self.total_pages;
             view.total_chapters = self.total_chapters;
             view.selection = None;
+            self.refresh_highlights(view);
+            self.refresh_search_hits(view);
         }
     }
 
@@ -58,6 +60,8 @@ impl App {
             view.total_pages = self.total_pages;
             view.total_chapters = self.total_chapters;
             view.selection = None;
+            self.refresh_highlights(view);
+            self.refresh_search_hits(view);
         }
         if let Some(target) = self.pending_chapter_jump.clone() {
             if view.jump_to_target(&target) {
@@ -10,16 +10,27 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
+use reader_core::layout::Hyphenator;
+
+use crate::command_line::render_status;
 use crate::layout::centered_rect;
 use crate::reader_view::ReaderView;
 
+use super::ex::load_history;
 use super::selection::handle_mouse_selection;
 use super::settings::load_settings;
-use super::types::{Command, CommandOutcome, Mode};
+use super::types::{Command, CommandOutcome, Mode, RunExit};
 use super::App;
 
+// Mouse and prompt keys that are not in the keymap
+const READER_HELP_EXTRAS: &[&str] = &[
+    "Mouse drag: select and copy text; click a note to read it",
+    "A count before a motion applies it that many times, as in 5j, 3n or 10 PageDown",
+    "In the search field: Ctrl-R regex, Ctrl-W whole word, Ctrl-A case, Ctrl-D accents",
+];
+
 impl App {
-    pub fn run(mut self) -> std::io::Result<usize> {
+    pub fn run(mut self) -> std::io::Result<RunExit> {
         let mut stdout = stdout();
         let raw_ok = enable_raw_mode().is_ok();
         if raw_ok {
@@ -31,9 +42,20 @@ impl App {
         let mut terminal = Terminal::new(backend)?;
 
         let mut view = ReaderView::new();
-        let (saved_justify, saved_two_pane, _spritz_settings) = load_settings();
-        view.justify = saved_justify;
-        view.two_pane = saved_two_pane;
+        let settings = load_settings();
+        view.justify = settings.justify;
+        view.two_pane = settings.two_pane;
+        view.fit_screen = settings.fit_screen;
+        view.right_to_left = self.right_to_left;
+        view.hyphenate = settings.hyphenate;
+        view.line_breaking = settings.line_breaking;
+        self.spritz_settings = settings.spritz;
+        if let Some(theme) = settings.theme {
+            self.theme = theme;
+            self.theme_changed = true;
+        }
+        self.ex_history = load_history();
+        view.hyphenator = Hyphenator::for_language(self.language.as_deref());
         view.book_title = self.book_title.clone();
         view.author = self.author.clone();
         view.theme = self.theme.clone();
@@ -41,16 +63,18 @@ impl App {
         let mut selection_anchor: Option<crate::reader_view::SelectionPoint> = None;
         let mut selection_active = false;
         let mut last_frame = Rect::default();
-        let mut width: u16 = 60;
+        let mut width = settings.width;
         let mut height: u16 = 20;
         // Use inner size for initial paginate to compute chapter_starts correctly
         let term_size = terminal
             .size()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
         let inner = ReaderView::inner_size(term_size.into(), width, view.two_pane);
-        let p = reader_core::layout::paginate_with_justify(&self.blocks, inner, view.justify);
+        let p =
+            reader_core::layout::paginate_with_options(&self.blocks, inner, view.layout_options());
         view.pages = p.pages;
         view.chapter_starts = p.chapter_starts;
+        view.heading_starts = p.heading_starts;
         view.anchors = p.anchors;
         view.chapter_titles = self.chapter_titles.clone();
         view.chapter_hrefs = self.chapter_hrefs.clone();
@@ -60,6 +84,10 @@ impl App {
         if let Some(idx) = self.initial_page {
             view.current = idx.min(view.pages.len().saturating_sub(1));
         }
+        self.refresh_highlights(&mut view);
+        if let Some(location) = self.initial_location.take() {
+            self.restore_location(&mut view, location);
+        }
         let mut last_inner: (u16, u16) = (inner.width, inner.height);
         // ensure initial last_size is used by next draw comparison
 
@@ -72,10 +100,13 @@ impl App {
                 let inner = ReaderView::inner_size(size, width, view.two_pane);
                 self.poll_incoming(&mut view, inner);
                 self.poll_incoming_chapters(&mut view, inner);
+                self.resolve_pending_location(&mut view);
+                self.resolve_pending_goto(&mut view);
+                self.resolve_pending_search(&mut view);
                 self.maybe_request_prefetch(&view);
                 self.maybe_request_chapter_prefetch(&view);
                 view.reflow(&self.blocks, inner);
-                view.render(f, size, width, self.last_search.as_deref());
+                view.render(f, size, width, self.last_search.as_ref());
             });
             #[cfg(feature = "kitty-images")]
             {
@@ -91,7 +122,7 @@ impl App {
             } else {
                 execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
             }
-            return Ok(view.current);
+            return Ok(self.run_exit(&view));
         }
 
         let mut exit = false;
@@ -105,6 +136,9 @@ impl App {
                 let inner = ReaderView::inner_size(size, width, view.two_pane);
                 self.poll_incoming(&mut view, inner);
                 self.poll_incoming_chapters(&mut view, inner);
+                self.resolve_pending_location(&mut view);
+                self.resolve_pending_goto(&mut view);
+                self.resolve_pending_search(&mut view);
                 self.maybe_request_prefetch(&view);
                 self.maybe_request_chapter_prefetch(&view);
                 if (inner.width, inner.height) != last_inner {
@@ -113,22 +147,39 @@ impl App {
                     view.current = view.current.min(view.pages.len().saturating_sub(1));
                     last_inner = (inner.width, inner.height);
                     view.selection = None;
+                    self.link_hints = None;
                     selection_anchor = None;
                     selection_active = false;
                 }
                 match self.mode {
                     Mode::Reader => {
-                        view.render(f, size, width, self.last_search.as_deref());
+                        view.render(f, size, width, self.last_search.as_ref());
+                        if let Some(hints) = &self.link_hints {
+                            view.render_link_hints(f, size, width, hints);
+                        }
                         drew_view = true;
                     }
                     Mode::Toc => {
                         if let Some(t) = &self.toc {
                             t.render(f, size, width);
                         } else {
-                            view.render(f, size, width, self.last_search.as_deref());
+                            view.render(f, size, width, self.last_search.as_ref());
+                            drew_view = true;
+                        }
+                    }
+                    Mode::Bookmarks => {
+                        if let Some(list) = &self.bookmark_view {
+                            list.render(f, size, width);
+                        } else {
+                            view.render(f, size, width, self.last_search.as_ref());
                             drew_view = true;
                         }
                     }
+                    Mode::Library => {
+                        if let Some(library) = &self.library {
+                            library.render(f, size);
+                        }
+                    }
                     Mode::Spritz => {
                         if let Some(spritz) = self.spritz.as_mut() {
                             spritz.update();
@@ -139,39 +190,24 @@ impl App {
                 if let Some(search) = &self.search {
                     search.render(f, size);
                 }
+                if let Some(note) = &self.note_input {
+                    note.render(f, size);
+                }
+                if let Some(line) = &self.command_line {
+                    line.render(f, size);
+                } else if let Some(status) = &self.status {
+                    render_status(f, size, status);
+                }
                 if self.show_help {
                     let popup_area = centered_rect(70, 70, size);
-                    let help_lines = match self.mode {
-                        Mode::Spritz => vec![
-                            "q / Ctrl-C / s / Esc: exit spritz mode",
-                            "Space: toggle play/pause",
-                            "j/k or arrows: navigate word-by-word",
-                            "h / l or arrows: adjust column width",
-                            "Ctrl+j/Ctrl+k: jump 10 words",
-                            "+/- or =/_: adjust WPM by 10",
-                            "[ / ]: adjust WPM by 50",
-                            "r: rewind to chapter start",
-                            "f: fast forward to chapter end",
-                            "Enter: resume playing if paused",
-                            "?: toggle this help",
-                        ],
-                        _ => vec![
-                            "q / Ctrl-C: quit",
-                            "s: toggle spritz speed reading mode",
-                            "j / k or arrows: scroll lines",
-                            "PageUp / PageDown: jump half-page",
-                            "h / l or arrows: adjust column width",
-                            "t: toggle table of contents; Enter to jump; Esc to close TOC",
-                            "/: search; Enter to submit; Esc to cancel",
-                            "J: toggle justification (persists)",
-                            "b: toggle two-page spread (persists)",
-                            "?: toggle this help",
-                        ],
-                    };
+                    let mut help_lines = self.keymap.help_lines(self.mode);
+                    if let Mode::Reader = self.mode {
+                        help_lines.extend(READER_HELP_EXTRAS.iter().map(|line| line.to_string()));
+                    }
                     let help = Paragraph::new(help_lines.join("\n"))
                         .block(
                             Block::default()
-                                .title("Help (Esc or ? to close)")
+                                .title("Help (Esc to close)")
                                 .borders(Borders::ALL),
                         )
                         .wrap(Wrap { trim: false });
@@ -193,7 +229,12 @@ impl App {
                 Ok(true) => match event::read() {
                     Ok(Event::Mouse(mouse)) => {
                         if let Mode::Reader = self.mode {
-                            if self.search.is_some() || self.show_help || self.footnote.is_some() {
+                            if self.search.is_some()
+                                || self.note_input.is_some()
+                                || self.command_line.is_some()
+                                || self.show_help
+                                || self.footnote.is_some()
+                            {
                                 continue;
                             }
                             handle_mouse_selection(
@@ -208,7 +249,9 @@ impl App {
                         }
                     }
                     Ok(Event::Key(key)) => {
-                        if let Some(command) = Command::from_key(&self, key) {
+                        self.status = None;
+                        if let Some(command) = Command::from_key(&mut self, key) {
+                            let command = command.with_count(self.count.take());
                             if self.apply_command(
                                 command,
                                 &mut view,
@@ -244,6 +287,13 @@ impl App {
         } else {
             execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
         }
-        Ok(view.current)
+        Ok(self.run_exit(&view))
+    }
+
+    fn run_exit(&mut self, view: &ReaderView) -> RunExit {
+        RunExit {
+            location: self.exit_location(view),
+            open: self.open_request.take(),
+        }
     }
 }
@@ -1,8 +1,17 @@
+use reader_core::index::SearchIndex;
+use reader_core::layout::page_for_position;
+use reader_core::search::Matcher;
+use reader_core::types::Location;
+
 use crate::reader_view::ReaderView;
 
-use super::types::SearchCommand;
+use super::types::{
+    ChapterPrefetchRequest, PendingSearch, PrefetchRequest, SearchCommand, SearchToggle,
+};
 use super::App;
 
+const UNLOADED_SEARCH_LIMIT: usize = 64;
+
 impl App {
     pub(super) fn apply_search_command(&mut self, view: &mut ReaderView, command: SearchCommand) {
         match command {
@@ -20,34 +29,274 @@ impl App {
                 }
             }
             SearchCommand::Submit => {
-                let (trimmed, start_from) = match &self.search {
-                    Some(search) => {
-                        let trimmed = search.query.trim().to_string();
-                        let start_from = if self.last_search.as_deref().map(str::trim)
-                            == Some(trimmed.as_str())
-                        {
-                            self.last_search_hit.map(|p| p + 1)
-                        } else {
-                            None
-                        };
-                        (trimmed, start_from)
+                let Some(search) = &mut self.search else {
+                    return;
+                };
+                if search.results.is_some() {
+                    let page = search.current_match().map(|hit| hit.page);
+                    view.search_hit = page.map(|_| search.selected);
+                    self.search = None;
+                    if let Some(page) = page {
+                        let from = self.location_for_view(view);
+                        self.push_jump(view, from);
+                        show_hit(view, page);
+                    }
+                    return;
+                }
+                let matcher = match Matcher::new(&search.query, search.options) {
+                    Ok(matcher) => matcher,
+                    Err(e) => {
+                        search.error = Some(e.to_string());
+                        return;
                     }
-                    None => return,
                 };
-                self.last_search = Some(trimmed.clone());
-                if let Some(idx) = view.search_forward(&trimmed, start_from) {
-                    let target = if view.two_pane {
-                        idx.saturating_sub(idx % 2)
-                    } else {
-                        idx
-                    };
-                    view.current = target;
-                    self.last_search_hit = Some(idx);
-                } else {
-                    self.last_search_hit = None;
+                self.search_options = search.options;
+                let results = view.find_all(&matcher);
+                view.search_hits = results.iter().map(|hit| hit.position).collect();
+                view.search_hit = None;
+                self.last_search = Some(matcher.clone());
+                if results.is_empty() {
+                    self.search = None;
+                    let from = self.location_for_view(view);
+                    self.search_unloaded(&matcher, from);
+                    return;
                 }
-                self.search = None;
+                let selected = results
+                    .iter()
+                    .position(|hit| hit.page >= view.current)
+                    .unwrap_or(0);
+                search.show_results(results, selected);
+            }
+        }
+    }
+
+    pub(super) fn toggle_search_option(&mut self, toggle: SearchToggle) {
+        let Some(search) = self.search.as_mut().filter(|s| s.results.is_none()) else {
+            return;
+        };
+        let options = &mut search.options;
+        match toggle {
+            SearchToggle::Regex => options.regex = !options.regex,
+            SearchToggle::WholeWord => options.whole_word = !options.whole_word,
+            SearchToggle::Case => options.case = options.case.next(),
+            SearchToggle::Diacritics => options.ignore_diacritics = !options.ignore_diacritics,
+        }
+        search.error = None;
+    }
+
+    /// `n` / `N`: go `count` matches forward or back from the last one,
+    /// wrapping around. Going forward past the last loaded match looks for
+    /// one in chapters still streaming in first.
+    pub(super) fn step_search(&mut self, view: &mut ReaderView, forward: bool, count: usize) {
+        let Some(matcher) = self.last_search.clone().filter(|m| !m.query().is_empty()) else {
+            return;
+        };
+        let from = self.location_for_view(view);
+        let next = view.next_search_hit(forward, count);
+        // Chapters that have not streamed in yet come before wrapping around
+        if forward
+            && next.is_none_or(|(_, wrapped)| wrapped)
+            && self.search_unloaded(&matcher, from.clone())
+        {
+            return;
+        }
+        let Some((next, _)) = next else {
+            return;
+        };
+        view.search_hit = Some(next);
+        let Some(page) = page_for_position(&view.pages, view.search_hits[next]) else {
+            return;
+        };
+        let shown = if view.two_pane { 2 } else { 1 };
+        if page - page % shown != view.current - view.current % shown {
+            self.push_jump(view, from);
+            show_hit(view, page);
+        }
+    }
+
+    /// Recount matches of the last search after more text streamed in.
+    pub(super) fn refresh_search_hits(&self, view: &mut ReaderView) {
+        if let Some(matcher) = &self.last_search {
+            view.search_hits = view
+                .find_all(matcher)
+                .iter()
+                .map(|hit| hit.position)
+                .collect();
+        }
+    }
+
+    /// Look for the next match in text that has not streamed in yet and
+    /// show it once it arrives: up to the chapter the library index points
+    /// at first, then through the rest of the book. False when everything
+    /// is loaded already.
+    fn search_unloaded(&mut self, matcher: &Matcher, from: Location) -> bool {
+        if self.all_chapters_loaded() {
+            return false;
+        }
+        let hint = self.indexed_search_hit(matcher);
+        let requested = if let Some(tx) = &self.prefetch_tx {
+            // PDF pages stream in on their own; the hint only goes first
+            if let Some(page) = hint.as_ref().and_then(|hit| {
+                hit.chapter_href()?
+                    .strip_prefix("page:")?
+                    .parse::<usize>()
+                    .ok()
+            }) {
+                let _ = tx.send(PrefetchRequest {
+                    start: page.saturating_sub(1),
+                    window: self.prefetch_window,
+                });
             }
+            self.total_pages.unwrap_or(usize::MAX)
+        } else {
+            let target_href = hint.and_then(|hit| hit.chapter_href().map(str::to_string));
+            let target_loaded = target_href
+                .as_deref()
+                .and_then(|href| self.chapter_index_for_href(href))
+                .map_or(self.total_chapters.unwrap_or(usize::MAX), |idx| idx + 1);
+            self.request_chapters(target_loaded, target_href);
+            target_loaded
+        };
+        self.pending_search = Some(PendingSearch {
+            from_block: self.blocks.len(),
+            requested,
+            from,
+        });
+        true
+    }
+
+    /// Show the first match in text that streamed in since the search ran;
+    /// when what was asked for arrived without one, ask for the rest.
+    pub(super) fn resolve_pending_search(&mut self, view: &mut ReaderView) {
+        let Some(pending) = self.pending_search.clone() else {
+            return;
+        };
+        let hit = view
+            .search_hits
+            .iter()
+            .position(|pos| pos.block >= pending.from_block);
+        if let Some((idx, page)) =
+            hit.and_then(|idx| Some((idx, page_for_position(&view.pages, view.search_hits[idx])?)))
+        {
+            view.search_hit = Some(idx);
+            self.pending_search = None;
+            self.push_jump(view, pending.from);
+            show_hit(view, page);
+        } else if self.all_chapters_loaded() {
+            self.pending_search = None;
+        } else if self.prefetch_chapter_tx.is_some()
+            && self.chapter_titles.len() >= pending.requested
+        {
+            let rest = self.total_chapters.unwrap_or(usize::MAX);
+            self.request_chapters(rest, None);
+            self.pending_search = Some(PendingSearch {
+                requested: rest,
+                ..pending
+            });
+        }
+    }
+
+    fn request_chapters(&self, target_loaded: usize, target_href: Option<String>) {
+        if let Some(tx) = &self.prefetch_chapter_tx {
+            let _ = tx.send(ChapterPrefetchRequest {
+                target_loaded,
+                target_href,
+            });
         }
     }
+
+    /// First hit for the query in a chapter that is not loaded yet, looked
+    /// up in the library index. The index only knows catalogued books and
+    /// matches whole words, so this is a hint of where to load up to; the
+    /// match itself is found in the loaded text.
+    fn indexed_search_hit(&self, matcher: &Matcher) -> Option<Location> {
+        if matcher.options().regex {
+            return None;
+        }
+        let book = self.book.as_ref()?;
+        // Chapters stream in order; PDF pages can arrive out of order
+        let from_chapter = if self.incoming_pages.is_some() {
+            (0..)
+                .find(|idx| !self.chapter_hrefs.contains(&format!("page:{}", idx + 1)))
+                .unwrap_or(0)
+        } else {
+            self.chapter_hrefs.len()
+        };
+        let mut index = SearchIndex::open().ok()?;
+        index
+            .search_book(
+                book.id(),
+                matcher.query(),
+                from_chapter,
+                UNLOADED_SEARCH_LIMIT,
+            )
+            .into_iter()
+            .find(|hit| !self.chapter_hrefs.contains(&hit.chapter_href))
+            .map(|hit| hit.location())
+    }
+}
+
+fn show_hit(view: &mut ReaderView, page: usize) {
+    view.current = if view.two_pane {
+        page.saturating_sub(page % 2)
+    } else {
+        page
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use reader_core::layout::Size;
+    use reader_core::search::{PageText, SearchOptions};
+    use reader_core::types::Block;
+
+    use super::*;
+    use crate::app::IncomingChapter;
+
+    fn chapter(index: usize, text: &str) -> IncomingChapter {
+        IncomingChapter {
+            chapter_index: index,
+            blocks: vec![Block::Paragraph(text.to_string())],
+            title: format!("Chapter {}", index + 1),
+            href: format!("ch{}.xhtml", index + 1),
+        }
+    }
+
+    #[test]
+    fn search_past_the_loaded_text_streams_in_the_rest_of_the_book() {
+        let (chapter_tx, chapter_rx) = channel();
+        let (request_tx, request_rx) = channel();
+        let mut app = App::new_with_blocks(vec![Block::Paragraph("Down the rabbit hole.".into())]);
+        app.chapter_titles = vec!["Chapter 1".into()];
+        app.chapter_hrefs = vec!["ch1.xhtml".into()];
+        app.incoming_chapters = Some(chapter_rx);
+        app.total_chapters = Some(3);
+        app.prefetch_chapter_tx = Some(request_tx);
+        let size = Size {
+            width: 30,
+            height: 4,
+        };
+        let mut view = ReaderView::new();
+        view.reflow(&app.blocks, size);
+        app.last_search = Some(Matcher::new("tea party", SearchOptions::default()).unwrap());
+
+        // A book opened by path has no index entry, so the rest is asked for
+        app.step_search(&mut view, true, 1);
+        assert_eq!(request_rx.try_recv().unwrap().target_loaded, 3);
+        chapter_tx.send(chapter(1, "The pool of tears.")).unwrap();
+        app.poll_incoming_chapters(&mut view, size);
+        app.resolve_pending_search(&mut view);
+        assert!(app.pending_search.is_some());
+        assert_eq!(view.current, 0);
+
+        chapter_tx.send(chapter(2, "A mad tea party.")).unwrap();
+        app.poll_incoming_chapters(&mut view, size);
+        app.resolve_pending_search(&mut view);
+        assert!(app.pending_search.is_none());
+        assert!(PageText::new(&view.pages[view.current])
+            .text
+            .contains("tea party"));
+    }
 }
@@ -102,37 +102,34 @@ pub(super) fn handle_mouse_selection(
                 });
             }
         }
-        MouseEventKind::Drag(MouseButton::Left) => {
-            if *selection_active {
-                if let Some(point) = App::mouse_selection_point(view, area, column_width, mouse) {
-                    if let Some(anchor) = *selection_anchor {
-                        view.selection = Some(SelectionRange {
-                            start: anchor,
-                            end: point,
-                        });
-                    }
+        MouseEventKind::Drag(MouseButton::Left) if *selection_active => {
+            if let Some(point) = App::mouse_selection_point(view, area, column_width, mouse) {
+                if let Some(anchor) = *selection_anchor {
+                    view.selection = Some(SelectionRange {
+                        start: anchor,
+                        end: point,
+                    });
                 }
             }
         }
-        MouseEventKind::Up(MouseButton::Left) => {
-            if *selection_active {
-                if let Some(selection) = view.selection {
-                    let (start, end) = selection.normalized();
-                    if start == end {
-                        if let Some(target) = view.link_at_point(start) {
-                            let label = view.link_label_at_point(start);
-                            if !app.maybe_open_footnote(view, &target, label.as_deref()) {
-                                view.jump_to_target(&target);
-                            }
-                        }
-                    } else {
-                        app.copy_selection(view, selection);
+        MouseEventKind::Up(MouseButton::Left) if *selection_active => {
+            if let Some(selection) = view.selection {
+                let (start, end) = selection.normalized();
+                if start == end {
+                    if let Some(target) = view.link_at_point(start) {
+                        let label = view.link_label_at_point(start);
+                        app.follow_link(view, &target, label.as_deref());
+                    } else if let Some(idx) = view.highlight_at_point(start) {
+                        app.maybe_open_note(idx);
                     }
+                    view.selection = None;
+                } else {
+                    // Keep the selection visible so it can be highlighted or annotated
+                    app.copy_selection(view, selection);
                 }
-                view.selection = None;
-                *selection_anchor = None;
-                *selection_active = false;
             }
+            *selection_anchor = None;
+            *selection_active = false;
         }
         _ => {}
     }
@@ -163,7 +160,7 @@ fn slice_graphemes(text: &str, start: usize, end: usize) -> String {
     graphemes[start..end].concat()
 }
 
-fn selection_text(view: &ReaderView, selection: SelectionRange) -> String {
+pub(super) fn selection_text(view: &ReaderView, selection: SelectionRange) -> String {
     let (start, end) = selection.normalized();
     let mut out: Vec<String> = Vec::new();
     for page_idx in start.page..=end.page {
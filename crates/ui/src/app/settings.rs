@@ -1,6 +1,58 @@
 use std::{fs, path::PathBuf};
 
+use reader_core::layout::LineBreaking;
+
+use crate::reader_view::{color_name, parse_color, ReaderView, Theme};
+
 use super::types::SpritzSettings;
+use super::App;
+
+pub(super) const DEFAULT_WIDTH: u16 = 60;
+
+/// Persisted reader preferences from `settings.toml`.
+#[derive(Clone)]
+pub(super) struct ReaderSettings {
+    pub width: u16,
+    pub justify: bool,
+    pub two_pane: bool,
+    pub fit_screen: bool,
+    pub hyphenate: bool,
+    pub line_breaking: LineBreaking,
+    pub spritz: SpritzSettings,
+    pub theme: Option<Theme>, // chosen with `:theme` or `:set`, over config.toml's
+}
+
+impl Default for ReaderSettings {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            justify: false,
+            two_pane: false,
+            fit_screen: true,
+            hyphenate: false,
+            line_breaking: LineBreaking::default(),
+            spritz: SpritzSettings::default(),
+            theme: None,
+        }
+    }
+}
+
+impl App {
+    /// Persist the view's layout toggles, the column width, the spritz
+    /// settings and the theme once it was changed at the command line.
+    pub(super) fn save_settings(&self, view: &ReaderView, width: u16) {
+        save_settings(&ReaderSettings {
+            width,
+            justify: view.justify,
+            two_pane: view.two_pane,
+            fit_screen: view.fit_screen,
+            hyphenate: view.hyphenate,
+            line_breaking: view.line_breaking,
+            spritz: self.spritz_settings,
+            theme: self.theme_changed.then(|| self.theme.clone()),
+        });
+    }
+}
 
 fn settings_path() -> Option<PathBuf> {
     reader_core::config::config_root().map(|dir| dir.join("settings.toml"))
@@ -13,10 +65,9 @@ fn legacy_settings_paths() -> Vec<PathBuf> {
         .collect()
 }
 
-pub(super) fn load_settings() -> (bool, bool, SpritzSettings) {
-    let mut justify = false;
-    let mut two_pane = false;
-    let mut spritz = SpritzSettings::default();
+pub(super) fn load_settings() -> ReaderSettings {
+    let mut settings = ReaderSettings::default();
+    let spritz = &mut settings.spritz;
     let mut candidates = Vec::new();
     if let Some(primary) = settings_path() {
         candidates.push(primary);
@@ -25,36 +76,71 @@ pub(super) fn load_settings() -> (bool, bool, SpritzSettings) {
     for path in candidates {
         if let Ok(contents) = fs::read_to_string(path) {
             for line in contents.lines() {
-                if let Some(val) = line.strip_prefix("justify=") {
-                    justify = val.trim().eq_ignore_ascii_case("true");
+                if let Some(val) = line.strip_prefix("width=") {
+                    settings.width = val.trim().parse().unwrap_or(settings.width);
+                } else if let Some(val) = line.strip_prefix("justify=") {
+                    settings.justify = val.trim().eq_ignore_ascii_case("true");
                 } else if let Some(val) = line.strip_prefix("two_pane=") {
-                    two_pane = val.trim().eq_ignore_ascii_case("true");
+                    settings.two_pane = val.trim().eq_ignore_ascii_case("true");
+                } else if let Some(val) = line.strip_prefix("fit_screen=") {
+                    settings.fit_screen = val.trim().eq_ignore_ascii_case("true");
+                } else if let Some(val) = line.strip_prefix("hyphenate=") {
+                    settings.hyphenate = val.trim().eq_ignore_ascii_case("true");
+                } else if let Some(val) = line.strip_prefix("line_breaking=") {
+                    settings.line_breaking =
+                        LineBreaking::from_name(val).unwrap_or(settings.line_breaking);
                 } else if let Some(val) = line.strip_prefix("spritz_wpm=") {
                     spritz.wpm = val.trim().parse().unwrap_or(spritz.wpm).clamp(100, 1000);
                 } else if let Some(val) = line.strip_prefix("spritz_pause_on_punct=") {
                     spritz.pause_on_punct = val.trim().eq_ignore_ascii_case("true");
                 } else if let Some(val) = line.strip_prefix("spritz_punct_pause_ms=") {
                     spritz.punct_pause_ms = val.trim().parse().unwrap_or(spritz.punct_pause_ms);
+                } else if let Some((field, val)) = line.split_once('=') {
+                    if let Some(color) = parse_color(val).filter(|_| Theme::FIELDS.contains(&field))
+                    {
+                        settings
+                            .theme
+                            .get_or_insert_with(Theme::default)
+                            .set_color(field.trim(), color);
+                    }
                 }
             }
             break;
         }
     }
-    (justify, two_pane, spritz)
+    settings
 }
 
-pub(super) fn save_settings(justify: bool, two_pane: bool, spritz: &SpritzSettings) {
+fn save_settings(settings: &ReaderSettings) {
     let target = settings_path().or_else(|| legacy_settings_paths().into_iter().next());
     if let Some(path) = target {
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
-        let _ = fs::write(
-            path,
-            format!(
-                "justify={justify}\ntwo_pane={two_pane}\nspritz_wpm={}\nspritz_pause_on_punct={}\nspritz_punct_pause_ms={}\n",
-                spritz.wpm, spritz.pause_on_punct, spritz.punct_pause_ms
-            ),
+        let ReaderSettings {
+            width,
+            justify,
+            two_pane,
+            fit_screen,
+            hyphenate,
+            line_breaking,
+            spritz,
+            theme,
+        } = settings;
+        let mut text = format!(
+                "width={width}\njustify={justify}\ntwo_pane={two_pane}\nfit_screen={fit_screen}\nhyphenate={hyphenate}\nline_breaking={}\nspritz_wpm={}\nspritz_pause_on_punct={}\nspritz_punct_pause_ms={}\n",
+                line_breaking.name(),
+                spritz.wpm,
+                spritz.pause_on_punct,
+                spritz.punct_pause_ms
         );
+        if let Some(theme) = theme {
+            for field in Theme::FIELDS {
+                if let Some(color) = theme.color(field) {
+                    text.push_str(&format!("{}={}\n", field, color_name(color)));
+                }
+            }
+        }
+        let _ = fs::write(path, text);
     }
 }
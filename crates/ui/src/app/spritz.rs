@@ -2,16 +2,16 @@ use chrono::Utc;
 
 use crate::spritz_view::SpritzView;
 
-use super::types::{Mode, SpritzSettings};
+use super::types::Mode;
 use super::App;
 
 impl App {
     pub(super) fn save_spritz_session(&self, spritz: &SpritzView) {
-        let Some(book_id) = &self.book_id else {
+        let Some(book) = &self.book else {
             return;
         };
         let session = reader_core::SpritzSession::new(
-            book_id.clone(),
+            book.id().to_string(),
             spritz.current_index,
             spritz.wpm,
             Utc::now().to_rfc3339(),
@@ -21,16 +21,15 @@ impl App {
 
     pub(super) fn start_spritz(&mut self) {
         let words = reader_core::layout::extract_words(&self.blocks);
-        let settings = SpritzSettings::default();
         let mut spritz = SpritzView::new(
             words,
-            settings,
+            self.spritz_settings,
             self.chapter_titles.clone(),
             self.theme.clone(),
         );
 
-        if let Some(book_id) = &self.book_id {
-            if let Some(session) = reader_core::load_spritz_session(book_id) {
+        if let Some(book) = &self.book {
+            if let Some(session) = reader_core::load_spritz_session(book.id()) {
                 spritz.current_index = session.word_index();
                 spritz.wpm = session.wpm();
             }
@@ -5,25 +5,41 @@ use std::{
 
 use arboard::Clipboard;
 use reader_core::{
+    annotations::Annotation,
+    jumps::JumpList,
     pdf::OutlineEntry,
-    types::{Block as ReaderBlock, Document, TocEntry},
+    search::{Matcher, SearchOptions},
+    types::{Block as ReaderBlock, BookId, Document, Location, TocEntry},
 };
 
 use crate::{
-    reader_view::Theme,
+    command_line::CommandLineView,
+    library_view::LibraryView,
+    reader_view::{LinkHints, Theme},
     search_view::SearchView,
     spritz_view::SpritzView,
-    views::{FootnoteView, TocView},
+    toc_view::TocView,
+    views::{BookmarkView, FootnoteView, NoteView},
 };
 
-use super::types::{ChapterPrefetchRequest, IncomingChapter, IncomingPage, Mode, PrefetchRequest};
+use super::keymap::{KeyChord, Keymap};
+use super::types::{
+    ChapterPrefetchRequest, GoTo, IncomingChapter, IncomingPage, Mode, OpenRequest, PendingSearch,
+    PrefetchRequest, SpritzSettings,
+};
 
 pub struct App {
     pub blocks: Vec<ReaderBlock>,
     pub initial_page: Option<usize>,
+    pub initial_location: Option<Location>,
+    pub pending_location: Option<Location>,
     pub mode: Mode,
     pub toc: Option<TocView>,
     pub search: Option<SearchView>,
+    pub link_hints: Option<LinkHints>,
+    pub command_line: Option<CommandLineView>,
+    pub ex_history: Vec<String>,
+    pub status: Option<String>, // message on the bottom line until the next key
     pub spritz: Option<SpritzView>,
     pub footnote: Option<FootnoteView>,
     pub chapter_titles: Vec<String>,
@@ -32,11 +48,25 @@ pub struct App {
     pub outlines: Vec<OutlineEntry>,
     pub book_title: Option<String>,
     pub author: Option<String>,
-    pub book_id: Option<String>,
+    pub language: Option<String>,
+    pub right_to_left: bool, // from the book's metadata, e.g. manga
+    pub book: Option<BookId>,
+    pub bookmarks: Vec<Location>,
+    pub jumps: JumpList,
+    pub bookmark_view: Option<BookmarkView>,
+    pub library: Option<LibraryView>,
+    pub open_request: Option<OpenRequest>,
+    pub annotations: Vec<Annotation>,
+    pub note_input: Option<NoteView>,
     pub theme: Theme,
-    pub last_search: Option<String>,
-    pub last_search_hit: Option<usize>,
+    pub theme_changed: bool, // set at the command line, so saved to settings.toml
+    pub last_search: Option<Matcher>,
+    pub search_options: SearchOptions,
+    pub spritz_settings: SpritzSettings,
     pub show_help: bool,
+    pub keymap: Keymap,
+    pub pending_keys: Vec<KeyChord>, // start of a multi-key binding
+    pub count: Option<usize>,        // count typed before a command
     pub incoming_pages: Option<Receiver<IncomingPage>>,
     pub total_pages: Option<usize>,
     pub prefetch_tx: Option<Sender<PrefetchRequest>>,
@@ -48,6 +78,8 @@ pub struct App {
     pub prefetch_chapter_window: usize,
     pub last_chapter_prefetch_at: Option<usize>,
     pub pending_chapter_jump: Option<String>,
+    pub pending_goto: Option<(GoTo, Location)>, // target and where the jump started
+    pub pending_search: Option<PendingSearch>,
     pub chapter_index_by_href: HashMap<String, usize>,
     pub clipboard: Option<Clipboard>,
 }
@@ -63,9 +95,15 @@ impl App {
         Self {
             blocks: Vec::new(),
             initial_page: None,
+            initial_location: None,
+            pending_location: None,
             mode: Mode::Reader,
             toc: None,
             search: None,
+            link_hints: None,
+            command_line: None,
+            ex_history: Vec::new(),
+            status: None,
             spritz: None,
             footnote: None,
             chapter_titles: Vec::new(),
@@ -74,11 +112,25 @@ impl App {
             outlines: Vec::new(),
             book_title: None,
             author: None,
-            book_id: None,
+            language: None,
+            right_to_left: false,
+            book: None,
+            bookmarks: Vec::new(),
+            jumps: JumpList::default(),
+            bookmark_view: None,
+            library: None,
+            open_request: None,
+            annotations: Vec::new(),
+            note_input: None,
             theme: Theme::default(),
+            theme_changed: false,
             last_search: None,
-            last_search_hit: None,
+            search_options: SearchOptions::default(),
+            spritz_settings: SpritzSettings::default(),
             show_help: false,
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            count: None,
             incoming_pages: None,
             total_pages: None,
             prefetch_tx: None,
@@ -90,6 +142,8 @@ impl App {
             prefetch_chapter_window: 2,
             last_chapter_prefetch_at: None,
             pending_chapter_jump: None,
+            pending_goto: None,
+            pending_search: None,
             chapter_index_by_href: HashMap::new(),
             clipboard: None,
         }
@@ -99,9 +153,15 @@ impl App {
         Self {
             blocks,
             initial_page: None,
+            initial_location: None,
+            pending_location: None,
             mode: Mode::Reader,
             toc: None,
             search: None,
+            link_hints: None,
+            command_line: None,
+            ex_history: Vec::new(),
+            status: None,
             spritz: None,
             footnote: None,
             chapter_titles: Vec::new(),
@@ -110,11 +170,25 @@ impl App {
             outlines: Vec::new(),
             book_title: None,
             author: None,
-            book_id: None,
+            language: None,
+            right_to_left: false,
+            book: None,
+            bookmarks: Vec::new(),
+            jumps: JumpList::default(),
+            bookmark_view: None,
+            library: None,
+            open_request: None,
+            annotations: Vec::new(),
+            note_input: None,
             theme: Theme::default(),
+            theme_changed: false,
             last_search: None,
-            last_search_hit: None,
+            search_options: SearchOptions::default(),
+            spritz_settings: SpritzSettings::default(),
             show_help: false,
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            count: None,
             incoming_pages: None,
             total_pages: None,
             prefetch_tx: None,
@@ -126,6 +200,8 @@ impl App {
             prefetch_chapter_window: 2,
             last_chapter_prefetch_at: None,
             pending_chapter_jump: None,
+            pending_goto: None,
+            pending_search: None,
             chapter_index_by_href: HashMap::new(),
             clipboard: None,
         }
@@ -139,9 +215,15 @@ impl App {
         Self {
             blocks,
             initial_page: Some(initial_page),
+            initial_location: None,
+            pending_location: None,
             mode: Mode::Reader,
             toc: None,
             search: None,
+            link_hints: None,
+            command_line: None,
+            ex_history: Vec::new(),
+            status: None,
             spritz: None,
             footnote: None,
             chapter_titles,
@@ -150,11 +232,25 @@ impl App {
             outlines: Vec::new(),
             book_title: None,
             author: None,
-            book_id: None,
+            language: None,
+            right_to_left: false,
+            book: None,
+            bookmarks: Vec::new(),
+            jumps: JumpList::default(),
+            bookmark_view: None,
+            library: None,
+            open_request: None,
+            annotations: Vec::new(),
+            note_input: None,
             theme: Theme::default(),
+            theme_changed: false,
             last_search: None,
-            last_search_hit: None,
+            search_options: SearchOptions::default(),
+            spritz_settings: SpritzSettings::default(),
             show_help: false,
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            count: None,
             incoming_pages: None,
             total_pages: None,
             prefetch_tx: None,
@@ -166,6 +262,8 @@ impl App {
             prefetch_chapter_window: 2,
             last_chapter_prefetch_at: None,
             pending_chapter_jump: None,
+            pending_goto: None,
+            pending_search: None,
             chapter_index_by_href: HashMap::new(),
             clipboard: None,
         }
@@ -179,7 +277,14 @@ impl App {
         app.toc_entries = toc_entries;
         app.book_title = info.title().map(str::to_string);
         app.author = info.author().map(str::to_string);
-        app.book_id = Some(info.id().to_string());
+        app.language = info.language().map(str::to_string);
+        app.right_to_left = info.right_to_left();
+        app.book = Some(BookId::new(
+            info.id(),
+            info.path(),
+            info.title().map(str::to_string),
+            info.format(),
+        ));
         app.outlines = outlines;
         if !app.chapter_titles.is_empty() {
             app.total_chapters = Some(app.chapter_titles.len());
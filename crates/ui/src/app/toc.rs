@@ -1,65 +1,110 @@
 use crate::reader_view::ReaderView;
-use crate::views::{TocItem, TocView};
+use crate::toc_view::{TocItem, TocView};
 
-use super::types::{ChapterPrefetchRequest, Mode};
+use super::types::{ChapterPrefetchRequest, Fold, GoTo, Mode, SearchCommand};
 use super::App;
 
 impl App {
     pub(super) fn open_toc(&mut self, view: &ReaderView) {
         let items = self.build_toc_items(view);
-        let mut toc = TocView::new(items);
-        if let Some(idx) = toc
-            .items
-            .iter()
-            .enumerate()
-            .rev()
-            .find_map(|(i, item)| item.page.filter(|p| *p <= view.current).map(|_| i))
-        {
-            toc.selected = idx;
-        }
-        self.toc = Some(toc);
+        let pages = self.all_chapters_loaded().then_some(view.pages.len());
+        self.toc = Some(TocView::new(items, view.current, pages));
         self.mode = Mode::Toc;
     }
 
     pub(super) fn submit_toc(&mut self, view: &mut ReaderView) {
-        if let Some(toc) = &self.toc {
-            if let Some(item) = toc.current_item() {
-                if let Some(target) = item.page {
-                    view.current = target.min(view.pages.len().saturating_sub(1));
-                } else if let Some(href) = item.href.as_deref() {
-                    if !view.jump_to_target(href) {
-                        self.pending_chapter_jump = Some(href.to_string());
-                        if let Some(tx) = &self.prefetch_chapter_tx {
-                            let target_loaded = self
-                                .chapter_index_for_href(href)
-                                .map(|idx| idx.saturating_add(1))
-                                .unwrap_or_else(|| {
-                                    self.chapter_titles
-                                        .len()
-                                        .saturating_add(self.prefetch_chapter_window.max(1))
-                                });
-                            let _ = tx.send(ChapterPrefetchRequest {
-                                target_loaded,
-                                target_href: Some(href.to_string()),
-                            });
-                        }
-                    }
-                }
+        self.mode = Mode::Reader;
+        let Some(toc) = self.toc.take() else {
+            return;
+        };
+        let Some(item) = toc.current_item() else {
+            return;
+        };
+        // Outline entries of a PDF still streaming in go to its pages
+        let pdf_page = item
+            .href
+            .as_deref()
+            .and_then(|href| href.strip_prefix("page:"))
+            .and_then(|page| page.parse().ok())
+            .filter(|_| self.incoming_pages.is_some());
+        let from = self.location_for_view(view);
+        if let Some(page) = item.page {
+            self.show_page(view, page);
+        } else if let Some(page) = pdf_page {
+            self.go_to(view, GoTo::Page(page));
+            return;
+        } else if let Some(href) = &item.href {
+            if !self.jump_to_href(view, href) {
+                return;
             }
         }
-        self.mode = Mode::Reader;
-        self.toc = None;
+        self.push_jump(view, from);
+    }
+
+    pub(super) fn fold_toc(&mut self, fold: Fold) {
+        let Some(toc) = &mut self.toc else {
+            return;
+        };
+        match fold {
+            Fold::Open => toc.open(),
+            Fold::Close => toc.close(),
+            Fold::Toggle => toc.toggle(),
+            Fold::Level(levels) => toc.fold_to_level(levels),
+            Fold::All => toc.unfold_all(),
+        }
+    }
+
+    // Enter jumps to the selected match right away
+    pub(super) fn apply_toc_filter(&mut self, view: &mut ReaderView, command: SearchCommand) {
+        let Some(toc) = &mut self.toc else {
+            return;
+        };
+        match command {
+            SearchCommand::Cancel => toc.clear_filter(),
+            SearchCommand::Submit => self.submit_toc(view),
+            SearchCommand::Backspace => toc.backspace(),
+            SearchCommand::Insert(c) => toc.push_char(c),
+        }
+    }
+
+    /// Jump to `href`, or stream in its chapter first and jump once it
+    /// arrives. False when the target is nowhere in the book.
+    pub(super) fn jump_to_href(&mut self, view: &mut ReaderView, href: &str) -> bool {
+        if view.jump_to_target(href) {
+            return true;
+        }
+        if self.all_chapters_loaded() {
+            return false;
+        }
+        self.pending_chapter_jump = Some(href.to_string());
+        if let Some(tx) = &self.prefetch_chapter_tx {
+            let target_loaded = self
+                .chapter_index_for_href(href)
+                .map(|idx| idx.saturating_add(1))
+                .unwrap_or_else(|| {
+                    self.chapter_titles
+                        .len()
+                        .saturating_add(self.prefetch_chapter_window.max(1))
+                });
+            let _ = tx.send(ChapterPrefetchRequest {
+                target_loaded,
+                target_href: Some(href.to_string()),
+            });
+        }
+        true
     }
 
     fn build_toc_items(&self, view: &ReaderView) -> Vec<TocItem> {
         if !self.outlines.is_empty() {
             let mut items = Vec::new();
             for entry in &self.outlines {
+                let href = format!("page:{}", entry.page_index + 1);
                 items.push(TocItem {
                     label: entry.title.clone(),
-                    level: 0,
-                    page: Some(entry.page_index),
-                    href: None,
+                    level: entry.level,
+                    page: view.page_for_href(&href),
+                    href: Some(href),
+                    progress: None,
                 });
             }
             return items;
@@ -73,6 +118,7 @@ impl App {
                     level: entry.level(),
                     page,
                     href: Some(entry.href().to_string()),
+                    progress: None,
                 });
             }
             return items;
@@ -83,6 +129,7 @@ impl App {
                 level: 0,
                 page: Some(0),
                 href: None,
+                progress: None,
             }];
         }
         let mut items: Vec<TocItem> = Vec::new();
@@ -101,6 +148,7 @@ impl App {
                 level: 0,
                 page: Some(*pidx),
                 href: None,
+                progress: None,
             });
         }
         items
@@ -1,4 +1,4 @@
-use reader_core::types::Block as ReaderBlock;
+use reader_core::types::{Block as ReaderBlock, Location};
 
 #[derive(Clone, Copy, Debug)]
 pub struct SpritzSettings {
@@ -17,13 +17,36 @@ impl Default for SpritzSettings {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mode {
     Reader,
     Toc,
     Spritz,
+    Bookmarks,
+    Library,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Where `gg`, `G` and the `:` prompt go. Pages and chapters count from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoTo {
+    Start,
+    End,
+    Page(usize),
+    Percent(u8),
+    Chapter(usize),
+}
+
+/// Folding the table of contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Fold {
+    Open,  // expand the entry, or step into it
+    Close, // collapse the entry, or step out to its parent
+    Toggle,
+    Level(usize), // show this many levels
+    All,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(super) enum SearchCommand {
     Cancel,
     Submit,
@@ -31,13 +54,42 @@ pub(super) enum SearchCommand {
     Insert(char),
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Search modes toggled from the search prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SearchToggle {
+    Regex,
+    WholeWord,
+    Case,
+    Diacritics,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(super) enum Command {
     Exit,
     Cancel,
     Submit,
     StartSearch,
+    SearchNext(usize),
+    SearchPrevious(usize),
+    JumpBack(usize),
+    JumpForward(usize),
+    StartLinkHints,
+    StartCommandLine,
+    GoTo(GoTo),
+    NextChapter(usize),
+    PreviousChapter(usize),
+    NextHeading(usize),
+    PreviousHeading(usize),
     ToggleToc,
+    Fold(Fold),
+    ToggleBookmarks,
+    OpenLibrary,
+    CycleLibrarySort,
+    StartLibrarySearch,
+    AddBookmark,
+    RemoveBookmark,
+    Highlight,
+    StartNote,
     ToggleSpritz,
     ToggleHelp,
     CloseHelp,
@@ -45,9 +97,11 @@ pub(super) enum Command {
     AdjustWidth(i16),
     NavigateDown(usize),
     NavigateUp(usize),
-    PageDown,
-    PageUp,
+    PageDown(usize),
+    PageUp(usize),
     ToggleJustify,
+    ToggleHyphenate,
+    ToggleLineBreaking,
     ToggleTwoPane,
     SpritzTogglePlay,
     SpritzJumpToChapterStart,
@@ -56,6 +110,15 @@ pub(super) enum Command {
     SpritzAdvance(usize),
     SpritzRewind(usize),
     Search(SearchCommand),
+    ToggleSearchOption(SearchToggle),
+    Note(SearchCommand),
+    LinkHint(SearchCommand),
+    Ex(SearchCommand),
+    ExComplete { back: bool },
+    ExHistory { older: bool },
+    TocFilter(SearchCommand),
+    LibraryFilter(SearchCommand),
+    LibraryQuery(SearchCommand),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -64,6 +127,19 @@ pub(super) enum CommandOutcome {
     Exit,
 }
 
+/// A book picked in the library, optionally at a search hit.
+pub struct OpenRequest {
+    pub path: String,
+    pub location: Option<Location>,
+}
+
+/// How `App::run` ended: where reading stopped, and the book picked in
+/// the library to open next.
+pub struct RunExit {
+    pub location: Location,
+    pub open: Option<OpenRequest>,
+}
+
 pub struct IncomingPage {
     pub page_index: usize,
     pub blocks: Vec<ReaderBlock>,
@@ -85,3 +161,12 @@ pub struct ChapterPrefetchRequest {
     pub target_loaded: usize,
     pub target_href: Option<String>,
 }
+
+/// A search that ran past the loaded text; its match is shown once the
+/// text it is in has streamed in.
+#[derive(Clone)]
+pub struct PendingSearch {
+    pub from_block: usize, // first block not loaded when the search ran
+    pub requested: usize,  // chapters or pages asked for so far
+    pub from: Location,    // where the search started
+}
@@ -0,0 +1,154 @@
+use ratatui::{prelude::*, widgets::*};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The `:` prompt on the bottom line of the screen, with Tab completion
+/// and Up/Down through earlier commands.
+pub struct CommandLineView {
+    pub input: String,
+    pub error: Option<String>, // why the last command was rejected
+    pub completions: Vec<String>,
+    pub completion: Option<usize>, // entry of `completions` in `input`
+    pub history_index: Option<usize>,
+    pub draft: String, // input typed before stepping through history
+}
+
+impl Default for CommandLineView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandLineView {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            error: None,
+            completions: Vec::new(),
+            completion: None,
+            history_index: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.input.push(c);
+            self.edited();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some((idx, _)) = self.input.grapheme_indices(true).next_back() {
+            self.input.truncate(idx);
+        }
+        self.edited();
+    }
+
+    fn edited(&mut self) {
+        self.error = None;
+        self.completions.clear();
+        self.completion = None;
+    }
+
+    /// Step through `completions`, computed for the input on the first
+    /// Tab. A single completion is taken at once, so the next Tab can
+    /// complete the word after it.
+    pub fn complete(&mut self, completions: impl FnOnce(&str) -> Vec<String>, back: bool) {
+        if self.completion.is_none() {
+            self.completions = completions(&self.input);
+        }
+        let count = self.completions.len();
+        if count == 0 {
+            return;
+        }
+        if count == 1 {
+            self.input = self.completions.remove(0);
+            self.edited();
+            return;
+        }
+        let next = match (self.completion, back) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(idx), false) => (idx + 1) % count,
+            (Some(idx), true) => (idx + count - 1) % count,
+        };
+        self.completion = Some(next);
+        self.input = self.completions[next].clone();
+        self.error = None;
+    }
+
+    /// Up/Down: replace the input with an older or newer entry of
+    /// `history`, and past the newest, with what was typed before.
+    pub fn recall(&mut self, history: &[String], older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) => history.len().checked_sub(1),
+            (None, false) => return,
+            (Some(idx), true) => Some(idx.saturating_sub(1)),
+            (Some(idx), false) => Some(idx + 1).filter(|next| *next < history.len()),
+        };
+        if index.is_none() && self.history_index.is_none() {
+            return;
+        }
+        if self.history_index.is_none() {
+            self.draft = self.input.clone();
+        }
+        self.history_index = index;
+        self.input = match index {
+            Some(idx) => history[idx].clone(),
+            None => self.draft.clone(),
+        };
+        self.edited();
+    }
+
+    pub fn render(&self, f: &mut Frame<'_>, area: Rect) {
+        let mut line = Line::from(format!(":{}", self.input));
+        if let Some(error) = &self.error {
+            line.push_span(Span::raw("  "));
+            line.push_span(Span::styled(error.clone(), Style::default().fg(Color::Red)));
+        }
+        render_bottom_line(f, area, 0, line);
+        if self.completions.len() > 1 {
+            self.render_completions(f, area);
+        }
+    }
+
+    // Like vim's wildmenu: the last words of each completion on the line
+    // above the prompt, the chosen one reversed
+    fn render_completions(&self, f: &mut Frame<'_>, area: Rect) {
+        let mut line = Line::default();
+        for (idx, completion) in self.completions.iter().enumerate() {
+            let word = completion
+                .rsplit([' ', '/', '='])
+                .find(|word| !word.is_empty())
+                .unwrap_or(completion);
+            let style = if Some(idx) == self.completion {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            line.push_span(Span::styled(word.to_string(), style));
+            line.push_span(Span::raw("  "));
+        }
+        render_bottom_line(f, area, 1, line);
+    }
+}
+
+/// A message on the bottom line of the screen, where the prompt was.
+pub fn render_status(f: &mut Frame<'_>, area: Rect, message: &str) {
+    render_bottom_line(f, area, 0, Line::from(message.to_string()));
+}
+
+// `above` counts lines up from the last one
+fn render_bottom_line(f: &mut Frame<'_>, area: Rect, above: u16, line: Line<'_>) {
+    if area.height <= above {
+        return;
+    }
+    let line_area = Rect {
+        x: area.x,
+        y: area.y + area.height - 1 - above,
+        width: area.width,
+        height: 1,
+    };
+    f.render_widget(Clear, line_area);
+    f.render_widget(Paragraph::new(line), line_area);
+}
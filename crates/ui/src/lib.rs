@@ -1,6 +1,9 @@
 pub mod app;
+pub mod command_line;
 pub mod layout;
+pub mod library_view;
 pub mod reader_view;
 pub mod search_view;
 pub mod spritz_view;
+pub mod toc_view;
 pub mod views;
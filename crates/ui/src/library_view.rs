@@ -0,0 +1,462 @@
+use std::cmp::Ordering;
+
+use ratatui::{prelude::*, widgets::*};
+use reader_core::index::SearchHit;
+use reader_core::library::LibraryBook;
+use reader_core::types::{AppStateRecord, DocumentFormat};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LibrarySort {
+    #[default]
+    Recent,
+    Title,
+    Author,
+    Series,
+}
+
+impl LibrarySort {
+    pub fn name(self) -> &'static str {
+        match self {
+            LibrarySort::Recent => "recent",
+            LibrarySort::Title => "title",
+            LibrarySort::Author => "author",
+            LibrarySort::Series => "series",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            LibrarySort::Recent => LibrarySort::Title,
+            LibrarySort::Title => LibrarySort::Author,
+            LibrarySort::Author => LibrarySort::Series,
+            LibrarySort::Series => LibrarySort::Recent,
+        }
+    }
+}
+
+pub struct LibraryItem {
+    pub book: LibraryBook,
+    pub last_read: Option<u64>,
+    pub progress: Option<String>,
+}
+
+impl LibraryItem {
+    pub fn new(book: LibraryBook, record: Option<&AppStateRecord>) -> Self {
+        let progress = record.map(|rec| progress_label(&book, rec));
+        Self {
+            last_read: record.and_then(|rec| rec.last_read()),
+            progress,
+            book,
+        }
+    }
+
+    fn matches(&self, terms: &[String]) -> bool {
+        let haystack = [
+            Some(self.book.display_title()),
+            self.book.author(),
+            self.book.series(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+        terms.iter().all(|term| haystack.contains(term))
+    }
+}
+
+// Where reading stopped, in the units the format has: chapters of the spine,
+// PDF or comic pages, or pages of the last layout for plain text.
+fn progress_label(book: &LibraryBook, record: &AppStateRecord) -> String {
+    let loc = record.last_location();
+    match book.format() {
+        DocumentFormat::Pdf => {
+            let page = loc
+                .chapter_href()
+                .and_then(|href| href.strip_prefix("page:"))
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(loc.spine_index() + 1);
+            match book.page_count() {
+                Some(total) => format!("p {}/{}", page.min(total), total),
+                None => format!("p {}", page),
+            }
+        }
+        DocumentFormat::Epub3
+        | DocumentFormat::Epub2
+        | DocumentFormat::Fb2
+        | DocumentFormat::Mobi
+        | DocumentFormat::Html => match book.chapter_count() {
+            Some(total) => format!("ch {}/{}", (loc.spine_index() + 1).min(total), total),
+            None => format!("ch {}", loc.spine_index() + 1),
+        },
+        DocumentFormat::Cbz => {
+            // Every block of a comic is one page image
+            let page = loc.block_index().unwrap_or(loc.offset()) + 1;
+            match book.page_count() {
+                Some(total) => format!("p {}/{}", page.min(total), total),
+                None => format!("p {}", page),
+            }
+        }
+        _ => format!("p {}", loc.offset() + 1),
+    }
+}
+
+fn format_label(format: DocumentFormat) -> &'static str {
+    match format {
+        DocumentFormat::Epub3 | DocumentFormat::Epub2 => "EPUB",
+        DocumentFormat::Pdf => "PDF",
+        DocumentFormat::Text => "TXT",
+        DocumentFormat::Markdown => "MD",
+        DocumentFormat::Fb2 => "FB2",
+        DocumentFormat::Mobi => "MOBI",
+        DocumentFormat::Cbz => "CBZ",
+        DocumentFormat::Html => "HTML",
+        DocumentFormat::Other => "?",
+    }
+}
+
+/// Catalogued books with sorting and an incremental filter. `visible`
+/// holds indices into `items` in display order. A full-text search
+/// replaces the list with `hits` until it is closed.
+pub struct LibraryView {
+    pub items: Vec<LibraryItem>,
+    pub visible: Vec<usize>,
+    pub selected: usize,
+    pub sort: LibrarySort,
+    pub filter: String,
+    pub filtering: bool,
+    pub query: Option<String>, // full-text query being typed
+    pub hits: Option<Vec<SearchHit>>,
+    pub hit_selected: usize,
+}
+
+impl LibraryView {
+    pub fn new(items: Vec<LibraryItem>, sort: LibrarySort) -> Self {
+        let mut view = Self {
+            items,
+            visible: Vec::new(),
+            selected: 0,
+            sort,
+            filter: String::new(),
+            filtering: false,
+            query: None,
+            hits: None,
+            hit_selected: 0,
+        };
+        view.refresh();
+        view
+    }
+
+    pub fn up(&mut self) {
+        if self.hits.is_some() {
+            self.hit_selected = self.hit_selected.saturating_sub(1);
+        } else {
+            self.selected = self.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if let Some(hits) = &self.hits {
+            if !hits.is_empty() {
+                self.hit_selected = (self.hit_selected + 1).min(hits.len() - 1);
+            }
+        } else if !self.visible.is_empty() {
+            self.selected = (self.selected + 1).min(self.visible.len() - 1);
+        }
+    }
+
+    pub fn current_hit(&self) -> Option<&SearchHit> {
+        self.hits.as_ref()?.get(self.hit_selected)
+    }
+
+    pub fn show_hits(&mut self, hits: Vec<SearchHit>) {
+        self.hits = Some(hits);
+        self.hit_selected = 0;
+    }
+
+    pub fn current_item(&self) -> Option<&LibraryItem> {
+        self.visible
+            .get(self.selected)
+            .and_then(|idx| self.items.get(*idx))
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.refresh();
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.filter.push(c);
+            self.refresh();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some((idx, _)) = self.filter.grapheme_indices(true).next_back() {
+            self.filter.truncate(idx);
+            self.refresh();
+        }
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filtering = false;
+        self.refresh();
+    }
+
+    /// Re-apply filter and sort, keeping the selected book selected.
+    pub fn refresh(&mut self) {
+        let current = self.visible.get(self.selected).copied();
+        let terms: Vec<String> = self
+            .filter
+            .to_lowercase()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let mut visible: Vec<usize> = (0..self.items.len())
+            .filter(|idx| self.items[*idx].matches(&terms))
+            .collect();
+        visible.sort_by(|a, b| self.compare(&self.items[*a], &self.items[*b]));
+        self.selected = current
+            .and_then(|idx| visible.iter().position(|v| *v == idx))
+            .unwrap_or(0);
+        self.visible = visible;
+    }
+
+    fn compare(&self, a: &LibraryItem, b: &LibraryItem) -> Ordering {
+        let title = |item: &LibraryItem| item.book.display_title().to_lowercase();
+        let author = |item: &LibraryItem| item.book.author().map(str::to_lowercase);
+        let series = |item: &LibraryItem| {
+            (
+                item.book.series().is_none(),
+                item.book.series().map(str::to_lowercase),
+            )
+        };
+        let index = |item: &LibraryItem| item.book.series_index().unwrap_or(f32::MAX);
+        let by_series = || {
+            series(a)
+                .cmp(&series(b))
+                .then_with(|| index(a).total_cmp(&index(b)))
+        };
+        match self.sort {
+            // Books never opened go last
+            LibrarySort::Recent => b.last_read.cmp(&a.last_read),
+            LibrarySort::Title => Ordering::Equal,
+            LibrarySort::Author => (author(a).is_none(), author(a))
+                .cmp(&(author(b).is_none(), author(b)))
+                .then_with(by_series),
+            LibrarySort::Series => by_series().then_with(|| author(a).cmp(&author(b))),
+        }
+        .then_with(|| title(a).cmp(&title(b)))
+        .then_with(|| a.book.path().cmp(b.book.path()))
+    }
+
+    pub fn render(&self, f: &mut Frame<'_>, area: Rect) {
+        let vchunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area);
+
+        if let Some(hits) = &self.hits {
+            self.render_hits(f, vchunks[0], hits);
+        } else {
+            self.render_books(f, vchunks[0]);
+        }
+
+        let status = if let Some(query) = &self.query {
+            format!("Search books: {}_", query)
+        } else if self.hits.is_some() {
+            "Search: Enter to open at the match, Esc to return to the library".to_string()
+        } else if self.filtering {
+            format!("Filter: {}_", self.filter)
+        } else if !self.filter.is_empty() {
+            format!("Filter: {} (/ to edit, Esc to clear)", self.filter)
+        } else {
+            "Library: Enter to open, / to filter, f to search inside books, o to change sort, q to quit"
+                .to_string()
+        };
+        f.render_widget(Paragraph::new(Line::from(status)), vchunks[1]);
+    }
+
+    fn render_hits(&self, f: &mut Frame<'_>, area: Rect, hits: &[SearchHit]) {
+        let block = Block::default().borders(Borders::ALL).title(format!(
+            "Search: {} {} (j/k, Enter, Esc)",
+            hits.len(),
+            if hits.len() == 1 { "match" } else { "matches" }
+        ));
+        let max_w = (area.width as usize).saturating_sub(2);
+        let items: Vec<ListItem> = if hits.is_empty() {
+            vec![ListItem::new(Line::from(
+                "No matches; books are indexed in the background after `librarian scan`",
+            ))]
+        } else {
+            hits.iter()
+                .map(|hit| {
+                    let book = hit.book_title.as_deref().unwrap_or(&hit.book_path);
+                    let heading = format!("{} — {}", book, hit.chapter_title);
+                    let dim = Style::default().fg(Color::DarkGray);
+                    let snippet = Line::from(vec![
+                        Span::styled("  ", dim),
+                        Span::styled(hit.snippet[..hit.highlight.start].to_string(), dim),
+                        Span::styled(
+                            hit.snippet[hit.highlight.clone()].to_string(),
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(hit.snippet[hit.highlight.end..].to_string(), dim),
+                    ]);
+                    ListItem::new(vec![Line::from(truncate(&heading, max_w)), snippet])
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+        let mut state =
+            ListState::default().with_selected((!hits.is_empty()).then_some(self.hit_selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_books(&self, f: &mut Frame<'_>, area: Rect) {
+        let title = format!(
+            "Library: {} of {} books, by {} (j/k, Enter, /, f, o, Esc)",
+            self.visible.len(),
+            self.items.len(),
+            self.sort.name()
+        );
+        let block = Block::default().borders(Borders::ALL).title(title);
+        if self.items.is_empty() {
+            let empty = Paragraph::new(
+                "No books catalogued yet.\n\nRun `librarian scan <folder>` to add a folder, then start librarian again.",
+            )
+            .block(block)
+            .wrap(Wrap { trim: false });
+            f.render_widget(Clear, area);
+            f.render_widget(empty, area);
+        } else {
+            let rows: Vec<Row> = self
+                .visible
+                .iter()
+                .map(|idx| {
+                    let item = &self.items[*idx];
+                    let series = item
+                        .book
+                        .series()
+                        .map(|name| match item.book.series_index() {
+                            Some(index) => format!("{} #{}", name, index),
+                            None => name.to_string(),
+                        });
+                    Row::new(vec![
+                        Cell::from(item.book.display_title().to_string()),
+                        Cell::from(item.book.author().unwrap_or("").to_string()),
+                        Cell::from(series.unwrap_or_default()),
+                        Cell::from(format_label(item.book.format())),
+                        Cell::from(item.progress.clone().unwrap_or_else(|| "new".into())),
+                    ])
+                })
+                .collect();
+            let widths = [
+                Constraint::Percentage(40),
+                Constraint::Percentage(22),
+                Constraint::Percentage(22),
+                Constraint::Length(4),
+                Constraint::Length(10),
+            ];
+            let table = Table::new(rows, widths)
+                .header(
+                    Row::new(vec!["Title", "Author", "Series", "Type", "Progress"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(block)
+                .row_highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+            let mut state = TableState::default()
+                .with_selected((!self.visible.is_empty()).then_some(self.selected));
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(table, area, &mut state);
+        }
+    }
+}
+
+fn truncate(text: &str, max_w: usize) -> String {
+    let gs: Vec<&str> = text.graphemes(true).collect();
+    if gs.len() <= max_w {
+        return text.to_string();
+    }
+    format!("{}…", gs[..max_w.saturating_sub(1)].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reader_core::library::Catalog;
+    use reader_core::types::{BookId, Location};
+    use std::fs;
+
+    fn catalog(dir: &std::path::Path) -> Catalog {
+        for name in ["zebra_notes.txt", "alpha.md", "middle_march.txt"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let mut catalog = Catalog::default();
+        catalog.add_root(dir);
+        catalog.scan();
+        catalog
+    }
+
+    fn record(book: &LibraryBook, last_read: u64) -> AppStateRecord {
+        let id = BookId::new(book.id(), book.path(), None, book.format());
+        let mut rec = AppStateRecord::new(id, Location::new(0, 4), Vec::new());
+        rec.set_last_read(Some(last_read));
+        rec
+    }
+
+    fn titles(view: &LibraryView) -> Vec<&str> {
+        view.visible
+            .iter()
+            .map(|idx| view.items[*idx].book.display_title())
+            .collect()
+    }
+
+    #[test]
+    fn sorts_and_filters_keep_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let catalog = catalog(dir.path());
+        let items = catalog
+            .books()
+            .iter()
+            .map(|book| {
+                let rec = match book.display_title() {
+                    "zebra notes" => Some(record(book, 200)),
+                    "middle march" => Some(record(book, 100)),
+                    _ => None,
+                };
+                LibraryItem::new(book.clone(), rec.as_ref())
+            })
+            .collect();
+        let mut view = LibraryView::new(items, LibrarySort::Recent);
+        assert_eq!(titles(&view), ["zebra notes", "middle march", "alpha"]);
+        assert_eq!(
+            view.current_item().unwrap().progress.as_deref(),
+            Some("p 5")
+        );
+
+        view.down();
+        view.cycle_sort();
+        assert_eq!(view.sort, LibrarySort::Title);
+        assert_eq!(titles(&view), ["alpha", "middle march", "zebra notes"]);
+        assert_eq!(
+            view.current_item().unwrap().book.display_title(),
+            "middle march"
+        );
+
+        for c in "NOTE".chars() {
+            view.push_char(c);
+        }
+        assert_eq!(titles(&view), ["zebra notes"]);
+        view.clear_filter();
+        assert_eq!(view.visible.len(), 3);
+    }
+}
@@ -1,4 +1,6 @@
+mod annotations;
 mod areas;
+mod hints;
 mod images;
 mod navigation;
 mod render;
@@ -13,7 +15,9 @@ mod tests;
 
 const SPREAD_GAP: u16 = 4;
 
+pub use annotations::HighlightSpan;
 pub use areas::ContentAreas;
+pub use hints::{LinkHint, LinkHints};
 pub use selection::{SelectionPoint, SelectionRange};
-pub use theme::Theme;
+pub use theme::{color_name, color_names, parse_color, Theme};
 pub use view::ReaderView;
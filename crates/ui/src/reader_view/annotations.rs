@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use reader_core::layout::{Segment, StyledLine, TextPosition};
+use reader_core::types::RgbColor;
+
+use super::{ReaderView, SelectionPoint};
+
+/// A resolved annotation range in the current block stream.
+#[derive(Clone, Copy)]
+pub struct HighlightSpan {
+    pub start: TextPosition,
+    pub end: TextPosition,
+    pub color: RgbColor,
+    pub has_note: bool,
+    pub annotation: usize, // index into App::annotations
+}
+
+impl ReaderView {
+    /// Content position under a selection point. Spacer lines snap to the
+    /// next line when `forward`, otherwise to the end of the previous one.
+    pub fn position_at_point(&self, point: SelectionPoint, forward: bool) -> Option<TextPosition> {
+        let page = self.pages.get(point.page)?;
+        if let Some(Some(pos)) = page.positions.get(point.line) {
+            let line = &page.lines[point.line];
+            let cols = line.column_units();
+            let units = cols.get(point.col).copied().unwrap_or(line.text_units());
+            return Some(TextPosition {
+                block: pos.block,
+                offset: pos.offset + units,
+            });
+        }
+        if forward {
+            page.positions.iter().skip(point.line).find_map(|p| *p)
+        } else {
+            let (line, pos) = page
+                .positions
+                .iter()
+                .enumerate()
+                .take(point.line)
+                .rev()
+                .find_map(|(i, p)| p.map(|p| (i, p)))?;
+            Some(TextPosition {
+                block: pos.block,
+                offset: pos.offset + page.lines[line].text_units(),
+            })
+        }
+    }
+
+    /// Annotation index of a highlight covering the point, if any.
+    pub fn highlight_at_point(&self, point: SelectionPoint) -> Option<usize> {
+        let pos = self.position_at_point(point, true)?;
+        self.highlights
+            .iter()
+            .find(|span| span.start <= pos && pos < span.end)
+            .map(|span| span.annotation)
+    }
+
+    /// Rows of a page where an annotation with a note begins.
+    pub(super) fn note_rows(&self, page_idx: usize) -> Vec<usize> {
+        let Some(page) = self.pages.get(page_idx) else {
+            return Vec::new();
+        };
+        let mut rows = Vec::new();
+        for (row, pos) in page.positions.iter().enumerate() {
+            let Some(pos) = pos else {
+                continue;
+            };
+            let units = page.lines[row].text_units();
+            let starts_here = self.highlights.iter().any(|span| {
+                span.has_note
+                    && span.start.block == pos.block
+                    && span.start.offset >= pos.offset
+                    && span.start.offset <= pos.offset + units
+            });
+            if starts_here {
+                rows.push(row);
+            }
+        }
+        rows
+    }
+
+    pub(super) fn apply_highlights<'a>(
+        &self,
+        page_idx: usize,
+        line_idx: usize,
+        line: &'a StyledLine,
+    ) -> Cow<'a, StyledLine> {
+        let pos = self
+            .pages
+            .get(page_idx)
+            .and_then(|page| page.positions.get(line_idx))
+            .copied()
+            .flatten();
+        let Some(pos) = pos else {
+            return Cow::Borrowed(line);
+        };
+        if self.highlights.is_empty() || line.image.is_some() {
+            return Cow::Borrowed(line);
+        }
+        let units = line.text_units();
+        let line_end = TextPosition {
+            block: pos.block,
+            offset: pos.offset + units,
+        };
+        let mut out: Option<StyledLine> = None;
+        for span in &self.highlights {
+            if span.end <= pos || span.start >= line_end {
+                continue;
+            }
+            let start_units = if span.start > pos {
+                span.start.offset - pos.offset
+            } else {
+                0
+            };
+            let end_units = if span.end < line_end {
+                span.end.offset - pos.offset
+            } else {
+                units
+            };
+            let styled = out.get_or_insert_with(|| line.clone());
+            let start_col = styled.column_for_units(start_units);
+            let end_col = styled.column_for_units(end_units);
+            *styled = with_background(styled, start_col, end_col, span.color);
+        }
+        match out {
+            Some(line) => Cow::Owned(line),
+            None => Cow::Borrowed(line),
+        }
+    }
+}
+
+fn with_background(line: &StyledLine, start: usize, end: usize, color: RgbColor) -> StyledLine {
+    if start >= end {
+        return line.clone();
+    }
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut col = 0usize;
+    for seg in &line.segments {
+        let gs: Vec<&str> = seg.text.graphemes(true).collect();
+        let seg_start = col;
+        let seg_end = col + gs.len();
+        col = seg_end;
+        if end <= seg_start || start >= seg_end {
+            segments.push(seg.clone());
+            continue;
+        }
+        let local_start = start.saturating_sub(seg_start);
+        let local_end = (end - seg_start).min(gs.len());
+        let mut push = |text: String, bg: Option<RgbColor>| {
+            if !text.is_empty() {
+                let mut part = seg.clone();
+                part.text = text;
+                part.bg = bg;
+                segments.push(part);
+            }
+        };
+        push(gs[..local_start].concat(), seg.bg);
+        push(gs[local_start..local_end].concat(), Some(color));
+        push(gs[local_end..].concat(), seg.bg);
+    }
+    StyledLine {
+        segments,
+        image: line.image.clone(),
+    }
+}
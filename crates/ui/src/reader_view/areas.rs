@@ -5,6 +5,9 @@ use reader_core::layout::Size;
 use super::ReaderView;
 use super::SPREAD_GAP;
 
+/// Panes of the reading area. `left` shows the current page, or the first
+/// page of a spread, and `right` the second; right-to-left books swap them
+/// on screen.
 pub struct ContentAreas {
     pub body: Rect,
     pub left: Rect,
@@ -55,10 +58,15 @@ impl ReaderView {
                     Constraint::Length(right_w),
                 ])
                 .split(para_area);
+            let (first, second) = if self.right_to_left {
+                (spreads[2], spreads[0])
+            } else {
+                (spreads[0], spreads[2])
+            };
             ContentAreas {
                 body: para_area,
-                left: spreads[0],
-                right: Some(spreads[2]),
+                left: first,
+                right: Some(second),
             }
         } else {
             ContentAreas {
@@ -0,0 +1,167 @@
+use ratatui::{prelude::*, widgets::*};
+use unicode_width::UnicodeWidthStr;
+
+use super::ReaderView;
+
+// Home row first, so most pages need only these
+const HINT_CHARS: &str = "asdfghjkl";
+
+/// A link on screen with the keys that follow it. `col` is the terminal
+/// column it starts at, counting wide characters such as CJK as two.
+#[derive(Clone, Debug)]
+pub struct LinkHint {
+    pub label: String,
+    pub page: usize,
+    pub line: usize,
+    pub col: usize,
+    pub target: String,
+    pub text: String,
+}
+
+/// Hint mode: the labelled links and the keys typed so far.
+pub struct LinkHints {
+    pub hints: Vec<LinkHint>,
+    pub typed: String,
+}
+
+impl LinkHints {
+    pub fn new(hints: Vec<LinkHint>) -> Self {
+        Self {
+            hints,
+            typed: String::new(),
+        }
+    }
+
+    /// Hints whose label starts with the keys typed so far.
+    pub fn candidates(&self) -> impl Iterator<Item = &LinkHint> {
+        self.hints
+            .iter()
+            .filter(|hint| hint.label.starts_with(&self.typed))
+    }
+
+    /// The hint named by the keys typed so far, once they spell out a
+    /// whole label.
+    pub fn chosen(&self) -> Option<&LinkHint> {
+        self.hints.iter().find(|hint| hint.label == self.typed)
+    }
+
+    /// The last candidate left, if only one is.
+    pub fn single(&self) -> Option<&LinkHint> {
+        let mut candidates = self.candidates();
+        let first = candidates.next();
+        candidates.next().is_none().then_some(first).flatten()
+    }
+}
+
+impl ReaderView {
+    /// Hints for every link on the page(s) on screen, in reading order.
+    /// Neighbouring segments of the same link on a line share one hint.
+    pub fn link_hints(&self) -> Vec<LinkHint> {
+        let pages = if self.two_pane {
+            let base = self.current.saturating_sub(self.current % 2);
+            base..base + 2
+        } else {
+            self.current..self.current + 1
+        };
+        let mut hints: Vec<LinkHint> = Vec::new();
+        for page_idx in pages {
+            let Some(page) = self.pages.get(page_idx) else {
+                continue;
+            };
+            for (line_idx, line) in page.lines.iter().enumerate() {
+                let mut col = 0usize;
+                let mut previous: Option<&str> = None;
+                for seg in &line.segments {
+                    let text = Self::segment_display_text(seg);
+                    match seg.link.as_deref() {
+                        Some(target) if previous == Some(target) => {
+                            if let Some(hint) = hints.last_mut() {
+                                hint.text.push_str(&text);
+                            }
+                        }
+                        Some(target) => hints.push(LinkHint {
+                            label: String::new(),
+                            page: page_idx,
+                            line: line_idx,
+                            col,
+                            target: target.to_string(),
+                            text: text.to_string(),
+                        }),
+                        None => {}
+                    }
+                    previous = seg.link.as_deref();
+                    col += text.width();
+                }
+            }
+        }
+        let labels = hint_labels(hints.len());
+        for (hint, label) in hints.iter_mut().zip(labels) {
+            hint.label = label;
+        }
+        hints
+    }
+
+    /// Draw the labels of the hints still matching what was typed over
+    /// the start of their links.
+    pub fn render_link_hints(
+        &self,
+        f: &mut Frame<'_>,
+        area: Rect,
+        column_width: u16,
+        hints: &LinkHints,
+    ) {
+        let areas = self.content_areas(area, column_width);
+        let base = if self.two_pane {
+            self.current.saturating_sub(self.current % 2)
+        } else {
+            self.current
+        };
+        for hint in hints.candidates() {
+            let pane = match (hint.page == base, areas.right) {
+                (true, _) => areas.left,
+                (false, Some(right)) => right,
+                (false, None) => continue,
+            };
+            let x = pane.x + hint.col as u16;
+            let y = pane.y + hint.line as u16;
+            if x >= pane.x + pane.width || y >= pane.y + pane.height {
+                continue;
+            }
+            let rest = &hint.label[hints.typed.len()..];
+            let label = Line::from(vec![
+                Span::styled(
+                    hints.typed.clone(),
+                    Style::default().fg(Color::DarkGray).bg(Color::Yellow),
+                ),
+                Span::styled(
+                    rest.to_string(),
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ]);
+            let width = (hint.label.len() as u16).min(pane.x + pane.width - x);
+            f.render_widget(Paragraph::new(label), Rect::new(x, y, width, 1));
+        }
+    }
+}
+
+/// `count` labels of equal length, so none is a prefix of another.
+pub(super) fn hint_labels(count: usize) -> Vec<String> {
+    let chars: Vec<char> = HINT_CHARS.chars().collect();
+    let mut len = 1;
+    while chars.len().pow(len) < count {
+        len += 1;
+    }
+    (0..count)
+        .map(|mut idx| {
+            let mut label = vec![chars[0]; len as usize];
+            for slot in label.iter_mut().rev() {
+                *slot = chars[idx % chars.len()];
+                idx /= chars.len();
+            }
+            label.into_iter().collect()
+        })
+        .collect()
+}
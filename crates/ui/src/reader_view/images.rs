@@ -47,7 +47,10 @@ impl ReaderView {
 
 #[cfg(feature = "kitty-images")]
 impl ReaderView {
-    pub(super) fn collect_image_placements(&mut self, page_idx: usize, area: Rect) {
+    /// Queue the images on a page shown in `area`. Page images are scaled
+    /// to fit `fit_area` instead, which is the whole screen in fit-to-screen
+    /// mode.
+    pub(super) fn collect_image_placements(&mut self, page_idx: usize, area: Rect, fit_area: Rect) {
         let Some(page) = self.pages.get(page_idx) else {
             return;
         };
@@ -55,6 +58,17 @@ impl ReaderView {
             let Some(image) = &line.image else {
                 continue;
             };
+            if let Some(size) = image.fit {
+                let target = fit_image(fit_area, size, cell_aspect());
+                self.image_placements.push(RenderImage {
+                    id: image.id.clone(),
+                    x: target.x,
+                    y: target.y,
+                    cols: target.width,
+                    rows: target.height,
+                });
+                continue;
+            }
             let y = area.y.saturating_add(line_idx as u16);
             if y >= area.y.saturating_add(area.height) {
                 break;
@@ -77,10 +91,7 @@ impl ReaderView {
         write!(out, "\x1b_Ga=d\x1b\\")?;
         let placements = self.image_placements.clone();
         for placement in placements {
-            let Some(data) = self.image_map.get(&placement.id) else {
-                continue;
-            };
-            let Some(encoded) = self.ensure_png_base64(&placement.id, data.as_slice()) else {
+            let Some(encoded) = self.ensure_png_base64(&placement.id) else {
                 continue;
             };
             queue!(out, MoveTo(placement.x, placement.y))?;
@@ -89,11 +100,11 @@ impl ReaderView {
         out.flush()
     }
 
-    fn ensure_png_base64(&mut self, id: &str, data: &[u8]) -> Option<String> {
+    fn ensure_png_base64(&mut self, id: &str) -> Option<String> {
         if let Some(cached) = self.image_cache.get(id) {
             return Some(cached.png_base64.clone());
         }
-        let png = encode_png(data)?;
+        let png = encode_png(self.image_map.get(id)?)?;
         let encoded = base64::engine::general_purpose::STANDARD.encode(png);
         self.image_cache.insert(
             id.to_string(),
@@ -105,6 +116,18 @@ impl ReaderView {
     }
 }
 
+/// Whether page images are drawn in the terminal; otherwise their first
+/// row names them.
+#[cfg(feature = "kitty-images")]
+pub(super) fn images_shown() -> bool {
+    kitty_supported()
+}
+
+#[cfg(not(feature = "kitty-images"))]
+pub(super) fn images_shown() -> bool {
+    false
+}
+
 #[cfg(feature = "kitty-images")]
 fn kitty_supported() -> bool {
     if env::var("KITTY_WINDOW_ID").is_ok() {
@@ -115,6 +138,41 @@ fn kitty_supported() -> bool {
         .unwrap_or(false)
 }
 
+/// Height of a terminal cell over its width, from the window's pixel size
+/// when the terminal reports it.
+#[cfg(feature = "kitty-images")]
+fn cell_aspect() -> f32 {
+    crossterm::terminal::window_size()
+        .ok()
+        .filter(|size| size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0)
+        .map(|size| {
+            (f32::from(size.height) / f32::from(size.rows))
+                / (f32::from(size.width) / f32::from(size.columns))
+        })
+        .unwrap_or(2.0)
+}
+
+/// The largest cell rectangle inside `area` with the image's aspect ratio,
+/// centred in it.
+#[cfg(feature = "kitty-images")]
+fn fit_image(area: Rect, (width, height): (u32, u32), cell_aspect: f32) -> Rect {
+    let aspect = height.max(1) as f32 / width.max(1) as f32 / cell_aspect;
+    let full_width_rows = (f32::from(area.width) * aspect).round() as u16;
+    let (cols, rows) = if full_width_rows <= area.height {
+        (area.width, full_width_rows)
+    } else {
+        let cols = (f32::from(area.height) / aspect).round() as u16;
+        (cols.min(area.width), area.height)
+    };
+    let (cols, rows) = (cols.max(1), rows.max(1));
+    Rect {
+        x: area.x + area.width.saturating_sub(cols) / 2,
+        y: area.y + area.height.saturating_sub(rows) / 2,
+        width: cols,
+        height: rows,
+    }
+}
+
 #[cfg(feature = "kitty-images")]
 fn encode_png(data: &[u8]) -> Option<Vec<u8>> {
     let image = image::load_from_memory(data).ok()?;
@@ -134,7 +192,7 @@ fn send_kitty_image<W: Write>(
 ) -> std::io::Result<()> {
     let chunk_size = 4096usize;
     let bytes = base64.as_bytes();
-    let total = (bytes.len() + chunk_size - 1) / chunk_size;
+    let total = bytes.len().div_ceil(chunk_size);
     for idx in 0..total {
         let start = idx * chunk_size;
         let end = (start + chunk_size).min(bytes.len());
@@ -1,6 +1,6 @@
 use unicode_segmentation::UnicodeSegmentation;
 
-use reader_core::layout::Size;
+use reader_core::layout::{page_for_position, LayoutOptions, Size, TextPosition};
 use reader_core::types::Block as ReaderBlock;
 
 use super::ReaderView;
@@ -32,17 +32,37 @@ impl ReaderView {
         }
     }
 
+    pub fn layout_options(&self) -> LayoutOptions<'_> {
+        LayoutOptions {
+            justify: self.justify,
+            hyphenator: self.hyphenator.as_ref().filter(|_| self.hyphenate),
+            line_breaking: self.line_breaking,
+        }
+    }
+
     pub fn reflow(&mut self, blocks: &[ReaderBlock], size: Size) {
-        let p = reader_core::layout::paginate_with_justify(blocks, size, self.justify);
+        // Keep the first visible line on screen rather than the page number
+        let anchor = self.current_position();
+        let p = reader_core::layout::paginate_with_options(blocks, size, self.layout_options());
         self.pages = p.pages;
         self.chapter_starts = p.chapter_starts;
+        self.heading_starts = p.heading_starts;
         self.anchors = p.anchors;
+        if let Some(idx) = anchor.and_then(|pos| page_for_position(&self.pages, pos)) {
+            self.current = idx;
+        }
         self.current = self.current.min(self.pages.len().saturating_sub(1));
         if self.two_pane {
             self.current = self.current.saturating_sub(self.current % 2);
         }
     }
 
+    pub fn current_position(&self) -> Option<TextPosition> {
+        self.pages
+            .get(self.current)
+            .and_then(|page| page.first_position())
+    }
+
     pub fn chapter_title(&self, idx: usize) -> String {
         self.chapter_titles
             .get(idx)
@@ -207,13 +227,17 @@ impl ReaderView {
         self.chapter_hrefs.get(idx).map(|s| s.as_str())
     }
 
-    pub(super) fn current_chapter_index(&self) -> Option<usize> {
+    pub fn current_chapter_index(&self) -> Option<usize> {
+        self.chapter_index_for_page(self.current)
+    }
+
+    pub(super) fn chapter_index_for_page(&self, page: usize) -> Option<usize> {
         if self.chapter_starts.is_empty() {
             return None;
         }
         let mut idx = 0usize;
         for (i, start) in self.chapter_starts.iter().enumerate() {
-            if *start <= self.current {
+            if *start <= page {
                 idx = i;
             } else {
                 break;
@@ -1,6 +1,8 @@
 use ratatui::{prelude::*, widgets::*};
+use reader_core::search::Matcher;
 use unicode_segmentation::UnicodeSegmentation;
 
+use super::images::images_shown;
 use super::selection::selection_for_line;
 use super::ReaderView;
 use super::SPREAD_GAP;
@@ -11,7 +13,7 @@ impl ReaderView {
         f: &mut Frame<'_>,
         area: Rect,
         column_width: u16,
-        highlight: Option<&str>,
+        highlight: Option<&Matcher>,
     ) {
         #[cfg(feature = "kitty-images")]
         {
@@ -165,31 +167,86 @@ impl ReaderView {
                 ])
                 .split(para_area);
             let base = self.current.saturating_sub(self.current % 2);
-            let left_lines = self.page_lines(base, highlight);
-            let right_lines = self.page_lines(base + 1, highlight);
+            // Right-to-left books read the first page of a spread on the right
+            let (first, second) = if self.right_to_left {
+                (spreads[2], spreads[0])
+            } else {
+                (spreads[0], spreads[2])
+            };
+            let first_lines = self.page_lines(base, highlight);
+            let second_lines = self.page_lines(base + 1, highlight);
             #[cfg(feature = "kitty-images")]
             {
-                self.collect_image_placements(base, spreads[0]);
-                self.collect_image_placements(base + 1, spreads[2]);
+                let (first_fit, second_fit) = if self.fit_screen {
+                    // Page images split the whole width between them
+                    let screen = Rect {
+                        x: content_area.x,
+                        width: content_area.width,
+                        ..para_area
+                    };
+                    let half = screen.width.saturating_sub(gap) / 2;
+                    let left = Rect {
+                        width: half,
+                        ..screen
+                    };
+                    let right = Rect {
+                        x: screen.x + screen.width - half,
+                        ..left
+                    };
+                    if self.right_to_left {
+                        (right, left)
+                    } else {
+                        (left, right)
+                    }
+                } else {
+                    (first, second)
+                };
+                self.collect_image_placements(base, first, first_fit);
+                self.collect_image_placements(base + 1, second, second_fit);
             }
-            let left_p = Paragraph::new(left_lines).wrap(Wrap { trim: false });
-            let right_p = Paragraph::new(right_lines).wrap(Wrap { trim: false });
-            f.render_widget(left_p, spreads[0]);
-            f.render_widget(right_p, spreads[2]);
+            let first_p = Paragraph::new(first_lines).wrap(Wrap { trim: false });
+            let second_p = Paragraph::new(second_lines).wrap(Wrap { trim: false });
+            f.render_widget(first_p, first);
+            f.render_widget(second_p, second);
+            // Note markers sit in the outer margin of their pane
+            let left_margin = spreads[0].x.checked_sub(2);
+            let right_margin = Some(spreads[2].x + spreads[2].width + 1);
+            let (first_margin, second_margin) = if self.right_to_left {
+                (right_margin, left_margin)
+            } else {
+                (left_margin, right_margin)
+            };
+            self.render_note_markers(f, base, first, first_margin, area);
+            self.render_note_markers(f, base + 1, second, second_margin, area);
         } else {
             let lines = self.page_lines(self.current, highlight);
             #[cfg(feature = "kitty-images")]
             {
-                self.collect_image_placements(self.current, para_area);
+                let fit = if self.fit_screen {
+                    Rect {
+                        x: content_area.x,
+                        width: content_area.width,
+                        ..para_area
+                    }
+                } else {
+                    para_area
+                };
+                self.collect_image_placements(self.current, para_area, fit);
             }
             let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
             f.render_widget(paragraph, para_area);
+            let margin = para_area.x.checked_sub(2);
+            self.render_note_markers(f, self.current, para_area, margin, area);
         }
 
         // Footer: powerline segments left author, right title
         let mut author = self.author.clone().unwrap_or_default();
         let mut title = self.book_title.clone().unwrap_or_default();
-        let total_width = body_width;
+        let hits_label = self.search_hits_label().map(|label| format!(" {} ", label));
+        let hits_len = hits_label
+            .as_ref()
+            .map_or(0, |label| label.graphemes(true).count());
+        let total_width = body_width.saturating_sub(hits_len);
         let mut footer_line = Line::default();
         let mut left_seg_len = author.graphemes(true).count();
         let mut right_seg_len = title.graphemes(true).count();
@@ -253,6 +310,14 @@ impl ReaderView {
                 Style::default().bg(self.theme.footer_pad_bg),
             ));
         }
+        if let Some(label) = hits_label {
+            footer_line.push_span(Span::styled(
+                label,
+                Style::default()
+                    .bg(self.theme.footer_bg)
+                    .fg(self.theme.footer_fg),
+            ));
+        }
         if !title.is_empty() {
             footer_line.push_span(Span::styled(
                 title,
@@ -265,16 +330,52 @@ impl ReaderView {
         f.render_widget(footer, header_footer_chunks[2]);
     }
 
-    fn page_lines(&self, idx: usize, highlight: Option<&str>) -> Vec<ratatui::text::Line<'static>> {
+    fn render_note_markers(
+        &self,
+        f: &mut Frame<'_>,
+        page_idx: usize,
+        pane: Rect,
+        margin_x: Option<u16>,
+        area: Rect,
+    ) {
+        let Some(x) = margin_x.filter(|x| *x >= area.x && *x < area.x + area.width) else {
+            return;
+        };
+        for row in self.note_rows(page_idx) {
+            let y = pane.y + row as u16;
+            if y >= pane.y + pane.height {
+                continue;
+            }
+            let marker = Paragraph::new(Span::styled("✎", Style::default().fg(Color::Yellow)));
+            f.render_widget(marker, Rect::new(x, y, 1, 1));
+        }
+    }
+
+    pub(super) fn page_lines(
+        &self,
+        idx: usize,
+        highlight: Option<&Matcher>,
+    ) -> Vec<ratatui::text::Line<'static>> {
         if let Some(page) = self.pages.get(idx) {
+            let matches = highlight
+                .map(|matcher| self.page_matches(idx, matcher))
+                .unwrap_or_default();
+            let images_shown = images_shown();
             page.lines
                 .iter()
                 .enumerate()
                 .map(|(line_idx, l)| {
+                    // Without terminal graphics a page image shows its name
+                    let placeholder = l.image.as_ref().filter(|image| image.fit.is_some());
+                    if let Some(image) = placeholder.filter(|_| !images_shown) {
+                        return ratatui::text::Line::from(format!("[page: {}]", image.id));
+                    }
                     let sel = self
                         .selection
                         .and_then(|selection| selection_for_line(selection, idx, line_idx, l));
-                    Self::highlight_line(l, highlight, sel)
+                    let styled = self.apply_highlights(idx, line_idx, l);
+                    let line_matches = matches.get(line_idx).map_or(&[][..], Vec::as_slice);
+                    Self::highlight_line(&styled, line_matches, sel)
                 })
                 .collect()
         } else {
@@ -1,39 +1,155 @@
-use reader_core::layout::Page;
+use std::ops::Range;
+
+use reader_core::layout::{Page, TextPosition};
+use reader_core::search::{Matcher, PageText};
+
+use crate::search_view::SearchMatch;
 
 use super::ReaderView;
 
 impl ReaderView {
-    pub fn search_forward(&self, query: &str, start_from: Option<usize>) -> Option<usize> {
-        let needle = query.trim();
-        if needle.is_empty() || self.pages.is_empty() {
+    pub fn search_forward(&self, matcher: &Matcher, start_from: Option<usize>) -> Option<usize> {
+        if self.pages.is_empty() {
             return None;
         }
-        let needle = needle.to_lowercase();
         let total = self.pages.len();
-        let start_raw = start_from.unwrap_or(self.current);
-        let start = if total == 0 { 0 } else { start_raw % total };
-        for offset in 0..total {
-            let idx = (start + offset) % total;
-            if Self::page_contains(&self.pages[idx], &needle) {
-                return Some(idx);
+        let start = start_from.unwrap_or(self.current) % total;
+        (0..total)
+            .map(|offset| (start + offset) % total)
+            .find(|idx| Self::page_contains(&self.pages[*idx], matcher))
+    }
+
+    /// Every match on the loaded pages, in reading order, with the line it
+    /// starts on as a highlighted snippet.
+    pub fn find_all(&self, matcher: &Matcher) -> Vec<SearchMatch> {
+        let mut found = Vec::new();
+        let mut last_position = TextPosition::default();
+        for (page_idx, page) in self.pages.iter().enumerate() {
+            let text = PageText::new(page);
+            let matches = matcher.find_iter(&text.text);
+            let per_line = Self::matches_by_line(page, &text, &matches);
+            for range in matches {
+                let line = text.line_at(range.start);
+                // Lines without a position of their own take the one above
+                let position = page
+                    .positions
+                    .iter()
+                    .take(line + 1)
+                    .rev()
+                    .flatten()
+                    .next()
+                    .copied()
+                    .or_else(|| page.first_position())
+                    .unwrap_or(last_position);
+                let chapter = self
+                    .chapter_index_for_page(page_idx)
+                    .map(|idx| self.chapter_label(idx))
+                    .unwrap_or_default();
+                found.push(SearchMatch {
+                    page: page_idx,
+                    position,
+                    chapter,
+                    snippet: Self::highlight_line(&page.lines[line], &per_line[line], None),
+                });
+            }
+            if let Some(position) = page.positions.iter().rev().flatten().next() {
+                last_position = *position;
             }
         }
-        None
+        found
     }
 
-    fn page_contains(page: &Page, needle: &str) -> bool {
-        if needle.is_empty() {
-            return false;
-        }
-        let mut buf = String::new();
-        for (i, line) in page.lines.iter().enumerate() {
-            if i > 0 {
-                buf.push(' ');
-            }
-            for seg in &line.segments {
-                buf.push_str(&seg.text.to_lowercase());
+    /// Matches of `matcher` on page `idx`, split per line; a match that
+    /// continues on the next line is highlighted on both.
+    pub(super) fn page_matches(&self, idx: usize, matcher: &Matcher) -> Vec<Vec<Range<usize>>> {
+        let Some(page) = self.pages.get(idx) else {
+            return Vec::new();
+        };
+        let text = PageText::new(page);
+        let matches = matcher.find_iter(&text.text);
+        Self::matches_by_line(page, &text, &matches)
+    }
+
+    fn matches_by_line(
+        page: &Page,
+        text: &PageText,
+        matches: &[Range<usize>],
+    ) -> Vec<Vec<Range<usize>>> {
+        let mut per_line = vec![Vec::new(); page.lines.len()];
+        for range in matches {
+            for (line, part) in text.line_ranges(range.clone()) {
+                per_line[line].push(part);
             }
         }
-        buf.contains(needle)
+        per_line
+    }
+
+    /// Index range in `search_hits` of the matches on the page or spread
+    /// on screen.
+    fn hits_on_screen(&self) -> Range<usize> {
+        let (base, shown) = if self.two_pane {
+            (self.current.saturating_sub(self.current % 2), 2)
+        } else {
+            (self.current, 1)
+        };
+        let Some(start) = self.pages.get(base).and_then(|p| p.first_position()) else {
+            return 0..0;
+        };
+        let before = self.search_hits.partition_point(|pos| *pos < start);
+        let end = self
+            .pages
+            .get(base + shown)
+            .and_then(|p| p.first_position())
+            .map_or(self.search_hits.len(), |end| {
+                self.search_hits.partition_point(|pos| *pos < end)
+            });
+        before..end.max(before)
+    }
+
+    /// The match `count` times `n` (`forward`) or `N` goes to: counting
+    /// from the match last stepped to while it is on screen, otherwise from
+    /// the first match from the top of the screen on, or the last one above
+    /// it. The flag is set when that wrapped around the loaded text.
+    pub fn next_search_hit(&self, forward: bool, count: usize) -> Option<(usize, bool)> {
+        let total = self.search_hits.len();
+        if total == 0 {
+            return None;
+        }
+        let count = count.max(1);
+        let on_screen = self.hits_on_screen();
+        let current = self.search_hit.filter(|idx| on_screen.contains(idx));
+        if forward {
+            let next = match current {
+                Some(idx) => idx.saturating_add(count),
+                None => on_screen.start.saturating_add(count - 1),
+            };
+            Some((next % total, next >= total))
+        } else {
+            let from = current.unwrap_or(on_screen.start);
+            let back = count % total;
+            Some(((from + total - back) % total, count > from))
+        }
+    }
+
+    /// Footer label for the last search: "match i/N" for the match stepped
+    /// to, or the first one on screen, otherwise the number of matches.
+    pub(super) fn search_hits_label(&self) -> Option<String> {
+        let total = self.search_hits.len();
+        if total == 0 {
+            return None;
+        }
+        let on_screen = self.hits_on_screen();
+        let shown = self
+            .search_hit
+            .filter(|idx| on_screen.contains(idx))
+            .or_else(|| (!on_screen.is_empty()).then_some(on_screen.start));
+        Some(match shown {
+            Some(idx) => format!("match {}/{}", idx + 1, total),
+            None => format!("{} {}", total, if total == 1 { "match" } else { "matches" }),
+        })
+    }
+
+    fn page_contains(page: &Page, matcher: &Matcher) -> bool {
+        matcher.is_match(&PageText::new(page).text)
     }
 }
@@ -1,11 +1,16 @@
 use ratatui::style::Color;
 
-use reader_core::layout::{Page, Segment, StyledLine, TextStyle};
+use std::ops::Range;
 
-use super::ReaderView;
+use reader_core::layout::{Page, Segment, Size, StyledLine, TextPosition, TextStyle};
+use reader_core::search::{Matcher, SearchOptions};
+use reader_core::types::{Block as ReaderBlock, ImageBlock, RgbColor};
+
+use super::{HighlightSpan, ReaderView};
 
 fn page(lines: &[&str]) -> Page {
     Page {
+        positions: vec![None; lines.len()],
         lines: lines
             .iter()
             .map(|s| StyledLine {
@@ -22,6 +27,15 @@ fn page(lines: &[&str]) -> Page {
     }
 }
 
+fn matcher(query: &str) -> Matcher {
+    Matcher::new(query, SearchOptions::default()).unwrap()
+}
+
+fn line_matches(line: &StyledLine, query: &str) -> Vec<Range<usize>> {
+    let text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+    matcher(query).find_iter(&text)
+}
+
 #[test]
 fn search_forward_is_case_insensitive() {
     let mut view = ReaderView::new();
@@ -30,8 +44,8 @@ fn search_forward_is_case_insensitive() {
         page(&["Second Match"]),
         page(&["Third"]),
     ];
-    assert_eq!(view.search_forward("match", None), Some(1));
-    assert_eq!(view.search_forward("SeCoNd", None), Some(1));
+    assert_eq!(view.search_forward(&matcher("match"), None), Some(1));
+    assert_eq!(view.search_forward(&matcher("SeCoNd"), None), Some(1));
 }
 
 #[test]
@@ -39,14 +53,14 @@ fn search_forward_wraps_from_end() {
     let mut view = ReaderView::new();
     view.pages = vec![page(&["Alpha"]), page(&["Beta"]), page(&["Gamma"])];
     view.current = 2;
-    assert_eq!(view.search_forward("alpha", None), Some(0));
+    assert_eq!(view.search_forward(&matcher("alpha"), None), Some(0));
 }
 
 #[test]
 fn search_forward_matches_across_lines() {
     let mut view = ReaderView::new();
     view.pages = vec![page(&["Hello brave", "new world"]), page(&["Unused"])];
-    assert_eq!(view.search_forward("brave new", None), Some(0));
+    assert_eq!(view.search_forward(&matcher("brave new"), None), Some(0));
 }
 
 #[test]
@@ -58,11 +72,11 @@ fn search_forward_can_start_after_previous_hit() {
         page(&["Red fish"]),
         page(&["Blue fish"]),
     ];
-    assert_eq!(view.search_forward("fish", None), Some(0));
-    assert_eq!(view.search_forward("fish", Some(1)), Some(1));
-    assert_eq!(view.search_forward("fish", Some(2)), Some(2));
-    assert_eq!(view.search_forward("fish", Some(3)), Some(3));
-    assert_eq!(view.search_forward("fish", Some(4)), Some(0)); // wraps
+    assert_eq!(view.search_forward(&matcher("fish"), None), Some(0));
+    assert_eq!(view.search_forward(&matcher("fish"), Some(1)), Some(1));
+    assert_eq!(view.search_forward(&matcher("fish"), Some(2)), Some(2));
+    assert_eq!(view.search_forward(&matcher("fish"), Some(3)), Some(3));
+    assert_eq!(view.search_forward(&matcher("fish"), Some(4)), Some(0)); // wraps
 }
 
 #[test]
@@ -77,7 +91,7 @@ fn highlight_line_marks_case_insensitive_matches() {
         }],
         image: None,
     };
-    let line = ReaderView::highlight_line(&styled, Some("world"), None);
+    let line = ReaderView::highlight_line(&styled, &line_matches(&styled, "world"), None);
     assert_eq!(line.spans.len(), 2);
     assert_eq!(line.spans[0].content, "Hello ");
     assert_eq!(line.spans[1].content, "World");
@@ -96,10 +110,274 @@ fn highlight_line_marks_multiple_occurrences() {
         }],
         image: None,
     };
-    let line = ReaderView::highlight_line(&styled, Some("ba"), None);
+    let line = ReaderView::highlight_line(&styled, &line_matches(&styled, "ba"), None);
     assert_eq!(line.spans.len(), 4); // "a" + "ba" + " " + "ba"
     assert_eq!(line.spans[1].content, "ba");
     assert_eq!(line.spans[1].style.bg, Some(Color::Yellow));
     assert_eq!(line.spans[3].content, "ba");
     assert_eq!(line.spans[3].style.bg, Some(Color::Yellow));
 }
+
+#[test]
+fn reflow_keeps_reading_position() {
+    let blocks: Vec<ReaderBlock> = (0..20)
+        .map(|i| ReaderBlock::Paragraph(format!("Paragraph {i} has several words to wrap around.")))
+        .collect();
+    let mut view = ReaderView::new();
+    view.reflow(
+        &blocks,
+        Size {
+            width: 60,
+            height: 6,
+        },
+    );
+    view.current = 5;
+    let before = view.current_position().unwrap();
+    view.reflow(
+        &blocks,
+        Size {
+            width: 20,
+            height: 6,
+        },
+    );
+    let after = view.current_position().unwrap();
+    assert!(after <= before);
+    let next = view.pages[view.current + 1].first_position().unwrap();
+    assert!(next > before);
+}
+
+#[test]
+fn page_images_are_named_without_terminal_graphics() {
+    let blocks = vec![ReaderBlock::Image(ImageBlock::full_page(
+        "vol/page1.png",
+        vec![0],
+        Some(600),
+        Some(900),
+    ))];
+    let mut view = ReaderView::new();
+    view.reflow(
+        &blocks,
+        Size {
+            width: 30,
+            height: 6,
+        },
+    );
+    let lines = view.page_lines(0, None);
+    assert_eq!(lines.len(), 6);
+    if !super::images::images_shown() {
+        assert_eq!(lines[0].to_string(), "[page: vol/page1.png]");
+    }
+}
+
+#[test]
+fn highlights_follow_text_across_reflow() {
+    let blocks = vec![ReaderBlock::Paragraph(
+        "alpha beta gamma delta epsilon zeta eta theta".into(),
+    )];
+    let mut view = ReaderView::new();
+    for width in [60, 8] {
+        view.reflow(&blocks, Size { width, height: 10 });
+        // "gamma delta" starts after "alpha beta " (11 units) and spans 11 units
+        view.highlights = vec![HighlightSpan {
+            start: TextPosition {
+                block: 0,
+                offset: 11,
+            },
+            end: TextPosition {
+                block: 0,
+                offset: 22,
+            },
+            color: RgbColor::new(1, 2, 3),
+            has_note: false,
+            annotation: 0,
+        }];
+        let mut marked = String::new();
+        for (idx, line) in view.pages[0].lines.iter().enumerate() {
+            let styled = view.apply_highlights(0, idx, line);
+            let text: String = styled
+                .segments
+                .iter()
+                .filter(|seg| seg.bg.is_some())
+                .map(|seg| seg.text.as_str())
+                .collect();
+            if !text.is_empty() {
+                marked.push_str(&text);
+                marked.push('|');
+            }
+        }
+        let expected = if width == 60 {
+            "gamma delta|"
+        } else {
+            "gamma|delta|"
+        };
+        assert_eq!(marked, expected);
+    }
+}
+
+// A page of block `block`, one line per 10 units
+fn positioned_page(block: usize, lines: &[&str]) -> Page {
+    let mut page = page(lines);
+    page.positions = (0..lines.len())
+        .map(|i| {
+            Some(TextPosition {
+                block,
+                offset: i * 10,
+            })
+        })
+        .collect();
+    page
+}
+
+#[test]
+fn find_all_lists_every_match_with_its_line() {
+    let mut view = ReaderView::new();
+    view.pages = vec![
+        positioned_page(0, &["One fish", "two fish"]),
+        positioned_page(1, &["No match"]),
+        positioned_page(2, &["Red FISH"]),
+    ];
+    let hits = view.find_all(&matcher("fish"));
+    assert_eq!(
+        hits.iter().map(|hit| hit.page).collect::<Vec<_>>(),
+        vec![0, 0, 2]
+    );
+    assert_eq!(hits[1].snippet.spans[1].content, "fish");
+    assert!(hits[0].position < hits[1].position);
+
+    view.search_hits = hits.iter().map(|hit| hit.position).collect();
+    assert_eq!(view.search_hits_label().as_deref(), Some("match 1/3"));
+    view.current = 1;
+    assert_eq!(view.search_hits_label().as_deref(), Some("3 matches"));
+    view.current = 2;
+    assert_eq!(view.search_hits_label().as_deref(), Some("match 3/3"));
+}
+
+#[test]
+fn n_steps_through_each_match_on_a_page() {
+    let mut view = ReaderView::new();
+    view.pages = vec![
+        positioned_page(0, &["One fish", "two fish"]),
+        positioned_page(1, &["No match"]),
+        positioned_page(2, &["Red FISH"]),
+    ];
+    view.search_hits = view
+        .find_all(&matcher("fish"))
+        .iter()
+        .map(|hit| hit.position)
+        .collect();
+    assert_eq!(view.next_search_hit(true, 1), Some((0, false)));
+    view.search_hit = Some(0);
+    assert_eq!(view.next_search_hit(true, 1), Some((1, false)));
+    view.search_hit = Some(1);
+    assert_eq!(view.search_hits_label().as_deref(), Some("match 2/3"));
+    assert_eq!(view.next_search_hit(false, 1), Some((0, false)));
+    view.current = 2;
+    view.search_hit = Some(2);
+    assert_eq!(view.next_search_hit(true, 1), Some((0, true)));
+    // Away from the last match, N goes to the one above the screen
+    view.current = 1;
+    assert_eq!(view.next_search_hit(false, 1), Some((1, false)));
+    // A count goes straight to the nth match
+    view.current = 0;
+    view.search_hit = Some(0);
+    assert_eq!(view.next_search_hit(true, 2), Some((2, false)));
+    assert_eq!(view.next_search_hit(true, 99_999), Some((0, true)));
+    assert_eq!(view.next_search_hit(false, 2), Some((1, true)));
+}
+
+#[test]
+fn find_all_counts_matches_on_lines_without_a_position() {
+    let mut view = ReaderView::new();
+    let mut loose = positioned_page(3, &["Fish heading", "loose fish", "fish"]);
+    loose.positions[1] = None;
+    loose.positions[0] = None;
+    view.pages = vec![positioned_page(2, &["A fish"]), loose];
+    let hits = view.find_all(&matcher("fish"));
+    assert_eq!(hits.len(), 4);
+    let positions: Vec<_> = hits.iter().map(|hit| hit.position).collect();
+    assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn search_modes_match_across_hyphenation_breaks() {
+    let mut view = ReaderView::new();
+    // "incompre-" wraps a 17-unit word; the hyphen is not in the text
+    let mut hyphenated = page(&["An incompre-", "hensible café"]);
+    hyphenated.positions = vec![
+        Some(TextPosition {
+            block: 0,
+            offset: 0,
+        }),
+        Some(TextPosition {
+            block: 0,
+            offset: 11,
+        }),
+    ];
+    view.pages = vec![page(&["Nothing here"]), hyphenated];
+    assert_eq!(
+        view.search_forward(&matcher("incomprehensible"), None),
+        Some(1)
+    );
+    assert_eq!(view.search_forward(&matcher("cafe"), None), None);
+
+    let accents = SearchOptions {
+        ignore_diacritics: true,
+        whole_word: true,
+        ..SearchOptions::default()
+    };
+    let cafe = Matcher::new("cafe", accents).unwrap();
+    assert_eq!(view.search_forward(&cafe, None), Some(1));
+    let matches = view.page_matches(1, &cafe);
+    assert_eq!(matches[1], vec![9..14]);
+
+    let regex = SearchOptions {
+        regex: true,
+        ..SearchOptions::default()
+    };
+    let pattern = Matcher::new(r"in\w+ble", regex).unwrap();
+    let matches = view.page_matches(1, &pattern);
+    assert_eq!(matches[0], vec![3..11]);
+    assert_eq!(matches[1], vec![0..8]);
+}
+
+#[test]
+fn link_hints_label_each_link_on_screen() {
+    let segment = |text: &str, link: Option<&str>| Segment {
+        text: text.to_string(),
+        fg: None,
+        bg: None,
+        style: TextStyle::default(),
+        link: link.map(str::to_string),
+    };
+    let mut linked = page(&[""]);
+    linked.lines[0].segments = vec![
+        segment("See ", None),
+        segment("chapter ", Some("ch2.xhtml")),
+        segment("two", Some("ch2.xhtml")),
+        segment(" and note", None),
+        segment("[1]", Some("notes.xhtml#fn1")),
+    ];
+    let mut view = ReaderView::new();
+    view.pages = vec![linked, page(&["No links"])];
+    let hints = view.link_hints();
+    assert_eq!(hints.len(), 2);
+    assert_eq!((hints[0].label.as_str(), hints[0].col), ("a", 4));
+    assert_eq!(hints[0].text, "chapter two");
+    assert_eq!((hints[1].label.as_str(), hints[1].col), ("s", 24));
+    assert_eq!(hints[1].target, "notes.xhtml#fn1");
+
+    view.current = 1;
+    assert!(view.link_hints().is_empty());
+
+    // Wide characters take two columns each
+    let mut wide = page(&[""]);
+    wide.lines[0].segments = vec![segment("参见", None), segment("第二章", Some("ch2.xhtml"))];
+    view.pages = vec![wide];
+    view.current = 0;
+    assert_eq!(view.link_hints()[0].col, 4);
+
+    let labels = super::hints::hint_labels(12);
+    assert!(labels.iter().all(|label| label.len() == 2));
+    assert_eq!(labels[0], "aa");
+    assert_eq!(labels[9], "sa");
+}
@@ -1,71 +1,51 @@
 use std::borrow::Cow;
+use std::ops::Range;
 
 use ratatui::prelude::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use unicode_segmentation::UnicodeSegmentation;
 
 use reader_core::layout::{Segment, StyledLine};
 
 use super::ReaderView;
 
 impl ReaderView {
+    /// Style `line` with search matches, given as byte ranges into the
+    /// concatenated text of its segments, or with a selection.
     pub(super) fn highlight_line(
         line: &StyledLine,
-        highlight: Option<&str>,
+        matches: &[Range<usize>],
         selection: Option<(usize, usize)>,
     ) -> Line<'static> {
         if let Some((start, end)) = selection {
             return Self::selection_line(line, start, end);
         }
-        let needle = highlight
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .unwrap_or("");
+        let match_style = Style::default().bg(Color::Yellow).fg(Color::Black);
         let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut seg_start = 0;
         for seg in &line.segments {
             let base_style = Self::segment_style(seg);
+            // Small caps only change ASCII case, so byte offsets still line up
             let seg_text = Self::segment_display_text(seg);
-            let segment_spans = Self::highlight_text(seg_text.as_ref(), needle, base_style);
-            spans.extend(segment_spans);
-        }
-        Line::from(spans)
-    }
-
-    fn highlight_text(text: &str, needle: &str, base_style: Style) -> Vec<Span<'static>> {
-        if needle.is_empty() {
-            return vec![Span::styled(text.to_string(), base_style)];
-        }
-        let needle_g: Vec<String> = needle.graphemes(true).map(|g| g.to_lowercase()).collect();
-        let mut spans: Vec<Span<'static>> = Vec::new();
-        let line_g: Vec<&str> = text.graphemes(true).collect();
-        let mut start = 0;
-        let mut i = 0;
-        while i + needle_g.len() <= line_g.len() {
-            let window = &line_g[i..i + needle_g.len()];
-            let matches = window
-                .iter()
-                .zip(needle_g.iter())
-                .all(|(a, b)| a.to_lowercase() == *b);
-            if matches {
-                if start < i {
-                    let plain = line_g[start..i].concat();
-                    spans.push(Span::styled(plain, base_style));
+            let seg_end = seg_start + seg_text.len();
+            let mut at = 0;
+            for range in matches {
+                let from = range.start.clamp(seg_start, seg_end) - seg_start;
+                let to = range.end.clamp(seg_start, seg_end) - seg_start;
+                if from >= to || from < at {
+                    continue;
                 }
-                let matched = window.concat();
-                spans.push(Span::styled(
-                    matched,
-                    Style::default().bg(Color::Yellow).fg(Color::Black),
-                ));
-                i += needle_g.len();
-                start = i;
-            } else {
-                i += 1;
+                if at < from {
+                    spans.push(Span::styled(seg_text[at..from].to_string(), base_style));
+                }
+                spans.push(Span::styled(seg_text[from..to].to_string(), match_style));
+                at = to;
             }
+            if at < seg_text.len() || seg_text.is_empty() {
+                spans.push(Span::styled(seg_text[at..].to_string(), base_style));
+            }
+            seg_start = seg_end;
         }
-        if start < line_g.len() {
-            spans.push(Span::styled(line_g[start..].concat(), base_style));
-        }
-        spans
+        Line::from(spans)
     }
 
     pub(super) fn segment_style(seg: &Segment) -> Style {
@@ -7,7 +7,7 @@ const TN_BG_STRONG: Color = Color::Rgb(65, 72, 104); // #414868
 const TN_FG: Color = Color::Rgb(192, 202, 245); // #c0caf5
 const TN_BLUE: Color = Color::Rgb(122, 162, 247); // #7aa2f7
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Theme {
     pub header_bg: Color,
     pub header_fg: Color,
@@ -29,3 +29,112 @@ impl Default for Theme {
         }
     }
 }
+
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+    ("gray", Color::DarkGray),
+    ("darkgray", Color::DarkGray),
+];
+
+impl Theme {
+    /// Names accepted by `preset`.
+    pub const PRESETS: &'static [&'static str] = &["default", "gruvbox", "dracula", "tokyonight"];
+
+    /// The colors that `[theme]` in config.toml and `:set` can change.
+    pub const FIELDS: &'static [&'static str] = &[
+        "header_bg",
+        "header_fg",
+        "header_pad_bg",
+        "footer_bg",
+        "footer_fg",
+        "footer_pad_bg",
+    ];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let (header_bg, header_fg, footer_bg, footer_fg) = match name.to_lowercase().as_str() {
+            "default" => return Some(Self::default()),
+            "gruvbox" => (Color::Yellow, Color::Black, Color::Green, Color::Black),
+            "dracula" => (Color::Magenta, Color::White, Color::Blue, Color::White),
+            "tokyonight" => (Color::Blue, Color::White, Color::Cyan, Color::Black),
+            _ => return None,
+        };
+        Some(Theme {
+            header_bg,
+            header_fg,
+            header_pad_bg: Color::DarkGray,
+            footer_bg,
+            footer_fg,
+            footer_pad_bg: Color::DarkGray,
+        })
+    }
+
+    pub fn color(&self, field: &str) -> Option<Color> {
+        Some(match field {
+            "header_bg" => self.header_bg,
+            "header_fg" => self.header_fg,
+            "header_pad_bg" => self.header_pad_bg,
+            "footer_bg" => self.footer_bg,
+            "footer_fg" => self.footer_fg,
+            "footer_pad_bg" => self.footer_pad_bg,
+            _ => return None,
+        })
+    }
+
+    /// Set one of `FIELDS`; false for any other name.
+    pub fn set_color(&mut self, field: &str, color: Color) -> bool {
+        let slot = match field {
+            "header_bg" => &mut self.header_bg,
+            "header_fg" => &mut self.header_fg,
+            "header_pad_bg" => &mut self.header_pad_bg,
+            "footer_bg" => &mut self.footer_bg,
+            "footer_fg" => &mut self.footer_fg,
+            "footer_pad_bg" => &mut self.footer_pad_bg,
+            _ => return false,
+        };
+        *slot = color;
+        true
+    }
+}
+
+/// A color by name (`yellow`, `darkgray`, ...) or as `#rrggbb`.
+pub fn parse_color(name: &str) -> Option<Color> {
+    let name = name.trim().to_lowercase();
+    if let Some(hex) = name.strip_prefix('#').filter(|hex| hex.len() == 6) {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        return Some(Color::Rgb(
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ));
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, color)| *color)
+}
+
+/// The name `parse_color` reads back as `color`.
+pub fn color_name(color: Color) -> String {
+    if let Color::Rgb(r, g, b) = color {
+        return format!("#{:02x}{:02x}{:02x}", r, g, b);
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(_, known)| *known == color)
+        .map_or_else(
+            || format!("{:?}", color).to_lowercase(),
+            |(name, _)| name.to_string(),
+        )
+}
+
+/// Color names offered when completing `:set`.
+pub fn color_names() -> impl Iterator<Item = &'static str> {
+    NAMED_COLORS.iter().map(|(name, _)| *name)
+}
@@ -1,20 +1,26 @@
 use std::collections::HashMap;
 
-use reader_core::layout::Page;
+use reader_core::layout::{Hyphenator, LineBreaking, Page, TextPosition};
 use reader_core::pdf::OutlineEntry;
 
 #[cfg(feature = "kitty-images")]
 use super::images::{KittyImage, RenderImage};
-use super::SelectionRange;
 use super::Theme;
+use super::{HighlightSpan, SelectionRange};
 
 pub struct ReaderView {
     pub pages: Vec<Page>,
     pub current: usize,
     pub last_key: Option<String>,
     pub justify: bool,
+    pub hyphenate: bool,
+    pub hyphenator: Option<Hyphenator>, // dictionary for the book's language
+    pub line_breaking: LineBreaking,
     pub two_pane: bool,
+    pub fit_screen: bool, // page images fill the screen instead of the text column
+    pub right_to_left: bool, // spreads read right to left, as in manga
     pub chapter_starts: Vec<usize>,
+    pub heading_starts: Vec<usize>,
     pub chapter_titles: Vec<String>,
     pub chapter_hrefs: Vec<String>,
     pub anchors: HashMap<String, usize>,
@@ -25,6 +31,9 @@ pub struct ReaderView {
     pub total_chapters: Option<usize>,
     pub toc_overrides: Vec<OutlineEntry>,
     pub selection: Option<SelectionRange>,
+    pub highlights: Vec<HighlightSpan>,
+    pub search_hits: Vec<TextPosition>, // line of each match of the last search, in order
+    pub search_hit: Option<usize>,      // index in search_hits of the match n/N went to
     pub image_map: HashMap<String, Vec<u8>>,
     #[cfg(feature = "kitty-images")]
     pub(super) image_cache: HashMap<String, KittyImage>,
@@ -45,8 +54,14 @@ impl ReaderView {
             current: 0,
             last_key: None,
             justify: false,
+            hyphenate: false,
+            hyphenator: None,
+            line_breaking: LineBreaking::default(),
             two_pane: false,
+            fit_screen: true,
+            right_to_left: false,
             chapter_starts: Vec::new(),
+            heading_starts: Vec::new(),
             chapter_titles: Vec::new(),
             chapter_hrefs: Vec::new(),
             anchors: HashMap::new(),
@@ -57,6 +72,9 @@ impl ReaderView {
             total_chapters: None,
             toc_overrides: Vec::new(),
             selection: None,
+            highlights: Vec::new(),
+            search_hits: Vec::new(),
+            search_hit: None,
             image_map: HashMap::new(),
             #[cfg(feature = "kitty-images")]
             image_cache: HashMap::new(),
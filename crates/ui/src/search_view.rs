@@ -1,8 +1,27 @@
 use ratatui::{prelude::*, widgets::*};
+use reader_core::layout::TextPosition;
+use reader_core::search::SearchOptions;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::layout::centered_rect;
+
+/// One match on the loaded pages. `position` is the start of the line it
+/// is on, which stays valid when the text reflows; `page` does not.
+pub struct SearchMatch {
+    pub page: usize,
+    pub position: TextPosition,
+    pub chapter: String,
+    pub snippet: Line<'static>,
+}
+
+/// The search prompt with its modes, and once submitted, the list of
+/// matches.
 pub struct SearchView {
     pub query: String,
+    pub options: SearchOptions,
+    pub error: Option<String>, // why the last submitted query was rejected
+    pub results: Option<Vec<SearchMatch>>,
+    pub selected: usize,
 }
 
 impl Default for SearchView {
@@ -13,17 +32,39 @@ impl Default for SearchView {
 
 impl SearchView {
     pub fn new() -> Self {
-        Self {
-            query: String::new(),
-        }
+        Self::with_query("")
     }
 
     pub fn with_query(query: &str) -> Self {
         Self {
             query: query.to_string(),
+            options: SearchOptions::default(),
+            error: None,
+            results: None,
+            selected: 0,
         }
     }
 
+    pub fn show_results(&mut self, results: Vec<SearchMatch>, selected: usize) {
+        self.selected = selected.min(results.len().saturating_sub(1));
+        self.results = Some(results);
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        let count = self.results.as_ref().map_or(0, Vec::len);
+        if count > 0 {
+            self.selected = (self.selected + 1).min(count - 1);
+        }
+    }
+
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.results.as_ref()?.get(self.selected)
+    }
+
     pub fn push_char(&mut self, c: char) {
         if !c.is_control() {
             self.query.push(c);
@@ -37,6 +78,10 @@ impl SearchView {
     }
 
     pub fn render(&self, f: &mut Frame<'_>, area: Rect) {
+        if let Some(results) = &self.results {
+            self.render_results(f, area, results);
+            return;
+        }
         let mut width = ((area.width as f32) * 0.5) as u16;
         width = width.max(20).min(area.width.saturating_sub(2).max(1)); // keep borders visible
         let height: u16 = 3;
@@ -47,11 +92,53 @@ impl SearchView {
             height,
         };
 
+        let footer = match &self.error {
+            Some(error) => Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))),
+            None => Line::from("^R regex ^W word ^A case ^D accents"),
+        };
         let block = Block::default()
-            .title("Search (Enter submit, Esc cancel)")
+            .title(format!(
+                "Search [{}] (Enter submit, Esc cancel)",
+                self.options.labels().join(", ")
+            ))
+            .title_bottom(footer)
             .borders(Borders::ALL);
         let prompt = Paragraph::new(format!("> {}", self.query)).block(block);
         f.render_widget(Clear, popup_area);
         f.render_widget(prompt, popup_area);
     }
+
+    fn render_results(&self, f: &mut Frame<'_>, area: Rect, results: &[SearchMatch]) {
+        let popup_area = centered_rect(80, 80, area);
+        let title = format!(
+            "Search: {} [{}] — {} {} (j/k, Enter, Esc)",
+            self.query.trim(),
+            self.options.labels().join(", "),
+            results.len(),
+            if results.len() == 1 {
+                "match"
+            } else {
+                "matches"
+            }
+        );
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let items: Vec<ListItem> = results
+            .iter()
+            .map(|hit| {
+                let heading = Line::from(Span::styled(
+                    format!("{} · p{}", hit.chapter, hit.page + 1),
+                    Style::default().fg(Color::DarkGray),
+                ));
+                let mut snippet = hit.snippet.clone();
+                snippet.spans.insert(0, Span::raw("  "));
+                ListItem::new(vec![heading, snippet])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_widget(Clear, popup_area);
+        f.render_stateful_widget(list, popup_area, &mut state);
+    }
 }
@@ -0,0 +1,461 @@
+use ratatui::{prelude::*, widgets::*};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::views::truncate_with_ellipsis;
+
+pub struct TocItem {
+    pub label: String,
+    pub level: usize, // depth in the tree, 0 at the top
+    pub page: Option<usize>,
+    pub href: Option<String>,
+    pub progress: Option<u8>, // percent read, when the entry's pages are known
+}
+
+/// The table of contents as a tree. Entries under a collapsed one are
+/// hidden; a filter shows the entries matching it with their parents.
+/// `visible` holds indices into `items` in display order.
+pub struct TocView {
+    pub items: Vec<TocItem>,
+    pub visible: Vec<usize>,
+    pub selected: usize,
+    pub collapsed: Vec<bool>,
+    pub current: Option<usize>, // entry containing the page being read
+    pub filter: String,
+    pub filtering: bool,
+}
+
+impl TocView {
+    /// `pages` is the length of the book when all of it is loaded.
+    pub fn new(mut items: Vec<TocItem>, current_page: usize, pages: Option<usize>) -> Self {
+        set_progress(&mut items, current_page, pages);
+        // The latest start at or before the page, the deepest on ties
+        let current = items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| item.page.filter(|p| *p <= current_page).map(|p| (p, idx)))
+            .max()
+            .map(|(_, idx)| idx);
+        let mut view = Self {
+            collapsed: vec![false; items.len()],
+            items,
+            visible: Vec::new(),
+            selected: 0,
+            current,
+            filter: String::new(),
+            filtering: false,
+        };
+        view.refresh();
+        if let Some(idx) = current {
+            view.select_item(idx);
+        }
+        view
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        if !self.visible.is_empty() {
+            self.selected = (self.selected + 1).min(self.visible.len() - 1);
+        }
+    }
+
+    pub fn current_page(&self) -> Option<usize> {
+        self.current_item().and_then(|item| item.page)
+    }
+
+    pub fn current_item(&self) -> Option<&TocItem> {
+        self.selected_index().and_then(|idx| self.items.get(idx))
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.visible.get(self.selected).copied()
+    }
+
+    /// Select `idx`, expanding the entries above it.
+    pub fn select_item(&mut self, idx: usize) {
+        let mut parent = self.parent(idx);
+        while let Some(above) = parent {
+            self.collapsed[above] = false;
+            parent = self.parent(above);
+        }
+        self.refresh();
+        if let Some(pos) = self.visible.iter().position(|v| *v == idx) {
+            self.selected = pos;
+        }
+    }
+
+    fn parent(&self, idx: usize) -> Option<usize> {
+        let level = self.items.get(idx)?.level;
+        (0..idx)
+            .rev()
+            .find(|above| self.items[*above].level < level)
+    }
+
+    fn has_children(&self, idx: usize) -> bool {
+        self.items
+            .get(idx + 1)
+            .is_some_and(|next| next.level > self.items[idx].level)
+    }
+
+    /// Expand the selected entry, or step into it when it already is.
+    pub fn open(&mut self) {
+        let Some(idx) = self.selected_index().filter(|idx| self.has_children(*idx)) else {
+            return;
+        };
+        if self.collapsed[idx] {
+            self.collapsed[idx] = false;
+            self.refresh();
+        } else if self.filter.is_empty() {
+            self.select_item(idx + 1);
+        }
+    }
+
+    /// Collapse the selected entry, or step out to its parent when there
+    /// is nothing to collapse.
+    pub fn close(&mut self) {
+        let Some(idx) = self.selected_index() else {
+            return;
+        };
+        if self.has_children(idx) && !self.collapsed[idx] && self.filter.is_empty() {
+            self.collapsed[idx] = true;
+            self.refresh();
+        } else if let Some(parent) = self.parent(idx) {
+            self.select_item(parent);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(idx) = self.selected_index().filter(|idx| self.has_children(*idx)) {
+            self.collapsed[idx] = !self.collapsed[idx];
+            self.refresh();
+        }
+    }
+
+    /// Show `levels` levels of the tree, collapsing everything deeper.
+    pub fn fold_to_level(&mut self, levels: usize) {
+        for idx in 0..self.items.len() {
+            self.collapsed[idx] = self.has_children(idx) && self.items[idx].level + 1 >= levels;
+        }
+        self.refresh();
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.collapsed.fill(false);
+        self.refresh();
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.filter.push(c);
+            self.refresh();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some((idx, _)) = self.filter.grapheme_indices(true).next_back() {
+            self.filter.truncate(idx);
+            self.refresh();
+        }
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filtering = false;
+        self.refresh();
+    }
+
+    /// Recompute `visible`. The selection moves to the best match of the
+    /// filter, or stays on its entry or the nearest one still shown above
+    /// it.
+    pub fn refresh(&mut self) {
+        let selected = self.selected_index();
+        let query: Vec<char> = self
+            .filter
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+        let mut best: Option<(usize, usize)> = None;
+        if query.is_empty() {
+            self.visible.clear();
+            let mut hidden_below = None;
+            for (idx, item) in self.items.iter().enumerate() {
+                if hidden_below.is_some_and(|level| item.level > level) {
+                    continue;
+                }
+                hidden_below = self.collapsed[idx].then_some(item.level);
+                self.visible.push(idx);
+            }
+        } else {
+            let mut shown = vec![false; self.items.len()];
+            for (idx, item) in self.items.iter().enumerate() {
+                let Some(score) = fuzzy_score(&item.label, &query) else {
+                    continue;
+                };
+                if best.is_none_or(|(top, _)| score > top) {
+                    best = Some((score, idx));
+                }
+                shown[idx] = true;
+                let mut parent = self.parent(idx);
+                while let Some(above) = parent.filter(|above| !shown[*above]) {
+                    shown[above] = true;
+                    parent = self.parent(above);
+                }
+            }
+            self.visible = (0..self.items.len()).filter(|idx| shown[*idx]).collect();
+        }
+        let mut target = best.map(|(_, idx)| idx).or(selected);
+        while let Some(idx) = target {
+            if let Some(pos) = self.visible.iter().position(|v| *v == idx) {
+                self.selected = pos;
+                return;
+            }
+            target = self.parent(idx).or_else(|| idx.checked_sub(1));
+        }
+        self.selected = 0;
+    }
+
+    pub fn render(&self, f: &mut Frame<'_>, area: Rect, column_width: u16) {
+        // Centered TOC view with same reader width
+        let vchunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(area);
+        let content_area = vchunks[0];
+        let col_w = column_width.min(content_area.width);
+        let left_pad = content_area.width.saturating_sub(col_w) / 2;
+        let centered = Rect {
+            x: content_area.x + left_pad,
+            y: content_area.y,
+            width: col_w,
+            height: content_area.height,
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Table of Contents (j/k, h/l fold, / filter, Enter, Esc)");
+        let max_w = (centered.width as usize).saturating_sub(2); // borders
+        let items: Vec<ListItem> = if self.visible.is_empty() {
+            vec![ListItem::new(Line::from("No matching entries"))]
+        } else {
+            self.visible
+                .iter()
+                .enumerate()
+                .map(|(pos, idx)| self.render_item(*idx, pos, max_w))
+                .collect()
+        };
+        let list = List::new(items).block(block);
+        let mut state =
+            ListState::default().with_selected((!self.visible.is_empty()).then_some(self.selected));
+        f.render_widget(Clear, centered);
+        f.render_stateful_widget(list, centered, &mut state);
+
+        let status = if self.filtering {
+            format!("Filter: {}_", self.filter)
+        } else {
+            "TOC: Enter to jump, h/l to fold, zM/zR for levels, / to filter, Esc to return"
+                .to_string()
+        };
+        f.render_widget(Paragraph::new(Line::from(status)), vchunks[1]);
+    }
+
+    fn render_item(&self, idx: usize, pos: usize, max_w: usize) -> ListItem<'_> {
+        let item = &self.items[idx];
+        let is_current = self.current == Some(idx);
+        let marker = if is_current { "• " } else { "  " };
+        let fold = match (self.has_children(idx), self.collapsed[idx]) {
+            (false, _) => "  ",
+            // Filtering shows matches under collapsed entries too
+            (true, true) if self.filter.is_empty() => "▸ ",
+            (true, _) => "▾ ",
+        };
+        let indent = "  ".repeat(item.level.min(6));
+        let label = format!("{}{}{}{}", marker, indent, fold, item.label);
+        // Pages and progress in columns on the right
+        let page = item.page.map(|p| format!("p{}", p + 1)).unwrap_or_default();
+        let progress = item
+            .progress
+            .filter(|p| *p > 0)
+            .map(|p| format!("{}%", p))
+            .unwrap_or_default();
+        let right = format!("{:>5} {:>4}", page, progress);
+        let label = truncate_with_ellipsis(&label, max_w.saturating_sub(right.len() + 1));
+        let pad = max_w.saturating_sub(label.graphemes(true).count() + right.len());
+        let line = format!("{}{}{}", label, " ".repeat(pad), right);
+        let mut style = Style::default();
+        if is_current {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if pos == self.selected {
+            style = style.bg(Color::Blue).fg(Color::White);
+        }
+        ListItem::new(Line::from(line)).style(style)
+    }
+}
+
+// An entry runs until the next one at its level or above, or to the end
+// of the book. Entries past the page are unread.
+fn set_progress(items: &mut [TocItem], current_page: usize, pages: Option<usize>) {
+    for idx in 0..items.len() {
+        let Some(start) = items[idx].page else {
+            continue;
+        };
+        let level = items[idx].level;
+        let end = match items[idx + 1..].iter().find(|next| next.level <= level) {
+            Some(next) => next.page,
+            None => pages,
+        };
+        items[idx].progress = if current_page < start {
+            Some(0)
+        } else {
+            end.map(|end| {
+                if current_page + 1 >= end || end <= start {
+                    100
+                } else {
+                    ((current_page + 1 - start) * 100 / (end - start)) as u8
+                }
+            })
+        };
+    }
+}
+
+/// Score `label` when the characters of `query` (lowercase) appear in it
+/// in order, ignoring case. Runs of adjacent characters and matches at
+/// the start of words score higher.
+fn fuzzy_score(label: &str, query: &[char]) -> Option<usize> {
+    let mut score = 0;
+    let mut wanted = query.iter().peekable();
+    let mut previous: Option<char> = None;
+    let mut adjacent = false;
+    for c in label.chars().flat_map(char::to_lowercase) {
+        let Some(want) = wanted.peek() else {
+            break;
+        };
+        if c == **want {
+            wanted.next();
+            score += 1;
+            if adjacent {
+                score += 2;
+            }
+            if !previous.is_some_and(char::is_alphanumeric) {
+                score += 3;
+            }
+            adjacent = true;
+        } else {
+            adjacent = false;
+        }
+        previous = Some(c);
+    }
+    wanted.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, level: usize, page: usize) -> TocItem {
+        TocItem {
+            label: label.to_string(),
+            level,
+            page: Some(page),
+            href: None,
+            progress: None,
+        }
+    }
+
+    fn tree() -> TocView {
+        TocView::new(
+            vec![
+                item("Part One", 0, 0),
+                item("Arrival", 1, 0),
+                item("The Harbour", 2, 4),
+                item("Departure", 1, 10),
+                item("Part Two", 0, 20),
+                item("Return", 1, 20),
+            ],
+            12,
+            Some(30),
+        )
+    }
+
+    fn labels(view: &TocView) -> Vec<&str> {
+        view.visible
+            .iter()
+            .map(|idx| view.items[*idx].label.as_str())
+            .collect()
+    }
+
+    fn selected(view: &TocView) -> &str {
+        &view.current_item().unwrap().label
+    }
+
+    #[test]
+    fn folds_the_tree_and_keeps_the_selection() {
+        let mut view = tree();
+        assert_eq!(view.current, Some(3));
+        assert_eq!(selected(&view), "Departure");
+
+        view.close();
+        assert_eq!(selected(&view), "Part One");
+        view.close();
+        assert_eq!(labels(&view), ["Part One", "Part Two", "Return"]);
+        view.open();
+        view.open();
+        assert_eq!(selected(&view), "Arrival");
+
+        view.fold_to_level(2);
+        assert_eq!(
+            labels(&view),
+            ["Part One", "Arrival", "Departure", "Part Two", "Return"]
+        );
+        view.fold_to_level(1);
+        assert_eq!(labels(&view), ["Part One", "Part Two"]);
+        assert_eq!(selected(&view), "Part One");
+        view.unfold_all();
+        assert_eq!(view.visible.len(), 6);
+
+        // Opening at a collapsed entry expands the entries above it
+        view.fold_to_level(1);
+        view.select_item(2);
+        assert_eq!(selected(&view), "The Harbour");
+    }
+
+    #[test]
+    fn filters_fuzzily_with_parents() {
+        let mut view = tree();
+        view.fold_to_level(1);
+        for c in "hbr".chars() {
+            view.push_char(c);
+        }
+        assert_eq!(labels(&view), ["Part One", "Arrival", "The Harbour"]);
+        assert_eq!(selected(&view), "The Harbour");
+
+        view.backspace();
+        view.backspace();
+        view.backspace();
+        view.push_char('p');
+        view.push_char('t');
+        // Word starts beat letters in the middle of words, so not Departure
+        assert_eq!(selected(&view), "Part One");
+        view.push_char('w');
+        assert_eq!(selected(&view), "Part Two");
+
+        view.push_char('z');
+        assert!(view.visible.is_empty());
+        assert!(view.current_item().is_none());
+        view.clear_filter();
+        assert_eq!(labels(&view), ["Part One", "Part Two"]);
+    }
+
+    #[test]
+    fn tracks_progress_through_entries() {
+        let view = tree();
+        let progress: Vec<Option<u8>> = view.items.iter().map(|item| item.progress).collect();
+        assert_eq!(
+            progress,
+            [Some(65), Some(100), Some(100), Some(30), Some(0), Some(0)]
+        );
+    }
+}
@@ -3,20 +3,21 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::layout::centered_rect;
 
-pub struct TocView {
-    pub items: Vec<TocItem>,
+pub struct BookmarkView {
+    pub items: Vec<BookmarkItem>,
     pub selected: usize,
 }
 
-pub struct TocItem {
-    pub label: String,
-    pub level: usize,
+pub struct BookmarkItem {
+    pub index: usize, // position in App::bookmarks
+    pub chapter: String,
+    pub snippet: String,
+    pub percent: Option<usize>,
     pub page: Option<usize>,
-    pub href: Option<String>,
 }
 
-impl TocView {
-    pub fn new(items: Vec<TocItem>) -> Self {
+impl BookmarkView {
+    pub fn new(items: Vec<BookmarkItem>) -> Self {
         Self { items, selected: 0 }
     }
 
@@ -29,16 +30,11 @@ impl TocView {
         }
     }
 
-    pub fn current_page(&self) -> Option<usize> {
-        self.items.get(self.selected).and_then(|item| item.page)
-    }
-
-    pub fn current_item(&self) -> Option<&TocItem> {
+    pub fn current_item(&self) -> Option<&BookmarkItem> {
         self.items.get(self.selected)
     }
 
     pub fn render(&self, f: &mut Frame<'_>, area: Rect, column_width: u16) {
-        // Centered TOC view with same reader width
         let vchunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(1), Constraint::Length(1)])
@@ -55,48 +51,68 @@ impl TocView {
 
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("Table of Contents (j/k, Enter, Esc)");
-        let max_w = centered.width as usize - 2; // borders
-        let items: Vec<ListItem> = self
-            .items
-            .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                let style = if i == self.selected {
-                    Style::default().bg(Color::Blue).fg(Color::White)
-                } else {
-                    Style::default()
-                };
-                let indent = "  ".repeat(item.level.min(6));
-                let mut label = format!("{}{}", indent, item.label);
-                let page_text = item.page.map(|p| format!("p{}", p + 1));
-                label = format_toc_line(&label, page_text.as_deref(), max_w);
-                ListItem::new(Line::from(label)).style(style)
-            })
-            .collect();
+            .title("Bookmarks (j/k, Enter, d, Esc)");
+        let max_w = (centered.width as usize).saturating_sub(2);
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new(Line::from(
+                "No bookmarks yet; press m while reading",
+            ))]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let style = if i == self.selected {
+                        Style::default().bg(Color::Blue).fg(Color::White)
+                    } else {
+                        Style::default()
+                    };
+                    let percent = item.percent.map(|p| format!("{}%", p));
+                    let title = format_toc_line(&item.chapter, percent.as_deref(), max_w);
+                    let snippet = truncate_with_ellipsis(&format!("  {}", item.snippet), max_w);
+                    ListItem::new(vec![
+                        Line::from(title),
+                        Line::from(Span::styled(snippet, Style::default().fg(Color::DarkGray))),
+                    ])
+                    .style(style)
+                })
+                .collect()
+        };
         let list = List::new(items).block(block);
         f.render_widget(Clear, centered);
         f.render_widget(list, centered);
 
-        // Status bar (reuse lower chunk)
-        let status = Paragraph::new(Line::from("TOC: Enter to jump, Esc to return"));
+        let status = Paragraph::new(Line::from(
+            "Bookmarks: Enter to jump, d to delete, Esc to return",
+        ));
         f.render_widget(status, vchunks[1]);
     }
 }
 
 pub struct FootnoteView {
+    pub title: String,
     pub text: String,
 }
 
 impl FootnoteView {
     pub fn new(text: String) -> Self {
-        Self { text }
+        Self {
+            title: "Footnote".into(),
+            text,
+        }
+    }
+
+    pub fn note(text: String) -> Self {
+        Self {
+            title: "Note".into(),
+            text,
+        }
     }
 
     pub fn render(&self, f: &mut Frame<'_>, area: Rect) {
         let popup_area = centered_rect(70, 50, area);
         let block = Block::default()
-            .title("Footnote (Esc to close)")
+            .title(format!("{} (Esc to close)", self.title))
             .borders(Borders::ALL);
         let body = Paragraph::new(self.text.clone())
             .block(block)
@@ -106,6 +122,44 @@ impl FootnoteView {
     }
 }
 
+pub struct NoteView {
+    pub annotation: usize, // index into App::annotations
+    pub text: String,
+}
+
+impl NoteView {
+    pub fn new(annotation: usize, text: &str) -> Self {
+        Self {
+            annotation,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.text.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some((idx, _)) = self.text.grapheme_indices(true).next_back() {
+            self.text.truncate(idx);
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame<'_>, area: Rect) {
+        let popup_area = centered_rect(70, 30, area);
+        let block = Block::default()
+            .title("Note (Enter save, Esc cancel)")
+            .borders(Borders::ALL);
+        let body = Paragraph::new(format!("> {}", self.text))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(Clear, popup_area);
+        f.render_widget(body, popup_area);
+    }
+}
+
 fn format_toc_line(label: &str, page_text: Option<&str>, max_w: usize) -> String {
     if max_w == 0 {
         return String::new();
@@ -125,7 +179,7 @@ fn format_toc_line(label: &str, page_text: Option<&str>, max_w: usize) -> String
     trimmed
 }
 
-fn truncate_with_ellipsis(text: &str, max_w: usize) -> String {
+pub(crate) fn truncate_with_ellipsis(text: &str, max_w: usize) -> String {
     if max_w == 0 {
         return String::new();
     }
@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use reader_core::{
+    annotations::load_annotations,
+    epub::EpubBook,
+    export::{BookExport, ExportFormat},
+    state::load_state,
+    types::{BookId, DocumentFormat, Location},
+};
+
+use crate::{
+    book_key, detect_format, fallback_title, normalize_spine_href, normalize_spine_href_for_links,
+};
+
+const USAGE: &str = "Usage: librarian export <book> [--format md|json] [--output FILE]";
+
+/// `librarian export <book>`: write the book's highlights, notes and
+/// bookmarks as Markdown or JSON, to stdout unless `--output` is given.
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let mut input: Option<&str> = None;
+    let mut format: Option<ExportFormat> = None;
+    let mut output: Option<&str> = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-f" | "--format" => {
+                let name = iter.next().ok_or(USAGE)?;
+                format = Some(
+                    ExportFormat::from_name(name)
+                        .ok_or_else(|| format!("Unknown export format: {}", name))?,
+                );
+            }
+            "-o" | "--output" => output = Some(iter.next().ok_or(USAGE)?),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let input = crate::scan::resolve_book(input.ok_or(USAGE)?)?;
+    let input = input.as_str();
+    let format = format
+        .or_else(|| {
+            output
+                .and_then(|out| Path::new(out).extension())
+                .and_then(|ext| ext.to_str())
+                .and_then(ExportFormat::from_name)
+        })
+        .unwrap_or(ExportFormat::Markdown);
+
+    let path = Path::new(input);
+    let doc_format = detect_format(input);
+    let epub = match doc_format {
+        DocumentFormat::Epub3 | DocumentFormat::Epub2 | DocumentFormat::Other => {
+            EpubBook::open(path).ok()
+        }
+        _ => None,
+    };
+    let key = match &epub {
+        Some(book) => book_key(path, book.metadata.identifier()),
+        None => reader_core::identity::key_for_file(path, doc_format)
+            .unwrap_or_else(|_| book_key(path, None)),
+    };
+    let probe = BookId::new(key, path.display().to_string(), None, doc_format);
+    let record = load_state(&probe);
+    let title = epub
+        .as_ref()
+        .and_then(|book| book.title.clone())
+        .or_else(|| {
+            record
+                .as_ref()
+                .and_then(|r| r.book().title().map(str::to_string))
+        });
+    let book_id = BookId::new(probe.id(), probe.path(), title, doc_format);
+
+    let annotations = load_annotations(&book_id);
+    let bookmarks = record.map(|r| r.bookmarks().to_vec()).unwrap_or_default();
+    let chapter_titles = epub.as_ref().map(epub_chapter_titles).unwrap_or_default();
+    let export = BookExport::new(&book_id, &annotations, &bookmarks, |loc: &Location| {
+        chapter_title(&chapter_titles, doc_format, loc)
+    });
+    if export.is_empty() {
+        eprintln!("No highlights or bookmarks saved for {}", path.display());
+    }
+
+    let rendered = export.render(format);
+    match output {
+        Some(out) => {
+            fs::write(out, rendered).map_err(|e| format!("Failed to write {}: {}", out, e))?
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            match writeln!(stdout, "{}", rendered.trim_end()) {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                    return Err(format!("Failed to write export: {}", e));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Titles keyed by the chapter hrefs stored in locations. Chapters are not
+// parsed here, so untitled ones fall back to a name derived from the file.
+fn epub_chapter_titles(book: &EpubBook) -> HashMap<String, String> {
+    let base = book.opf_base();
+    let labels = book.toc_labels().unwrap_or_default();
+    book.spine()
+        .iter()
+        .map(|item| {
+            let title = labels
+                .get(&normalize_spine_href(&base, &item.href))
+                .cloned()
+                .unwrap_or_else(|| fallback_title(&item.href));
+            (normalize_spine_href_for_links(&base, &item.href), title)
+        })
+        .collect()
+}
+
+fn chapter_title(
+    titles: &HashMap<String, String>,
+    format: DocumentFormat,
+    loc: &Location,
+) -> String {
+    if let Some(title) = loc.chapter_href().and_then(|href| titles.get(href)) {
+        return title.clone();
+    }
+    match format {
+        DocumentFormat::Pdf => {
+            let page = loc
+                .chapter_href()
+                .and_then(|href| href.strip_prefix("page:"))
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(loc.spine_index() + 1);
+            format!("Page {}", page)
+        }
+        DocumentFormat::Text | DocumentFormat::Markdown => "Text".to_string(),
+        _ => format!("Chapter {}", loc.spine_index() + 1),
+    }
+}
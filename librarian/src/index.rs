@@ -0,0 +1,213 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    thread,
+};
+
+use reader_core::{
+    epub::EpubBook,
+    fb2::Fb2Book,
+    index::{IndexedChapter, SearchIndex},
+    layout::chapter_block_starts,
+    library::{Catalog, LibraryBook},
+    mobi::MobiBook,
+    pdf::PdfLoader,
+    text::TextFile,
+    types::{Document, DocumentFormat},
+};
+
+use crate::{html_document, EpubChapterLoader};
+
+const SEARCH_LIMIT: usize = 50;
+
+#[derive(Default)]
+pub(crate) struct IndexReport {
+    pub(crate) indexed: usize,
+    pub(crate) failed: usize,
+    pub(crate) removed: usize,
+}
+
+/// Chapters of a book as the reader loads them, so hits map onto the
+/// same chapter hrefs and block numbers.
+fn chapters_for(book: &LibraryBook) -> Option<Vec<IndexedChapter>> {
+    let path = Path::new(book.path());
+    match book.format() {
+        DocumentFormat::Epub3 | DocumentFormat::Epub2 => {
+            let epub = EpubBook::open(path).ok()?;
+            let label_map = epub.toc_labels().unwrap_or_default();
+            let mut loader = EpubChapterLoader::new(epub, label_map);
+            let mut chapters = Vec::new();
+            while let Some(chapter) = loader.next_chapter() {
+                chapters.push(IndexedChapter::from_blocks(
+                    chapter.href,
+                    chapter.title,
+                    &chapter.blocks,
+                ));
+            }
+            Some(chapters)
+        }
+        DocumentFormat::Pdf => {
+            let mut loader = PdfLoader::open(path).ok()?;
+            let chapters = (0..loader.page_count())
+                .filter_map(|idx| {
+                    let blocks = loader.load_page(idx).ok()?;
+                    Some(IndexedChapter::from_blocks(
+                        format!("page:{}", idx + 1),
+                        format!("Page {}", idx + 1),
+                        &blocks,
+                    ))
+                })
+                .collect();
+            Some(chapters)
+        }
+        DocumentFormat::Fb2 => Some(document_chapters(&Fb2Book::open(path).ok()?.to_document())),
+        DocumentFormat::Mobi => Some(document_chapters(&MobiBook::open(path).ok()?.to_document())),
+        DocumentFormat::Html => Some(document_chapters(&html_document(path).ok()?)),
+        DocumentFormat::Text | DocumentFormat::Markdown => {
+            let document = TextFile::open(path).ok()?.to_document();
+            let href = document
+                .chapter_hrefs()
+                .first()
+                .cloned()
+                .unwrap_or_default();
+            let title = document
+                .chapter_titles()
+                .first()
+                .cloned()
+                .unwrap_or_default();
+            Some(vec![IndexedChapter::from_blocks(
+                href,
+                title,
+                document.blocks(),
+            )])
+        }
+        // Comic pages are images with nothing to index
+        DocumentFormat::Cbz => Some(Vec::new()),
+        DocumentFormat::Other => None,
+    }
+}
+
+/// Split a whole-book document back into its chapters.
+fn document_chapters(document: &Document) -> Vec<IndexedChapter> {
+    let blocks = document.blocks();
+    let starts = chapter_block_starts(blocks);
+    starts
+        .iter()
+        .enumerate()
+        .map(|(idx, start)| {
+            // Each later chapter is preceded by a three-block separator
+            let end = starts.get(idx + 1).map_or(blocks.len(), |next| next - 3);
+            IndexedChapter::from_blocks(
+                document
+                    .chapter_hrefs()
+                    .get(idx)
+                    .cloned()
+                    .unwrap_or_default(),
+                document
+                    .chapter_titles()
+                    .get(idx)
+                    .cloned()
+                    .unwrap_or_default(),
+                &blocks[*start..end],
+            )
+        })
+        .collect()
+}
+
+/// Index catalogued books that changed since they were last indexed and
+/// forget books that left the catalog.
+pub(crate) fn update_index(
+    catalog: &Catalog,
+    index: &mut SearchIndex,
+    mut on_book: impl FnMut(&LibraryBook, bool),
+) -> std::io::Result<IndexReport> {
+    let mut report = IndexReport {
+        removed: index.retain_books(|id| catalog.books().iter().any(|book| book.id() == id))?,
+        ..IndexReport::default()
+    };
+    for book in catalog.books() {
+        if index.is_current(book) {
+            continue;
+        }
+        match chapters_for(book) {
+            Some(chapters) => {
+                index.add_book(book, chapters)?;
+                report.indexed += 1;
+                on_book(book, true);
+            }
+            None => {
+                report.failed += 1;
+                on_book(book, false);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Bring the index up to date on a background thread while the reader runs.
+pub(crate) fn spawn_background_indexer() {
+    thread::spawn(|| {
+        let catalog = Catalog::load();
+        if catalog.books().is_empty() {
+            return;
+        }
+        if let Ok(mut index) = SearchIndex::open() {
+            let _ = update_index(&catalog, &mut index, |_, _| {});
+        }
+    });
+}
+
+/// `librarian index`: index the library in the foreground.
+pub(crate) fn run_index() -> Result<(), String> {
+    let catalog = Catalog::load();
+    if catalog.books().is_empty() {
+        return Err("The library is empty; add books with `librarian scan <dir>`".into());
+    }
+    let mut index = SearchIndex::open().map_err(|e| e.to_string())?;
+    let report = update_index(&catalog, &mut index, |book, ok| {
+        if ok {
+            println!("indexed {}", book.display_title());
+        } else {
+            eprintln!("failed to index {}", book.path());
+        }
+    })
+    .map_err(|e| format!("Failed to write index: {}", e))?;
+    println!(
+        "{} books indexed, {} removed, {} failed ({} in index)",
+        report.indexed,
+        report.removed,
+        report.failed,
+        index.book_count()
+    );
+    Ok(())
+}
+
+/// `librarian search <query>`: print matching passages across the library.
+pub(crate) fn run_search(args: &[String]) -> Result<(), String> {
+    let query = args.join(" ");
+    if query.trim().is_empty() {
+        return Err("Usage: librarian search <words>".into());
+    }
+    let mut index = SearchIndex::open().map_err(|e| e.to_string())?;
+    let hits = index.search(&query, SEARCH_LIMIT);
+    if hits.is_empty() {
+        println!("No matches in {} indexed books", index.book_count());
+    }
+    // Stop quietly when piped into a pager that exits early
+    let mut stdout = io::stdout().lock();
+    for hit in hits {
+        let written = writeln!(
+            stdout,
+            "{} — {}\n    {}",
+            hit.book_title.as_deref().unwrap_or(&hit.book_path),
+            hit.chapter_title,
+            hit.snippet
+        );
+        match written {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(e) => return Err(format!("Failed to write results: {}", e)),
+            Ok(()) => {}
+        }
+    }
+    Ok(())
+}
@@ -2,31 +2,26 @@ use std::{
     collections::{HashSet, VecDeque},
     env,
     path::{Path, PathBuf},
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
     thread,
     time::Duration,
 };
 
 use reader_core::{
+    annotations::load_annotations,
     epub::EpubBook,
     pdf::PdfLoader,
     state::{load_state, save_state},
     types::{AppStateRecord, BookId, Document, DocumentFormat, DocumentInfo, Location},
 };
-use ui::app::{ChapterPrefetchRequest, IncomingChapter, IncomingPage, PrefetchRequest};
-
-const SKIP_HINTS: [&str; 10] = [
-    "cover",
-    "nav",
-    "toc",
-    "title",
-    "front",
-    "copyright",
-    "acknowledg",
-    "glossary",
-    "colophon",
-    "dedication",
-];
+use ui::app::{
+    ChapterPrefetchRequest, IncomingChapter, IncomingPage, OpenRequest, PrefetchRequest,
+};
+use ui::reader_view::{parse_color, Theme};
+
+mod export;
+mod index;
+mod scan;
 
 struct ChapterStream {
     document: Document,
@@ -175,6 +170,12 @@ fn fallback_title(href: &str) -> String {
     }
 }
 
+/// Stable id for a book file, or its path if the file cannot be read.
+fn book_key(path: &Path, identifier: Option<&str>) -> String {
+    reader_core::identity::book_key(path, identifier)
+        .unwrap_or_else(|_| format!("path:{}", path.display()))
+}
+
 fn strip_fragment(href: &str) -> &str {
     href.split('#').next().unwrap_or(href)
 }
@@ -263,35 +264,94 @@ impl EpubChapterLoader {
     }
 
     fn is_candidate_item(&self, item: &reader_core::epub::SpineItem) -> bool {
-        let href = item.href.to_ascii_lowercase();
-        let mt_is_xhtml = item
-            .media_type
-            .as_deref()
-            .map(|mt| mt.contains("xhtml") || mt.contains("html"))
-            .unwrap_or(true);
-        if !mt_is_xhtml || href.contains("nav") || href.contains("toc") {
-            return false;
-        }
-        let key = normalize_spine_href(&self.base, &item.href);
-        let label = self.label_map.get(&key);
-        if label.is_none() && SKIP_HINTS.iter().any(|h| href.contains(h)) {
-            return false;
-        }
-        true
+        self.book.is_chapter_item(item, &self.label_map)
     }
 }
 
 fn main() {
-    // Accept optional EPUB/PDF/TXT/MD path: default to docs/alice.epub
+    // Accept optional EPUB/PDF/FB2/MOBI/CBZ/HTML/TXT/MD path or library title; without one, open the library
     let args: Vec<String> = env::args().collect();
-    let input_path = args
-        .get(1)
-        .cloned()
-        .unwrap_or_else(|| "docs/alice.epub".to_string());
-    let format = detect_format(&input_path);
+    if let Err(e) = reader_core::identity::migrate_path_keys() {
+        eprintln!("Failed to migrate saved progress: {}", e);
+    }
+    if args.get(1).map(String::as_str) == Some("export") {
+        if let Err(e) = export::run(&args[2..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("scan") {
+        if let Err(e) = scan::run(&args[2..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("index") {
+        if let Err(e) = index::run_index() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("search") {
+        if let Err(e) = index::run_search(&args[2..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut next = match args.get(1) {
+        Some(arg) => match scan::resolve_book(arg) {
+            Ok(path) => Some(OpenRequest {
+                path,
+                location: None,
+            }),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    index::spawn_background_indexer();
+    // A book picked in the library is opened in the same process
+    loop {
+        let request = match next.take() {
+            Some(request) => open_book(&request.path, request.location),
+            None => run_library(),
+        };
+        match request {
+            Some(request) => next = Some(request),
+            None => break,
+        }
+    }
+    eprintln!(
+        "Run with: cargo run -p librarian [path_or_library_title]  # no argument opens the library"
+    );
+}
+
+fn run_library() -> Option<OpenRequest> {
+    let mut app = ui::app::App::new_library();
+    apply_config(&mut app);
+    match app.run() {
+        Ok(exit) => exit.open,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            None
+        }
+    }
+}
+
+/// Read a book until the reader quits, starting at `start` instead of the
+/// saved position when given; returns the next book to open when the
+/// reader switched books through the library.
+fn open_book(input_path: &str, start: Option<Location>) -> Option<OpenRequest> {
+    let format = detect_format(input_path);
 
     if matches!(format, DocumentFormat::Pdf) {
-        let path = Path::new(&input_path);
+        let path = Path::new(input_path);
         let page_limit = env::var("LIBRARIAN_PDF_PAGE_LIMIT")
             .ok()
             .and_then(|s| s.parse::<usize>().ok());
@@ -312,51 +372,47 @@ fn main() {
                         target_pages, actual_pages
                     );
                 }
-                run_reader_streaming(
+                return run_reader_streaming(
                     document,
                     book_id,
-                    0,
                     rx,
                     prefetch_tx,
                     actual_pages,
                     prefetch_window,
+                    start,
                 );
-                return;
             }
             Err(reader_core::pdf::PdfError::Encrypted) => {
                 eprintln!("Failed to open PDF: file is encrypted (password protected)");
-                return;
+                return None;
             }
             Err(e) => {
                 eprintln!("Failed to open PDF: {}", e);
-                return;
+                return None;
             }
         }
     }
 
-    if matches!(format, DocumentFormat::Text | DocumentFormat::Markdown) {
-        let path = Path::new(&input_path);
-        match reader_core::text::TextFile::open(path) {
-            Ok(text_doc) => {
-                let document = text_doc.to_document();
+    let path = Path::new(input_path);
+    if let Some(loaded) = load_document(path, format) {
+        return match loaded {
+            Ok(document) => {
                 let title = document.info().title().map(str::to_string);
                 let book_id = BookId::new(
-                    format!("path:{}", path.display()),
+                    document.info().id(),
                     path.display().to_string(),
                     title,
                     format,
                 );
-                run_reader(document, book_id, 0);
-                return;
+                run_reader(document, book_id, start)
             }
             Err(e) => {
-                eprintln!("Failed to open text file: {}", e);
-                return;
+                eprintln!("Failed to open {}: {}", path.display(), e);
+                None
             }
-        }
+        };
     }
 
-    let path = Path::new(&input_path);
     let initial_chapters = env::var("LIBRARIAN_EPUB_INITIAL_CHAPTERS")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
@@ -367,21 +423,11 @@ fn main() {
         .unwrap_or(2);
 
     match stream_epub_lazy(path, initial_chapters, format) {
-        Ok(stream) => {
-            run_reader_chapter_streaming(stream, 0, prefetch_window);
+        Ok(stream) => run_reader_chapter_streaming(stream, prefetch_window, start),
+        Err(e) => {
+            eprintln!("Failed to open file: {}", e);
+            None
         }
-        Err(_) => match stream_epub_lazy(
-            Path::new("docs/alice.epub"),
-            initial_chapters,
-            DocumentFormat::Epub3,
-        ) {
-            Ok(stream) => {
-                run_reader_chapter_streaming(stream, 0, prefetch_window);
-            }
-            Err(e) => {
-                eprintln!("Failed to open file: {}", e);
-            }
-        },
     }
 }
 
@@ -392,7 +438,7 @@ fn stream_epub_lazy(
 ) -> Result<ChapterStream, reader_core::epub::ReaderError> {
     let book = EpubBook::open(path)?;
     let book_id = BookId::new(
-        format!("path:{}", path.display()),
+        book_key(path, book.metadata.identifier()),
         path.display().to_string(),
         book.title.clone(),
         format,
@@ -482,15 +528,20 @@ fn stream_epub_lazy(
                     }
                     progressed = true;
                 }
-                if !progressed
-                    && pending_loaded == loaded_count
-                    && pending_href.is_none()
-                    && prefetch_rx
-                        .recv_timeout(Duration::from_millis(200))
-                        .is_err()
-                {
+                if progressed {
                     continue;
                 }
+                // Wait for the next request without dropping it
+                match prefetch_rx.recv_timeout(Duration::from_millis(200)) {
+                    Ok(req) => {
+                        pending_loaded = pending_loaded.max(req.target_loaded);
+                        if let Some(href) = req.target_href {
+                            pending_href = Some(href);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
             }
         });
     }
@@ -584,7 +635,7 @@ fn stream_pdf(
     }
 
     let book_id = BookId::new(
-        format!("path:{}", path.display()),
+        book_key(path, None),
         path.display().to_string(),
         title,
         DocumentFormat::Pdf,
@@ -603,37 +654,40 @@ fn stream_pdf(
     ))
 }
 
-fn run_reader(document: Document, book_id: BookId, selected_index: usize) {
-    // Load last location and update initial spine index
-    let mut last = load_state(&book_id)
-        .map(|r| r.last_location().clone())
+fn run_reader(document: Document, book_id: BookId, start: Option<Location>) -> Option<OpenRequest> {
+    // Load last location; anchored records are resolved once the app has paginated
+    let record = load_state(&book_id);
+    let last = start
+        .or_else(|| record.as_ref().map(|r| r.last_location().clone()))
         .unwrap_or_else(|| Location::new(0, 0));
-    last.set_spine_index(selected_index);
 
     let mut app = ui::app::App::new_with_document(document, last.offset());
-    apply_theme_config(&mut app);
-
-    let current_idx = match app.run() {
-        Ok(idx) => idx,
+    app.initial_location = Some(last.clone());
+    app.jumps = record
+        .as_ref()
+        .map(|r| r.jumps().clone())
+        .unwrap_or_default();
+    app.bookmarks = record.map(|r| r.bookmarks().to_vec()).unwrap_or_default();
+    app.annotations = load_annotations(&book_id);
+    apply_config(&mut app);
+
+    let (last, open) = match app.run() {
+        Ok(exit) => (exit.location, exit.open),
         Err(e) => {
             eprintln!("Error: {}", e);
-            0
+            (last, None)
         }
     };
 
-    // Save last location using current page index
-    last.set_offset(current_idx);
-    let rec = AppStateRecord::new(book_id, last, vec![]);
-    let _ = save_state(&rec);
-
-    eprintln!("Run with: cargo run -p librarian [path_to_epub|path_to_txt|path_to_md]  # default docs/alice.epub");
+    save_last_location(book_id, last);
+    open
 }
 
 fn run_reader_chapter_streaming(
     stream: ChapterStream,
-    selected_index: usize,
     prefetch_window: usize,
-) {
+    start: Option<Location>,
+) -> Option<OpenRequest> {
     let ChapterStream {
         document,
         book_id,
@@ -642,11 +696,11 @@ fn run_reader_chapter_streaming(
         total_chapters,
         chapter_index_by_href,
     } = stream;
-    // Load last location and update initial spine index
-    let mut last = load_state(&book_id)
-        .map(|r| r.last_location().clone())
+    // Load last location; anchored records are resolved once the app has paginated
+    let record = load_state(&book_id);
+    let last = start
+        .or_else(|| record.as_ref().map(|r| r.last_location().clone()))
         .unwrap_or_else(|| Location::new(0, 0));
-    last.set_spine_index(selected_index);
 
     let mut app = ui::app::App::new_with_document_chapter_streaming(
         document,
@@ -657,40 +711,41 @@ fn run_reader_chapter_streaming(
         prefetch_window,
         chapter_index_by_href,
     );
-    apply_theme_config(&mut app);
-
-    let current_idx = match app.run() {
-        Ok(idx) => idx,
+    app.initial_location = Some(last.clone());
+    app.jumps = record
+        .as_ref()
+        .map(|r| r.jumps().clone())
+        .unwrap_or_default();
+    app.bookmarks = record.map(|r| r.bookmarks().to_vec()).unwrap_or_default();
+    app.annotations = load_annotations(&book_id);
+    apply_config(&mut app);
+
+    let (last, open) = match app.run() {
+        Ok(exit) => (exit.location, exit.open),
         Err(e) => {
             eprintln!("Error: {}", e);
-            0
+            (last, None)
         }
     };
 
-    // Save last location using current page index
-    last.set_offset(current_idx);
-    let rec = AppStateRecord::new(book_id, last, vec![]);
-    let _ = save_state(&rec);
-
-    eprintln!(
-        "Run with: cargo run -p librarian [path_to_epub|path_to_txt|path_to_md]  # default docs/alice.epub"
-    );
+    save_last_location(book_id, last);
+    open
 }
 
 fn run_reader_streaming(
     document: Document,
     book_id: BookId,
-    selected_index: usize,
     incoming: Receiver<IncomingPage>,
     prefetch_tx: Sender<PrefetchRequest>,
     total_pages: usize,
     prefetch_window: usize,
-) {
-    // Load last location and update initial spine index
-    let mut last = load_state(&book_id)
-        .map(|r| r.last_location().clone())
+    start: Option<Location>,
+) -> Option<OpenRequest> {
+    // Load last location; anchored records are resolved once the app has paginated
+    let record = load_state(&book_id);
+    let last = start
+        .or_else(|| record.as_ref().map(|r| r.last_location().clone()))
         .unwrap_or_else(|| Location::new(0, 0));
-    last.set_spine_index(selected_index);
 
     let mut app = ui::app::App::new_with_document_streaming(
         document,
@@ -700,26 +755,45 @@ fn run_reader_streaming(
         prefetch_tx,
         prefetch_window,
     );
-    apply_theme_config(&mut app);
-
-    let current_idx = match app.run() {
-        Ok(idx) => idx,
+    app.initial_location = Some(last.clone());
+    app.jumps = record
+        .as_ref()
+        .map(|r| r.jumps().clone())
+        .unwrap_or_default();
+    app.bookmarks = record.map(|r| r.bookmarks().to_vec()).unwrap_or_default();
+    app.annotations = load_annotations(&book_id);
+    apply_config(&mut app);
+
+    let (last, open) = match app.run() {
+        Ok(exit) => (exit.location, exit.open),
         Err(e) => {
             eprintln!("Error: {}", e);
-            0
+            (last, None)
         }
     };
 
-    // Save last location using current page index
-    last.set_offset(current_idx);
-    let rec = AppStateRecord::new(book_id, last, vec![]);
+    save_last_location(book_id, last);
+    open
+}
+
+fn save_last_location(book_id: BookId, last: Location) {
+    // Reload so bookmarks saved while reading are kept
+    let mut rec =
+        load_state(&book_id).unwrap_or_else(|| AppStateRecord::new(book_id, last.clone(), vec![]));
+    *rec.last_location_mut() = last;
     let _ = save_state(&rec);
+}
 
-    eprintln!("Run with: cargo run -p librarian [path_to_epub|path_to_txt|path_to_md]  # default docs/alice.epub");
+fn apply_config(app: &mut ui::app::App) {
+    let Some(config) = read_config() else {
+        return;
+    };
+    apply_theme_config(app, &config);
+    apply_keymap_config(app, &config);
 }
 
-fn apply_theme_config(app: &mut ui::app::App) {
-    // Load theme from primary config root with legacy fallbacks
+/// config.toml from the primary config root with legacy fallbacks.
+fn read_config() -> Option<toml::Value> {
     let mut candidates = Vec::new();
     if let Some(dir) = reader_core::config::config_root() {
         candidates.push(dir.join("config.toml"));
@@ -727,117 +801,108 @@ fn apply_theme_config(app: &mut ui::app::App) {
     for legacy in reader_core::config::legacy_config_roots() {
         candidates.push(legacy.join("config.toml"));
     }
-    for cfg_path in candidates {
-        if let Ok(text) = std::fs::read_to_string(&cfg_path) {
-            if let Ok(value) = toml::from_str::<toml::Value>(&text) {
-                if let Some(theme) = value.get("theme").and_then(|v| v.as_table()) {
-                    fn parse_color(s: &str) -> Option<ratatui::style::Color> {
-                        match s.to_lowercase().as_str() {
-                            "black" => Some(ratatui::style::Color::Black),
-                            "red" => Some(ratatui::style::Color::Red),
-                            "green" => Some(ratatui::style::Color::Green),
-                            "yellow" => Some(ratatui::style::Color::Yellow),
-                            "blue" => Some(ratatui::style::Color::Blue),
-                            "magenta" => Some(ratatui::style::Color::Magenta),
-                            "cyan" => Some(ratatui::style::Color::Cyan),
-                            "white" => Some(ratatui::style::Color::White),
-                            "gray" | "darkgray" => Some(ratatui::style::Color::DarkGray),
-                            _ => None,
-                        }
-                    }
-                    // Presets by name
-                    if let Some(name) = theme.get("name").and_then(|v| v.as_str()) {
-                        match name.to_lowercase().as_str() {
-                            "gruvbox" => {
-                                app.theme.header_bg = ratatui::style::Color::Yellow;
-                                app.theme.header_fg = ratatui::style::Color::Black;
-                                app.theme.header_pad_bg = ratatui::style::Color::DarkGray;
-                                app.theme.footer_bg = ratatui::style::Color::Green;
-                                app.theme.footer_fg = ratatui::style::Color::Black;
-                                app.theme.footer_pad_bg = ratatui::style::Color::DarkGray;
-                            }
-                            "dracula" => {
-                                app.theme.header_bg = ratatui::style::Color::Magenta;
-                                app.theme.header_fg = ratatui::style::Color::White;
-                                app.theme.header_pad_bg = ratatui::style::Color::DarkGray;
-                                app.theme.footer_bg = ratatui::style::Color::Blue;
-                                app.theme.footer_fg = ratatui::style::Color::White;
-                                app.theme.footer_pad_bg = ratatui::style::Color::DarkGray;
-                            }
-                            "tokyonight" => {
-                                app.theme.header_bg = ratatui::style::Color::Blue;
-                                app.theme.header_fg = ratatui::style::Color::White;
-                                app.theme.header_pad_bg = ratatui::style::Color::DarkGray;
-                                app.theme.footer_bg = ratatui::style::Color::Cyan;
-                                app.theme.footer_fg = ratatui::style::Color::Black;
-                                app.theme.footer_pad_bg = ratatui::style::Color::DarkGray;
-                            }
-                            _ => {}
-                        }
-                    }
-                    // Individual overrides
-                    if let Some(s) = theme
-                        .get("header_bg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.header_bg = s;
-                    }
-                    if let Some(s) = theme
-                        .get("header_fg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.header_fg = s;
-                    }
-                    if let Some(s) = theme
-                        .get("header_pad_bg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.header_pad_bg = s;
-                    }
-                    if let Some(s) = theme
-                        .get("footer_bg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.footer_bg = s;
-                    }
-                    if let Some(s) = theme
-                        .get("footer_fg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.footer_fg = s;
-                    }
-                    if let Some(s) = theme
-                        .get("footer_pad_bg")
-                        .and_then(|v| v.as_str())
-                        .and_then(parse_color)
-                    {
-                        app.theme.footer_pad_bg = s;
-                    }
-                }
-            }
-            break;
+    let text = candidates
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())?;
+    toml::from_str(&text).ok()
+}
+
+fn apply_theme_config(app: &mut ui::app::App, config: &toml::Value) {
+    let Some(theme) = config.get("theme").and_then(|v| v.as_table()) else {
+        return;
+    };
+    // A preset by name, then individual overrides
+    if let Some(preset) = theme
+        .get("name")
+        .and_then(|v| v.as_str())
+        .and_then(Theme::preset)
+    {
+        app.theme = preset;
+    }
+    for field in Theme::FIELDS {
+        if let Some(color) = theme
+            .get(*field)
+            .and_then(|v| v.as_str())
+            .and_then(parse_color)
+        {
+            app.theme.set_color(field, color);
+        }
+    }
+}
+
+/// `[keymap.<mode>]` tables map keys in vim notation to action names; a
+/// bad entry is reported and the default keymap kept.
+fn apply_keymap_config(app: &mut ui::app::App, config: &toml::Value) {
+    let Some(modes) = config.get("keymap").and_then(|v| v.as_table()) else {
+        return;
+    };
+    let mut overrides = Vec::new();
+    for (mode, keys) in modes {
+        let Some(keys) = keys.as_table() else {
+            eprintln!("Ignoring keymap: [keymap.{}] is not a table", mode);
+            return;
+        };
+        for (key, action) in keys {
+            let Some(action) = action.as_str() else {
+                eprintln!("Ignoring keymap: the action for `{}` is not a string", key);
+                return;
+            };
+            overrides.push((mode.as_str(), key.as_str(), action));
         }
     }
+    match ui::app::Keymap::with_overrides(overrides) {
+        Ok(keymap) => app.keymap = keymap,
+        Err(e) => eprintln!("Ignoring keymap: {}", e),
+    }
+}
+
+/// Books read whole before the reader starts; `None` for the formats that
+/// stream in (PDF and EPUB).
+fn load_document(path: &Path, format: DocumentFormat) -> Option<Result<Document, String>> {
+    let loaded = match format {
+        DocumentFormat::Text | DocumentFormat::Markdown => reader_core::text::TextFile::open(path)
+            .map(|book| book.to_document())
+            .map_err(|e| e.to_string()),
+        DocumentFormat::Fb2 => reader_core::fb2::Fb2Book::open(path)
+            .map(|book| book.to_document())
+            .map_err(|e| e.to_string()),
+        DocumentFormat::Mobi => reader_core::mobi::MobiBook::open(path)
+            .map(|book| book.to_document())
+            .map_err(|e| e.to_string()),
+        DocumentFormat::Cbz => reader_core::cbz::CbzBook::open(path)
+            .map(|book| book.into_document())
+            .map_err(|e| e.to_string()),
+        DocumentFormat::Html => html_document(path).map_err(|e| e.to_string()),
+        DocumentFormat::Epub3
+        | DocumentFormat::Epub2
+        | DocumentFormat::Pdf
+        | DocumentFormat::Other => return None,
+    };
+    Some(loaded)
+}
+
+/// A standalone page as a document, cut down to its main article when
+/// `[html] article = true` is set in config.toml.
+fn html_document(path: &Path) -> Result<Document, reader_core::html::HtmlError> {
+    let mut book = reader_core::html::HtmlBook::open(path)?;
+    let article = read_config()
+        .and_then(|config| config.get("html")?.get("article")?.as_bool())
+        .unwrap_or(false);
+    if article {
+        book.extract_article();
+    }
+    Ok(book.to_document())
 }
 
 fn detect_format(path: &str) -> DocumentFormat {
-    Path::new(path)
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|ext| {
-            let lower = ext.to_ascii_lowercase();
-            match lower.as_str() {
-                "pdf" => DocumentFormat::Pdf,
-                "epub" => DocumentFormat::Epub3,
-                "txt" | "text" => DocumentFormat::Text,
-                "md" | "markdown" => DocumentFormat::Markdown,
-                _ => DocumentFormat::Other,
-            }
-        })
-        .unwrap_or(DocumentFormat::Text)
+    let path = Path::new(path);
+    // Unlike the catalog, a loose HTML page given by path opens as a book
+    if reader_core::html::is_html_path(path) {
+        return DocumentFormat::Html;
+    }
+    match reader_core::library::format_for_path(path) {
+        Some(format) => format,
+        None if path.extension().is_none() => DocumentFormat::Text,
+        None => DocumentFormat::Other,
+    }
 }
@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use reader_core::library::Catalog;
+
+/// `librarian scan [DIR...]`: add folders to the library and rescan every
+/// configured folder, only re-reading files that changed since last time.
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let mut catalog = Catalog::load();
+    for dir in args {
+        let path = Path::new(dir);
+        if !path.is_dir() {
+            return Err(format!("Not a directory: {}", dir));
+        }
+        catalog.add_root(path);
+    }
+    if catalog.roots().is_empty() {
+        return Err("Usage: librarian scan <dir>... (no library folders configured yet)".into());
+    }
+    let report = catalog.scan();
+    catalog
+        .save()
+        .map_err(|e| format!("Failed to save library: {}", e))?;
+    println!(
+        "{} books in {} folder(s): {} added, {} updated, {} removed",
+        catalog.books().len(),
+        catalog.roots().len(),
+        report.added,
+        report.updated,
+        report.removed
+    );
+    Ok(())
+}
+
+/// Resolve a command-line book argument: an existing path is used as is,
+/// anything else is looked up by title in the library.
+pub(crate) fn resolve_book(arg: &str) -> Result<String, String> {
+    if Path::new(arg).exists() {
+        return Ok(arg.to_string());
+    }
+    let catalog = Catalog::load();
+    let found = catalog.find_by_title(arg);
+    match found.as_slice() {
+        [] => Err(format!("No such file or library title: {}", arg)),
+        [book] => Ok(book.path().to_string()),
+        [first, ..] if first.display_title().eq_ignore_ascii_case(arg.trim()) => {
+            Ok(first.path().to_string())
+        }
+        many => {
+            let titles: Vec<String> = many
+                .iter()
+                .map(|book| format!("  {} ({})", book.display_title(), book.path()))
+                .collect();
+            Err(format!(
+                "Several library books match \"{}\":\n{}",
+                arg,
+                titles.join("\n")
+            ))
+        }
+    }
+}